@@ -0,0 +1,347 @@
+//! The parser as it was before the lexer was rewritten to scan bytes a
+//! word at a time, kept to measure the new one against it.
+//!
+//! Only what the benchmark needs is kept: peeking, skipping,
+//! `finish` and the documentation have been removed.
+
+use sise::{is_atom_chr, is_atom_string_chr, ParseError, ParsedItem, ParserOptions};
+
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    state: State,
+}
+
+enum State {
+    Beginning,
+    Parsing { depth: usize },
+    Finishing,
+    // Only reached when used as an iterator
+    Finished,
+}
+
+impl<'a> Parser<'a> {
+    pub fn from_bytes_with_options(data: &'a [u8], options: ParserOptions) -> Self {
+        Self {
+            lexer: Lexer::new(data, options),
+            state: State::Beginning,
+        }
+    }
+
+    fn next_item(&mut self) -> Result<ParsedItem<'a>, ParseError> {
+        match self.state {
+            State::Beginning => {
+                let (pos, token) = self.get_token()?;
+                match token {
+                    Token::Eof => Err(ParseError::UnexpectedEof { pos }),
+                    Token::LeftParen => {
+                        self.state = State::Parsing { depth: 0 };
+                        Ok(ParsedItem::ListStart(pos))
+                    }
+                    Token::RightParen => Err(ParseError::UnexpectedRightParen { pos }),
+                    Token::Atom(atom) => {
+                        self.state = State::Finishing;
+                        Ok(ParsedItem::Atom(atom, pos))
+                    }
+                    Token::DatumComment => unreachable!(),
+                }
+            }
+            State::Parsing { depth } => {
+                let (pos, token) = self.get_token()?;
+                match token {
+                    Token::Eof => Err(ParseError::UnexpectedEof { pos }),
+                    Token::LeftParen => {
+                        self.state = State::Parsing { depth: depth + 1 };
+                        Ok(ParsedItem::ListStart(pos))
+                    }
+                    Token::RightParen => {
+                        if depth == 0 {
+                            self.state = State::Finishing;
+                        } else {
+                            self.state = State::Parsing { depth: depth - 1 };
+                        }
+                        Ok(ParsedItem::ListEnd(pos))
+                    }
+                    Token::Atom(atom) => Ok(ParsedItem::Atom(atom, pos)),
+                    Token::DatumComment => unreachable!(),
+                }
+            }
+            State::Finishing | State::Finished => panic!("parsing finished"),
+        }
+    }
+
+    fn get_token(&mut self) -> Result<(usize, Token<'a>), ParseError> {
+        // Each item holds a nesting depth (relative to the first `#;`)
+        // and the number of nodes that still have to be skipped at
+        // that depth.
+        let mut pending_skips: Vec<(usize, usize)> = Vec::new();
+        let mut depth = 0;
+        loop {
+            let (pos, token) = self.lexer.get_token()?;
+            if token == Token::DatumComment {
+                match pending_skips.last_mut() {
+                    Some(last) if last.0 == depth => last.1 += 1,
+                    _ => pending_skips.push((depth, 1)),
+                }
+                continue;
+            }
+
+            let skip_depth = match pending_skips.last() {
+                None => return Ok((pos, token)),
+                Some(&(skip_depth, _)) => skip_depth,
+            };
+
+            let node_ended = match token {
+                Token::Eof => return Err(ParseError::UnexpectedEof { pos }),
+                Token::LeftParen => {
+                    depth += 1;
+                    false
+                }
+                Token::RightParen => {
+                    if skip_depth == depth {
+                        // `#;` not followed by a node
+                        return Err(ParseError::UnexpectedRightParen { pos });
+                    }
+                    depth -= 1;
+                    true
+                }
+                Token::Atom(_) => true,
+                Token::DatumComment => unreachable!(),
+            };
+
+            if node_ended && skip_depth == depth {
+                let last = pending_skips.last_mut().unwrap();
+                last.1 -= 1;
+                if last.1 == 0 {
+                    pending_skips.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<ParsedItem<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match self.state {
+            State::Finishing => {
+                self.state = State::Finished;
+                return match self.get_token() {
+                    Ok((_, Token::Eof)) => None,
+                    Ok((pos, _)) => Some(Err(ParseError::ExpectedEof { pos })),
+                    Err(e) => Some(Err(e)),
+                };
+            }
+            State::Finished => return None,
+            _ => self.next_item(),
+        };
+        if result.is_err() {
+            self.state = State::Finished;
+        }
+        Some(result)
+    }
+}
+
+impl core::iter::FusedIterator for Parser<'_> {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Eof,
+    LeftParen,
+    RightParen,
+    Atom(&'a str),
+    DatumComment,
+}
+
+struct Lexer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    options: ParserOptions,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a [u8], options: ParserOptions) -> Self {
+        Lexer {
+            input,
+            pos: 0,
+            options,
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    fn eat_any_byte(&mut self) -> Option<u8> {
+        let byte = *self.input.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    #[must_use]
+    #[inline]
+    fn eat_byte(&mut self, byte: u8) -> bool {
+        if self.input.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    fn eat_bytes(&mut self, bytes: &[u8]) -> bool {
+        if self.input[self.pos..].starts_with(bytes) {
+            self.pos += bytes.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    fn eat_byte_if(&mut self, pred: impl FnOnce(u8) -> bool) -> bool {
+        match self.input.get(self.pos) {
+            Some(&byte) if pred(byte) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[cold]
+    fn illegal_chr(&self, pos: usize, make_error: fn(usize, char) -> ParseError) -> ParseError {
+        let bytes = &self.input[pos..self.input.len().min(pos + 4)];
+        let valid = match core::str::from_utf8(bytes) {
+            Ok(valid) => valid,
+            Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap(),
+        };
+        match valid.chars().next() {
+            Some(chr) => make_error(pos, chr),
+            None => ParseError::InvalidUtf8 { pos },
+        }
+    }
+
+    fn get_token(&mut self) -> Result<(usize, Token<'a>), ParseError> {
+        loop {
+            let chr_pos = self.pos;
+            if self.eat_byte(b' ')
+                || self.eat_byte(b'\t')
+                || self.eat_byte(b'\n')
+                || self.eat_byte(b'\r')
+            {
+                // skip whitespace
+            } else if self.eat_byte(b';') {
+                // skip comments
+                loop {
+                    let chr_pos = self.pos;
+                    match self.eat_any_byte() {
+                        None => return Ok((self.pos, Token::Eof)),
+                        Some(b'\n' | b'\r') => break,
+                        Some(b'\t' | b' '..=b'~') => {}
+                        Some(_) => {
+                            return Err(self.illegal_chr(chr_pos, |pos, chr| {
+                                ParseError::IllegalChrInComment { pos, chr }
+                            }));
+                        }
+                    }
+                }
+            } else if self.options.block_comments && self.eat_bytes(b"#|") {
+                self.skip_block_comment()?;
+            } else if self.options.datum_comments && self.eat_bytes(b"#;") {
+                return Ok((chr_pos, Token::DatumComment));
+            } else if self.eat_byte(b'(') {
+                return Ok((chr_pos, Token::LeftParen));
+            } else if self.eat_byte(b')') {
+                return Ok((chr_pos, Token::RightParen));
+            } else if let Some(byte) = self.eat_any_byte() {
+                if is_atom_byte(byte) || byte == b'"' {
+                    let begin_pos = chr_pos;
+                    let end_pos = self.lex_atom(byte)?;
+                    // Atoms are made only of ASCII characters
+                    let atom = core::str::from_utf8(&self.input[begin_pos..end_pos]).unwrap();
+                    return Ok((begin_pos, Token::Atom(atom)));
+                } else {
+                    // invalid character
+                    return Err(
+                        self.illegal_chr(chr_pos, |pos, chr| ParseError::IllegalChr { pos, chr })
+                    );
+                }
+            } else {
+                // end-of-file
+                return Ok((self.pos, Token::Eof));
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), ParseError> {
+        let mut depth = 1;
+        loop {
+            let chr_pos = self.pos;
+            if self.eat_bytes(b"|#") {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if self.eat_bytes(b"#|") {
+                depth += 1;
+            } else {
+                match self.eat_any_byte() {
+                    None => return Err(ParseError::UnfinishedBlockComment { pos: chr_pos }),
+                    Some(b'\t' | b'\n' | b'\r' | b' '..=b'~') => {}
+                    Some(_) => {
+                        return Err(self.illegal_chr(chr_pos, |pos, chr| {
+                            ParseError::IllegalChrInComment { pos, chr }
+                        }));
+                    }
+                }
+            }
+        }
+    }
+
+    fn lex_atom(&mut self, first_byte: u8) -> Result<usize, ParseError> {
+        let mut in_string = first_byte == b'"';
+        loop {
+            let chr_pos = self.pos;
+            if in_string {
+                if self.eat_byte(b'"') {
+                    in_string = false;
+                } else if self.eat_byte(b'\\') {
+                    let chr_pos = self.pos;
+                    if let Some(byte) = self.eat_any_byte() {
+                        if byte != b'"' && byte != b'\\' && !is_atom_string_byte(byte) {
+                            return Err(self.illegal_chr(chr_pos, |pos, chr| {
+                                ParseError::IllegalChrInString { pos, chr }
+                            }));
+                        }
+                    } else {
+                        return Err(ParseError::UnfinishedString { pos: chr_pos });
+                    }
+                } else if let Some(byte) = self.eat_any_byte() {
+                    if !is_atom_string_byte(byte) {
+                        return Err(self.illegal_chr(chr_pos, |pos, chr| {
+                            ParseError::IllegalChrInString { pos, chr }
+                        }));
+                    }
+                } else {
+                    return Err(ParseError::UnfinishedString { pos: chr_pos });
+                }
+            } else if self.eat_byte(b'"') {
+                in_string = true;
+            } else if !self.eat_byte_if(is_atom_byte) {
+                return Ok(chr_pos);
+            }
+        }
+    }
+}
+
+#[inline]
+fn is_atom_byte(byte: u8) -> bool {
+    is_atom_chr(char::from(byte))
+}
+
+#[inline]
+fn is_atom_string_byte(byte: u8) -> bool {
+    is_atom_string_chr(char::from(byte))
+}
@@ -0,0 +1,114 @@
+//! Measures the throughput of `Parser` with some synthetic inputs,
+//! next to that of the parser before the lexer rewrite (see
+//! `baseline`).
+//!
+//! Run with `cargo bench --bench parser`.
+
+use std::time::{Duration, Instant};
+
+mod baseline;
+
+fn gen_atoms(n: usize) -> String {
+    let mut s = String::from("(");
+    for i in 0..n {
+        s.push_str(" atom-");
+        s.push_str(&i.to_string());
+    }
+    s.push(')');
+    s
+}
+
+fn gen_strings(n: usize) -> String {
+    let mut s = String::from("(");
+    for i in 0..n {
+        s.push_str(" \"a long string with spaces and \\\"escapes\\\" number ");
+        s.push_str(&i.to_string());
+        s.push('"');
+    }
+    s.push(')');
+    s
+}
+
+fn gen_indented(n: usize) -> String {
+    let mut s = String::from("(\n");
+    for i in 0..n {
+        s.push_str("        ; a comment line that explains the next entry\n");
+        s.push_str("        (entry ");
+        s.push_str(&i.to_string());
+        s.push_str("\n                (key value)\n                (other \"string\"))\n");
+    }
+    s.push(')');
+    s
+}
+
+fn parse_all<'a, I>(parser: I) -> usize
+where
+    I: Iterator<Item = Result<sise::ParsedItem<'a>, sise::ParseError>>,
+{
+    let mut num_items = 0;
+    for item in parser {
+        item.unwrap();
+        num_items += 1;
+    }
+    num_items
+}
+
+/// Returns the throughput of `parse` in MiB/s over about `duration`.
+fn measure(data: &str, duration: Duration, parse: &dyn Fn() -> usize) -> f64 {
+    // Warm up
+    let num_items = parse();
+
+    let mut iterations = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        assert_eq!(parse(), num_items);
+        iterations += 1;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let mib = (data.len() as f64) * f64::from(iterations) / (1024.0 * 1024.0);
+    mib / elapsed
+}
+
+/// Measures both parsers in alternating rounds and keeps the best
+/// round of each, so a slow period of the machine does not affect
+/// only one of them.
+fn bench(name: &str, data: &str, options: sise::ParserOptions) {
+    const ROUNDS: usize = 7;
+    const ROUND_DURATION: Duration = Duration::from_millis(300);
+
+    let parse_before = || {
+        parse_all(baseline::Parser::from_bytes_with_options(
+            data.as_bytes(),
+            options,
+        ))
+    };
+    let parse_after = || parse_all(sise::Parser::with_options(data, options));
+
+    let mut before = 0.0f64;
+    let mut after = 0.0f64;
+    for _ in 0..ROUNDS {
+        before = before.max(measure(data, ROUND_DURATION, &parse_before));
+        after = after.max(measure(data, ROUND_DURATION, &parse_after));
+    }
+    println!(
+        "{:<10} {:>10.1} MiB/s -> {:>10.1} MiB/s ({:+.0}%)",
+        name,
+        before,
+        after,
+        (after / before - 1.0) * 100.0,
+    );
+}
+
+fn main() {
+    let default = sise::ParserOptions::default();
+    let block_comments = sise::ParserOptions {
+        block_comments: true,
+        ..sise::ParserOptions::default()
+    };
+
+    bench("atoms", &gen_atoms(200_000), default);
+    bench("strings", &gen_strings(50_000), default);
+    bench("indented", &gen_indented(20_000), default);
+    bench("indented#|", &gen_indented(20_000), block_comments);
+}
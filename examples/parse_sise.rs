@@ -16,10 +16,13 @@ fn main() {
     }
 
     let file_data = std::fs::read(&args[1]).unwrap();
-    let file_data = String::from_utf8(file_data).unwrap();
-    let mut parser = sise::Parser::new(&file_data);
-    let parsed = sise::parse_tree(&mut parser).unwrap();
-    parser.finish().unwrap();
+    let mut parser = sise::Parser::from_bytes(&file_data);
+    let parsed = sise::parse_tree(&mut parser)
+        .and_then(|parsed| parser.finish().map(|()| parsed))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to parse {:?}: {}.", args[1], e);
+            std::process::exit(1);
+        });
 
     println!("{:#?}", parsed);
 }
@@ -33,10 +33,13 @@ fn main() {
     };
 
     let file_data = std::fs::read(&args[2]).unwrap();
-    let file_data = String::from_utf8(file_data).unwrap();
-    let mut parser = sise::Parser::new(&file_data);
-    let parsed = sise::parse_tree(&mut parser).unwrap();
-    parser.finish().unwrap();
+    let mut parser = sise::Parser::from_bytes(&file_data);
+    let parsed = sise::parse_tree(&mut parser)
+        .and_then(|parsed| parser.finish().map(|()| parsed))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to parse {:?}: {}.", args[2], e);
+            std::process::exit(1);
+        });
 
     let break_line_at = match serialize_style {
         SerializeStyle::Compact => usize::MAX,
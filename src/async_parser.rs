@@ -0,0 +1,190 @@
+use alloc::vec::Vec;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_io::AsyncRead;
+
+use crate::incremental::{IncrementalParser, Step};
+use crate::{OwnedParsedItem, ParseError, ParserOptions, TreeNode};
+
+/// Error returned by [`AsyncParser`] and [`AsyncTreeParser`].
+#[derive(Debug)]
+pub enum AsyncParseError {
+    /// Reading from the underlying reader failed
+    Io(std::io::Error),
+    /// The input is not valid SISE
+    Parse(ParseError),
+}
+
+impl core::fmt::Display for AsyncParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            AsyncParseError::Io(ref e) => write!(f, "read error: {}", e),
+            AsyncParseError::Parse(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AsyncParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            AsyncParseError::Io(ref e) => Some(e),
+            AsyncParseError::Parse(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for AsyncParseError {
+    #[inline]
+    fn from(e: std::io::Error) -> Self {
+        AsyncParseError::Io(e)
+    }
+}
+
+impl From<ParseError> for AsyncParseError {
+    #[inline]
+    fn from(e: ParseError) -> Self {
+        AsyncParseError::Parse(e)
+    }
+}
+
+/// Parser that reads from an [`AsyncRead`] and yields the items of
+/// the nodes it finds as a [`Stream`].
+///
+/// The input is a sequence of top-level nodes (e.g., messages received
+/// from a socket), and the stream ends when the reader reaches
+/// end-of-file between two nodes. It uses the same lexing rules and
+/// reports the same errors (with byte offsets from the beginning of the
+/// input) as [`Parser`](crate::Parser). The stream ends after the first
+/// error.
+///
+/// It does not depend on any particular runtime. Only the data that
+/// has not been parsed yet is kept in memory.
+///
+/// # Example
+///
+/// ```
+/// use futures::StreamExt as _;
+/// use sise::OwnedParsedItem;
+///
+/// let reader: &[u8] = b"(a b) c";
+/// let parser = sise::AsyncParser::new(reader);
+/// let items: Vec<_> = futures::executor::block_on(parser.map(Result::unwrap).collect());
+/// assert_eq!(
+///     items,
+///     [
+///         OwnedParsedItem::ListStart(0),
+///         OwnedParsedItem::Atom(String::from("a"), 1),
+///         OwnedParsedItem::Atom(String::from("b"), 3),
+///         OwnedParsedItem::ListEnd(4),
+///         OwnedParsedItem::Atom(String::from("c"), 6),
+///     ],
+/// );
+/// ```
+pub struct AsyncParser<R> {
+    reader: R,
+    inner: IncrementalParser,
+}
+
+impl<R: AsyncRead + Unpin> AsyncParser<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader,
+            inner: IncrementalParser::new(options),
+        }
+    }
+
+    /// Turns the parser into a stream of whole nodes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures::StreamExt as _;
+    /// use sise::sise_tree;
+    ///
+    /// let reader: &[u8] = b"(a (b)) c";
+    /// let parser = sise::AsyncParser::new(reader).into_trees();
+    /// let nodes: Vec<_> = futures::executor::block_on(parser.map(Result::unwrap).collect());
+    /// assert_eq!(nodes, [sise_tree!(["a", ["b"]]), sise_tree!("c")]);
+    /// ```
+    pub fn into_trees(self) -> AsyncTreeParser<R> {
+        AsyncTreeParser {
+            parser: self,
+            stack: Vec::new(),
+        }
+    }
+
+    fn poll_next_item(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<OwnedParsedItem, AsyncParseError>>> {
+        loop {
+            match self.inner.next_step() {
+                Ok(Step::Item(item)) => return Poll::Ready(Some(Ok(item))),
+                Ok(Step::NeedInput) => {
+                    let reader = &mut self.reader;
+                    let result = futures_core::ready!(self
+                        .inner
+                        .read_with(|buf| Pin::new(&mut *reader).poll_read(cx, buf)));
+                    if let Err(e) = result {
+                        self.inner.abort();
+                        return Poll::Ready(Some(Err(AsyncParseError::Io(e))));
+                    }
+                }
+                Ok(Step::End) => return Poll::Ready(None),
+                Err(e) => return Poll::Ready(Some(Err(AsyncParseError::Parse(e)))),
+            }
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncParser<R> {
+    type Item = Result<OwnedParsedItem, AsyncParseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_item(cx)
+    }
+}
+
+/// Stream of the top-level nodes read by an [`AsyncParser`].
+///
+/// Created with [`AsyncParser::into_trees`]. The stream ends after the
+/// first error.
+pub struct AsyncTreeParser<R> {
+    parser: AsyncParser<R>,
+    // Lists that are being built
+    stack: Vec<Vec<TreeNode>>,
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncTreeParser<R> {
+    type Item = Result<TreeNode, AsyncParseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let node = match futures_core::ready!(this.parser.poll_next_item(cx)) {
+                None => return Poll::Ready(None),
+                Some(Err(e)) => {
+                    this.stack.clear();
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Some(Ok(OwnedParsedItem::Atom(atom, _))) => TreeNode::Atom(atom),
+                Some(Ok(OwnedParsedItem::ListStart(_))) => {
+                    this.stack.push(Vec::new());
+                    continue;
+                }
+                Some(Ok(OwnedParsedItem::ListEnd(_))) => TreeNode::List(this.stack.pop().unwrap()),
+            };
+            if let Some(parent) = this.stack.last_mut() {
+                parent.push(node);
+            } else {
+                return Poll::Ready(Some(Ok(node)));
+            }
+        }
+    }
+}
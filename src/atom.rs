@@ -0,0 +1,206 @@
+use alloc::string::String;
+
+use crate::{validate_atom, AtomError, TreeNode};
+
+/// A borrowed string that is guaranteed to be a valid atom.
+///
+/// # Example
+///
+/// ```
+/// use sise::{AtomError, AtomStr};
+///
+/// let atom = AtomStr::new("\"a b\"").unwrap();
+/// assert_eq!(atom.as_str(), "\"a b\"");
+/// assert_eq!(
+///     AtomStr::new("a b"),
+///     Err(AtomError::IllegalChr { pos: 1, chr: ' ' }),
+/// );
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AtomStr<'a> {
+    atom: &'a str,
+}
+
+impl<'a> AtomStr<'a> {
+    /// Checks that `atom` is a valid atom.
+    #[inline]
+    pub fn new(atom: &'a str) -> Result<Self, AtomError> {
+        validate_atom(atom)?;
+        Ok(Self { atom })
+    }
+
+    #[inline]
+    pub fn as_str(self) -> &'a str {
+        self.atom
+    }
+
+    /// Copies the atom into an owned [`Atom`].
+    #[inline]
+    pub fn to_atom(self) -> Atom {
+        Atom {
+            atom: String::from(self.atom),
+        }
+    }
+}
+
+impl core::ops::Deref for AtomStr<'_> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.atom
+    }
+}
+
+impl AsRef<str> for AtomStr<'_> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.atom
+    }
+}
+
+impl core::fmt::Display for AtomStr<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.atom)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for AtomStr<'a> {
+    type Error = AtomError;
+
+    #[inline]
+    fn try_from(atom: &'a str) -> Result<Self, AtomError> {
+        Self::new(atom)
+    }
+}
+
+/// An owned string that is guaranteed to be a valid atom.
+///
+/// # Example
+///
+/// ```
+/// use sise::{Atom, AtomError};
+///
+/// let atom = Atom::new(String::from("port")).unwrap();
+/// assert_eq!(atom, "port");
+/// assert_eq!(sise::TreeNode::from(atom), "port");
+/// assert_eq!(
+///     Atom::new(String::from("\"abc")),
+///     Err(AtomError::UnfinishedString { pos: 0 }),
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Atom {
+    atom: String,
+}
+
+impl Atom {
+    /// Checks that `atom` is a valid atom.
+    #[inline]
+    pub fn new(atom: String) -> Result<Self, AtomError> {
+        validate_atom(&atom)?;
+        Ok(Self { atom })
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.atom
+    }
+
+    #[inline]
+    pub fn as_atom_str(&self) -> AtomStr<'_> {
+        AtomStr { atom: &self.atom }
+    }
+
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.atom
+    }
+}
+
+impl core::ops::Deref for Atom {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        &self.atom
+    }
+}
+
+impl AsRef<str> for Atom {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.atom
+    }
+}
+
+impl core::borrow::Borrow<str> for Atom {
+    #[inline]
+    fn borrow(&self) -> &str {
+        &self.atom
+    }
+}
+
+impl core::fmt::Display for Atom {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.atom)
+    }
+}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        self.atom == other
+    }
+}
+
+impl PartialEq<&str> for Atom {
+    fn eq(&self, other: &&str) -> bool {
+        self.atom == *other
+    }
+}
+
+impl TryFrom<String> for Atom {
+    type Error = AtomError;
+
+    #[inline]
+    fn try_from(atom: String) -> Result<Self, AtomError> {
+        Self::new(atom)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Atom {
+    type Error = AtomError;
+
+    #[inline]
+    fn try_from(atom: &'a str) -> Result<Self, AtomError> {
+        AtomStr::new(atom).map(AtomStr::to_atom)
+    }
+}
+
+impl From<AtomStr<'_>> for Atom {
+    #[inline]
+    fn from(atom: AtomStr<'_>) -> Self {
+        atom.to_atom()
+    }
+}
+
+impl From<Atom> for String {
+    #[inline]
+    fn from(atom: Atom) -> Self {
+        atom.atom
+    }
+}
+
+impl From<Atom> for TreeNode {
+    #[inline]
+    fn from(atom: Atom) -> Self {
+        Self::Atom(atom.atom)
+    }
+}
+
+impl From<AtomStr<'_>> for TreeNode {
+    #[inline]
+    fn from(atom: AtomStr<'_>) -> Self {
+        Self::Atom(String::from(atom.atom))
+    }
+}
@@ -0,0 +1,128 @@
+use alloc::string::String;
+
+use crate::{is_atom_string_chr, validate_atom};
+
+/// Encodes an arbitrary string as a quoted atom.
+///
+/// `"` and `\` are escaped with `\`, tabs and line breaks are encoded
+/// as `\t`, `\n` and `\r`, and any other character that is not allowed
+/// in an atom is encoded as `\u{HEX}`, where `HEX` is its code point in
+/// hexadecimal. The result can be decoded back with [`decode_string`].
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(sise::encode_string("a \"b\"\n"), r#""a \"b\"\n""#);
+/// assert_eq!(sise::encode_string("\u{e9}"), r#""\u{e9}""#);
+/// assert!(sise::check_atom(&sise::encode_string("\0\\")));
+/// ```
+pub fn encode_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for chr in s.chars() {
+        match chr {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            chr if is_atom_string_chr(chr) => result.push(chr),
+            chr => {
+                use core::fmt::Write as _;
+                write!(result, "\\u{{{:x}}}", u32::from(chr)).unwrap();
+            }
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Suggests an atom for arbitrary text, such as a value typed by a
+/// user.
+///
+/// If `text` is already a valid atom, it is returned unchanged.
+/// Otherwise, it is encoded as a quoted atom with [`encode_string`],
+/// which can be decoded back with [`decode_string`].
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(sise::suggest_atom("port"), "port");
+/// assert_eq!(sise::suggest_atom("\"a b\""), "\"a b\"");
+/// assert_eq!(sise::suggest_atom("a b"), "\"a b\"");
+/// assert_eq!(sise::suggest_atom("say \"hi\""), r#""say \"hi\"""#);
+/// assert_eq!(sise::suggest_atom(""), "\"\"");
+/// ```
+pub fn suggest_atom(text: &str) -> String {
+    if validate_atom(text).is_ok() {
+        String::from(text)
+    } else {
+        encode_string(text)
+    }
+}
+
+/// A string that is converted into a [`TreeNode`](crate::TreeNode) as
+/// a quoted atom, encoded with [`encode_string`].
+///
+/// # Example
+///
+/// ```
+/// use sise::{Quoted, TreeNode};
+///
+/// assert_eq!(TreeNode::from(Quoted("a \"b\"")), r#""a \"b\"""#);
+/// assert_eq!(TreeNode::from(Quoted(String::from("c"))), r#""c""#);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Quoted<S>(pub S);
+
+/// Decodes an atom produced by [`encode_string`].
+///
+/// Returns `None` if `atom` is not a single quoted string or it
+/// contains an unknown escape sequence.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(
+///     sise::decode_string(r#""a \"b\"\n""#).as_deref(),
+///     Some("a \"b\"\n"),
+/// );
+/// assert_eq!(sise::decode_string(r#""\u{e9}""#).as_deref(), Some("\u{e9}"));
+/// assert_eq!(sise::decode_string("bare"), None);
+/// assert_eq!(sise::decode_string(r#""a"b"#), None);
+/// assert_eq!(sise::decode_string(r#""\x""#), None);
+/// ```
+pub fn decode_string(atom: &str) -> Option<String> {
+    let inner = atom.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '\\' => match chars.next()? {
+                '"' => result.push('"'),
+                '\\' => result.push('\\'),
+                't' => result.push('\t'),
+                'n' => result.push('\n'),
+                'r' => result.push('\r'),
+                'u' => {
+                    let rem = chars.as_str().strip_prefix('{')?;
+                    let end = rem.find('}')?;
+                    let hex = &rem[..end];
+                    if hex.is_empty()
+                        || hex.len() > 6
+                        || !hex.bytes().all(|b| b.is_ascii_hexdigit())
+                    {
+                        return None;
+                    }
+                    result.push(char::from_u32(u32::from_str_radix(hex, 16).ok()?)?);
+                    chars = rem[(end + 1)..].chars();
+                }
+                _ => return None,
+            },
+            '"' => return None,
+            chr if is_atom_string_chr(chr) => result.push(chr),
+            _ => return None,
+        }
+    }
+    Some(result)
+}
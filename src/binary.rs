@@ -0,0 +1,322 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::{check_atom, ItemReader, ParsedItem, TreeNode};
+
+/// Bytes at the beginning of the binary format, including its version.
+const MAGIC: &[u8; 5] = b"SISB\x01";
+
+// The body is a sequence of varint tags:
+// * `TAG_LIST_START` and `TAG_LIST_END` are `(` and `)`.
+// * An even tag `2 + 2 * len` is an atom of `len` bytes that follow it.
+// * An odd tag `3 + 2 * index` is an atom from the deduplication table.
+const TAG_LIST_START: u64 = 0;
+const TAG_LIST_END: u64 = 1;
+
+/// Options for [`to_binary`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BinaryOptions {
+    /// Writes atoms that appear more than once only once, in a table at
+    /// the beginning of the output, and refers to them by index.
+    pub dedup_atoms: bool,
+}
+
+/// Represents an error found when reading the binary format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BinaryError {
+    /// The data does not begin with the expected magic bytes and version
+    InvalidHeader,
+
+    /// A variable-length integer is longer than 64 bits
+    InvalidVarint { pos: usize },
+
+    /// An atom refers to an index that is not in the deduplication table
+    InvalidAtomIndex { pos: usize },
+
+    /// The bytes of an atom are not a valid atom
+    InvalidAtom { pos: usize },
+
+    /// There is a list end outside any list
+    UnexpectedListEnd { pos: usize },
+
+    /// Unexpected end-of-file
+    UnexpectedEof { pos: usize },
+
+    /// Found data after the root node
+    ExpectedEof { pos: usize },
+}
+
+impl core::fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            BinaryError::InvalidHeader => f.write_str("invalid binary header"),
+            BinaryError::InvalidVarint { pos } => write!(f, "invalid varint at byte {}", pos),
+            BinaryError::InvalidAtomIndex { pos } => {
+                write!(f, "invalid atom index at byte {}", pos)
+            }
+            BinaryError::InvalidAtom { pos } => write!(f, "invalid atom at byte {}", pos),
+            BinaryError::UnexpectedListEnd { pos } => {
+                write!(f, "unexpected list end at byte {}", pos)
+            }
+            BinaryError::UnexpectedEof { pos } => {
+                write!(f, "unexpected end-of-file at byte {}", pos)
+            }
+            BinaryError::ExpectedEof { pos } => write!(f, "expected end-of-file at byte {}", pos),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BinaryError {}
+
+/// Serializes a tree into a compact binary format, which can be read
+/// back with [`BinaryReader`].
+///
+/// # Panics
+///
+/// Panics if the tree contains an invalid atom.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree = sise_tree!(["server", ["port", "8080"], ["port", "8081"]]);
+///
+/// let plain = sise::to_binary(&tree, sise::BinaryOptions::default());
+/// let dedup = sise::to_binary(&tree, sise::BinaryOptions { dedup_atoms: true });
+/// assert!(dedup.len() < plain.len());
+///
+/// for data in [plain, dedup].iter() {
+///     let mut reader = sise::BinaryReader::new(data).unwrap();
+///     assert_eq!(sise::parse_tree(&mut reader), Ok(tree.clone()));
+///     reader.finish().unwrap();
+/// }
+/// ```
+pub fn to_binary(node: &TreeNode, options: BinaryOptions) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+
+    let mut table = BTreeMap::new();
+    if options.dedup_atoms {
+        let mut counts = BTreeMap::new();
+        count_atoms(node, &mut counts);
+        // Assign indices in order of first appearance
+        let mut repeated = Vec::new();
+        collect_repeated(node, &counts, &mut table, &mut repeated);
+        write_varint(repeated.len() as u64, &mut out);
+        for atom in repeated {
+            write_varint(atom.len() as u64, &mut out);
+            out.extend_from_slice(atom.as_bytes());
+        }
+    } else {
+        write_varint(0, &mut out);
+    }
+
+    write_node(node, &table, &mut out);
+    out
+}
+
+fn count_atoms<'a>(node: &'a TreeNode, counts: &mut BTreeMap<&'a str, usize>) {
+    match *node {
+        TreeNode::Atom(ref atom) => *counts.entry(atom.as_str()).or_insert(0) += 1,
+        TreeNode::List(ref list) => {
+            for item in list.iter() {
+                count_atoms(item, counts);
+            }
+        }
+    }
+}
+
+fn collect_repeated<'a>(
+    node: &'a TreeNode,
+    counts: &BTreeMap<&'a str, usize>,
+    table: &mut BTreeMap<&'a str, usize>,
+    repeated: &mut Vec<&'a str>,
+) {
+    match *node {
+        TreeNode::Atom(ref atom) => {
+            if counts[atom.as_str()] > 1 && !table.contains_key(atom.as_str()) {
+                table.insert(atom.as_str(), repeated.len());
+                repeated.push(atom.as_str());
+            }
+        }
+        TreeNode::List(ref list) => {
+            for item in list.iter() {
+                collect_repeated(item, counts, table, repeated);
+            }
+        }
+    }
+}
+
+fn write_node(node: &TreeNode, table: &BTreeMap<&str, usize>, out: &mut Vec<u8>) {
+    match *node {
+        TreeNode::Atom(ref atom) => {
+            assert!(check_atom(atom), "invalid atom {:?}", atom);
+            if let Some(&index) = table.get(atom.as_str()) {
+                write_varint(3 + 2 * index as u64, out);
+            } else {
+                write_varint(2 + 2 * atom.len() as u64, out);
+                out.extend_from_slice(atom.as_bytes());
+            }
+        }
+        TreeNode::List(ref list) => {
+            write_varint(TAG_LIST_START, out);
+            for item in list.iter() {
+                write_node(item, table, out);
+            }
+            write_varint(TAG_LIST_END, out);
+        }
+    }
+}
+
+/// Writes `value` in LEB128 (7 bits per byte, least significant first).
+fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Reads data written by [`to_binary`], producing the same items as a
+/// [`Parser`](crate::Parser) would produce for the same tree in text form.
+///
+/// The position of each item is the byte offset of its tag in the
+/// binary data.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let data = sise::to_binary(&sise_tree!(["a", "b"]), sise::BinaryOptions::default());
+/// let mut reader = sise::BinaryReader::new(&data).unwrap();
+/// assert_eq!(reader.next_item().unwrap(), sise::ParsedItem::ListStart(6));
+/// assert_eq!(reader.next_item().unwrap(), sise::ParsedItem::Atom("a", 7));
+/// assert_eq!(reader.next_item().unwrap(), sise::ParsedItem::Atom("b", 9));
+/// assert_eq!(reader.next_item().unwrap(), sise::ParsedItem::ListEnd(11));
+/// reader.finish().unwrap();
+/// ```
+pub struct BinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    table: Vec<&'a str>,
+    // `None` once the root node has been read
+    depth: Option<usize>,
+}
+
+impl<'a> BinaryReader<'a> {
+    /// Creates a reader, reading the header and the deduplication
+    /// table from `data`.
+    pub fn new(data: &'a [u8]) -> Result<Self, BinaryError> {
+        if !data.starts_with(MAGIC) {
+            return Err(BinaryError::InvalidHeader);
+        }
+        let mut reader = Self {
+            data,
+            pos: MAGIC.len(),
+            table: Vec::new(),
+            depth: Some(0),
+        };
+
+        let table_len = reader.read_varint()?;
+        for _ in 0..table_len {
+            let pos = reader.pos;
+            let len = reader.read_varint()?;
+            let atom = reader.read_atom(len, pos)?;
+            reader.table.push(atom);
+        }
+        Ok(reader)
+    }
+
+    pub fn next_item(&mut self) -> Result<ParsedItem<'a>, BinaryError> {
+        let depth = self.depth.expect("parsing finished");
+        let pos = self.pos;
+        let (item, new_depth) = match self.read_varint()? {
+            TAG_LIST_START => (ParsedItem::ListStart(pos), Some(depth + 1)),
+            TAG_LIST_END => {
+                if depth == 0 {
+                    return Err(BinaryError::UnexpectedListEnd { pos });
+                }
+                let new_depth = if depth == 1 { None } else { Some(depth - 1) };
+                (ParsedItem::ListEnd(pos), new_depth)
+            }
+            tag => {
+                let atom = if tag % 2 == 0 {
+                    self.read_atom((tag - 2) / 2, pos)?
+                } else {
+                    usize::try_from((tag - 3) / 2)
+                        .ok()
+                        .and_then(|index| self.table.get(index).copied())
+                        .ok_or(BinaryError::InvalidAtomIndex { pos })?
+                };
+                let new_depth = if depth == 0 { None } else { Some(depth) };
+                (ParsedItem::Atom(atom, pos), new_depth)
+            }
+        };
+        self.depth = new_depth;
+        Ok(item)
+    }
+
+    /// Checks that there is no data after the root node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the root node has not been read completely.
+    pub fn finish(self) -> Result<(), BinaryError> {
+        assert!(self.depth.is_none(), "parsing not finished yet");
+        if self.pos == self.data.len() {
+            Ok(())
+        } else {
+            Err(BinaryError::ExpectedEof { pos: self.pos })
+        }
+    }
+
+    fn read_varint(&mut self) -> Result<u64, BinaryError> {
+        let start = self.pos;
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or(BinaryError::UnexpectedEof { pos: self.pos })?;
+            self.pos += 1;
+            if shift == 63 && byte > 1 {
+                return Err(BinaryError::InvalidVarint { pos: start });
+            }
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Reads an atom of `len` bytes. `pos` is the offset of its tag.
+    fn read_atom(&mut self, len: u64, pos: usize) -> Result<&'a str, BinaryError> {
+        let bytes = usize::try_from(len)
+            .ok()
+            .and_then(|len| self.data[self.pos..].get(..len))
+            .ok_or(BinaryError::UnexpectedEof {
+                pos: self.data.len(),
+            })?;
+        match core::str::from_utf8(bytes) {
+            Ok(atom) if check_atom(atom) => {
+                self.pos += bytes.len();
+                Ok(atom)
+            }
+            _ => Err(BinaryError::InvalidAtom { pos }),
+        }
+    }
+}
+
+impl<'a> ItemReader<'a> for BinaryReader<'a> {
+    type Error = BinaryError;
+
+    #[inline]
+    fn next_item(&mut self) -> Result<ParsedItem<'a>, BinaryError> {
+        BinaryReader::next_item(self)
+    }
+}
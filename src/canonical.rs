@@ -0,0 +1,181 @@
+use alloc::string::String;
+
+use crate::{check_atom, ParseError, ParsedItem, Parser, ParserOptions, TreeNode};
+
+/// Converts SISE text into its canonical form.
+///
+/// The canonical form is a single line without comments, where each
+/// pair of adjacent atoms or lists is separated by exactly one space,
+/// there is no space after `(` or before `)`, and there is no line
+/// break at the end. Atoms are kept as they are. Two inputs that
+/// represent the same tree have the same canonical form, and
+/// canonicalizing a canonical text returns it unchanged.
+///
+/// This definition is part of the stable interface of the crate, so
+/// canonical texts (and [`hash_tree`] values) can be stored.
+///
+/// # Example
+///
+/// ```
+/// let data = "; config\n(server\n  (port  8080) ; main\n  (host \"a b\")\n)\n";
+/// let canonical = sise::canonicalize(data).unwrap();
+/// assert_eq!(canonical, "(server (port 8080) (host \"a b\"))");
+/// assert_eq!(sise::canonicalize(&canonical).unwrap(), canonical);
+/// ```
+pub fn canonicalize(data: &str) -> Result<String, ParseError> {
+    canonicalize_with(data, ParserOptions::default())
+}
+
+/// Like [`canonicalize`], but with some syntax extensions enabled.
+///
+/// # Example
+///
+/// ```
+/// let options = sise::ParserOptions {
+///     block_comments: true,
+///     datum_comments: true,
+/// };
+/// let data = "(a #| comment |# b #;(c d) e)";
+/// assert_eq!(sise::canonicalize_with(data, options).unwrap(), "(a b e)");
+/// ```
+pub fn canonicalize_with(data: &str, options: ParserOptions) -> Result<String, ParseError> {
+    let mut result = String::with_capacity(data.len());
+    let mut writer = CanonicalWriter::new(|s: &str| result.push_str(s));
+    let mut parser = Parser::with_options(data, options);
+    let mut depth = 0usize;
+    loop {
+        let item = parser.next_item()?;
+        match item {
+            ParsedItem::Atom(..) => {}
+            ParsedItem::ListStart(_) => depth += 1,
+            ParsedItem::ListEnd(_) => depth -= 1,
+        }
+        writer.item(item);
+        if depth == 0 {
+            break;
+        }
+    }
+    parser.finish()?;
+    Ok(result)
+}
+
+/// Serializes a tree in the canonical form described at
+/// [`canonicalize`].
+///
+/// # Panics
+///
+/// Panics if the tree contains an invalid atom.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree = sise_tree!(["server", ["port", "8080"], []]);
+/// assert_eq!(sise::canonicalize_tree(&tree), "(server (port 8080) ())");
+/// ```
+pub fn canonicalize_tree(node: &TreeNode) -> String {
+    let mut result = String::new();
+    let mut writer = CanonicalWriter::new(|s: &str| result.push_str(s));
+    writer.node(node);
+    result
+}
+
+/// Computes a 64-bit hash of a tree, which is the FNV-1a hash of the
+/// UTF-8 bytes of its canonical form (see [`canonicalize`]).
+///
+/// Unlike the [`Hash`](core::hash::Hash) implementation of
+/// [`TreeNode`], the result does not depend on the platform or the
+/// version of the crate, so it can be stored. It is not a
+/// cryptographic hash.
+///
+/// # Panics
+///
+/// Panics if the tree contains an invalid atom.
+///
+/// # Example
+///
+/// ```
+/// let tree = sise::parse_tree(&mut sise::Parser::new("(a  b ; c\n)")).unwrap();
+/// assert_eq!(tree, sise::sise_tree!(["a", "b"]));
+/// assert_eq!(sise::hash_tree(&tree), 0x2718_87f6_0048_517f);
+/// ```
+pub fn hash_tree(node: &TreeNode) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut writer = CanonicalWriter::new(|s: &str| {
+        for &byte in s.as_bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    });
+    writer.node(node);
+    hash
+}
+
+/// Writes items in canonical form into a sink of string fragments.
+pub(crate) struct CanonicalWriter<F: FnMut(&str)> {
+    sink: F,
+    // Whether the next atom or list needs a space before it
+    need_space: bool,
+}
+
+impl<F: FnMut(&str)> CanonicalWriter<F> {
+    pub(crate) fn new(sink: F) -> Self {
+        Self {
+            sink,
+            need_space: false,
+        }
+    }
+
+    fn item(&mut self, item: ParsedItem<'_>) {
+        match item {
+            ParsedItem::Atom(atom, _) => {
+                if self.need_space {
+                    (self.sink)(" ");
+                }
+                (self.sink)(atom);
+                self.need_space = true;
+            }
+            ParsedItem::ListStart(_) => {
+                if self.need_space {
+                    (self.sink)(" ");
+                }
+                (self.sink)("(");
+                self.need_space = false;
+            }
+            ParsedItem::ListEnd(_) => {
+                (self.sink)(")");
+                self.need_space = true;
+            }
+        }
+    }
+
+    pub(crate) fn node(&mut self, node: &TreeNode) {
+        if let Err(atom) = self.try_node(node) {
+            panic!("invalid atom {:?}", atom);
+        }
+    }
+
+    /// Like `node`, but stops at the first invalid atom and returns it.
+    pub(crate) fn try_node<'n>(&mut self, node: &'n TreeNode) -> Result<(), &'n str> {
+        match *node {
+            TreeNode::Atom(ref atom) => {
+                if !check_atom(atom) {
+                    return Err(atom);
+                }
+                self.item(ParsedItem::Atom(atom, 0));
+            }
+            TreeNode::List(ref list) => {
+                self.item(ParsedItem::ListStart(0));
+                for item in list.iter() {
+                    self.try_node(item)?;
+                }
+                self.item(ParsedItem::ListEnd(0));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::tree::DEFAULT_LINE_WIDTH;
+use crate::{Atom, AtomError, Serializer, SerializerSink, SerializerStyle, TreeBuilder, TreeNode};
+
+/// A tree node whose atoms are guaranteed to be valid, so it can always
+/// be serialized.
+///
+/// It is obtained from a [`TreeNode`] with [`TryFrom`], which reports
+/// the first invalid atom, and converted back with [`From`]. It is
+/// written with [`serialize`](Self::serialize) or [`Display`], which
+/// formats it like [`TreeNode`] does.
+///
+/// [`Display`]: core::fmt::Display
+///
+/// # Example
+///
+/// ```
+/// use sise::{sise_tree, AtomError, CheckedTreeNode, TreeNode};
+///
+/// let tree = sise_tree!(["server", ["port", "80"]]);
+/// let checked = CheckedTreeNode::try_from(tree.clone()).unwrap();
+/// assert_eq!(TreeNode::from(checked), tree);
+///
+/// let error = CheckedTreeNode::try_from(sise_tree!(["server", ["host", "a b"]])).unwrap_err();
+/// assert_eq!(error.path, [1, 1]);
+/// assert_eq!(error.error, AtomError::IllegalChr { pos: 1, chr: ' ' });
+/// assert_eq!(
+///     error.to_string(),
+///     "invalid atom at (1 1): illegal character ' ' at byte 1",
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CheckedTreeNode {
+    Atom(Atom),
+    List(Vec<CheckedTreeNode>),
+}
+
+impl CheckedTreeNode {
+    /// Return whether the node is an `Atom`.
+    #[inline]
+    pub fn is_atom(&self) -> bool {
+        matches!(self, Self::Atom(_))
+    }
+
+    /// Return whether the node is a `List`.
+    #[inline]
+    pub fn is_list(&self) -> bool {
+        matches!(self, Self::List(_))
+    }
+
+    /// Returns a reference to the atom if the node is an `Atom`.
+    #[inline]
+    pub fn as_atom(&self) -> Option<&Atom> {
+        match *self {
+            Self::Atom(ref atom) => Some(atom),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the list if the node is a `List`.
+    #[inline]
+    pub fn as_list(&self) -> Option<&Vec<Self>> {
+        match *self {
+            Self::List(ref list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the list if the node is a `List`.
+    ///
+    /// There is no mutable access to atoms, since they could be made
+    /// invalid, but they can be replaced with other [`Atom`]s.
+    #[inline]
+    pub fn as_mut_list(&mut self) -> Option<&mut Vec<Self>> {
+        match *self {
+            Self::List(ref mut list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Writes the node into `serializer`, placing line breaks as
+    /// [`serialize_tree`](crate::serialize_tree) does.
+    ///
+    /// Unlike [`serialize_tree`](crate::serialize_tree), it cannot
+    /// panic because of an invalid atom.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::{sise_tree, CheckedTreeNode};
+    ///
+    /// let checked = CheckedTreeNode::try_from(sise_tree!(["a", ["b", "c"]])).unwrap();
+    /// assert_eq!(checked.to_string(), "(a (b c))");
+    /// assert_eq!(format!("{:#1.1}", checked), "(a\n (b\n  c\n )\n)");
+    ///
+    /// let style = sise::SerializerStyle {
+    ///     line_break: "\n",
+    ///     indentation: " ",
+    /// };
+    /// let mut result = String::new();
+    /// let mut serializer = sise::Serializer::new(style, &mut result);
+    /// checked.serialize(&mut serializer, usize::MAX);
+    /// serializer.finish(false);
+    /// assert_eq!(result, "(a (b c))");
+    /// ```
+    pub fn serialize(&self, serializer: &mut Serializer<'_, '_>, break_line_at: usize) {
+        let mut sink = SerializerSink::new(serializer, break_line_at);
+        let mut stack = Vec::new();
+        let mut iter = core::slice::from_ref(self).iter();
+        loop {
+            match iter.next() {
+                Some(Self::Atom(atom)) => sink.atom(atom.as_str(), 0),
+                Some(Self::List(list)) => {
+                    sink.begin_list(0);
+                    stack.push(core::mem::replace(&mut iter, list.iter()));
+                }
+                None => match stack.pop() {
+                    Some(parent) => {
+                        sink.end_list(0);
+                        iter = parent;
+                    }
+                    None => return,
+                },
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for CheckedTreeNode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indentation = if f.alternate() {
+            " ".repeat(f.precision().unwrap_or(2))
+        } else {
+            String::new()
+        };
+        let break_line_at = if f.alternate() {
+            f.width().unwrap_or(DEFAULT_LINE_WIDTH)
+        } else {
+            usize::MAX
+        };
+        let style = SerializerStyle {
+            line_break: "\n",
+            indentation: &indentation,
+        };
+        let mut result = String::new();
+        let mut serializer = Serializer::new(style, &mut result);
+        self.serialize(&mut serializer, break_line_at);
+        serializer.finish(false);
+        f.write_str(&result)
+    }
+}
+
+/// Error returned when converting a [`TreeNode`] that contains an
+/// invalid atom into a [`CheckedTreeNode`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckedTreeError {
+    /// Indices of the invalid atom, starting from the root
+    pub path: Vec<usize>,
+    pub error: AtomError,
+}
+
+impl core::fmt::Display for CheckedTreeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("invalid atom at (")?;
+        for (i, index) in self.path.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", index)?;
+        }
+        write!(f, "): {}", self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CheckedTreeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl TryFrom<TreeNode> for CheckedTreeNode {
+    type Error = CheckedTreeError;
+
+    fn try_from(node: TreeNode) -> Result<Self, CheckedTreeError> {
+        let mut path = Vec::new();
+        check_node(node, &mut path).map_err(|error| CheckedTreeError { path, error })
+    }
+}
+
+/// On error, `path` is left pointing to the invalid atom.
+fn check_node(node: TreeNode, path: &mut Vec<usize>) -> Result<CheckedTreeNode, AtomError> {
+    match node {
+        TreeNode::Atom(atom) => Atom::new(atom).map(CheckedTreeNode::Atom),
+        TreeNode::List(list) => {
+            let mut checked = Vec::with_capacity(list.len());
+            for (i, item) in list.into_iter().enumerate() {
+                path.push(i);
+                checked.push(check_node(item, path)?);
+                path.pop();
+            }
+            Ok(CheckedTreeNode::List(checked))
+        }
+    }
+}
+
+impl From<CheckedTreeNode> for TreeNode {
+    fn from(node: CheckedTreeNode) -> Self {
+        match node {
+            CheckedTreeNode::Atom(atom) => Self::from(atom),
+            CheckedTreeNode::List(list) => Self::List(list.into_iter().map(Self::from).collect()),
+        }
+    }
+}
+
+impl From<Atom> for CheckedTreeNode {
+    #[inline]
+    fn from(atom: Atom) -> Self {
+        Self::Atom(atom)
+    }
+}
+
+impl From<Vec<CheckedTreeNode>> for CheckedTreeNode {
+    #[inline]
+    fn from(list: Vec<Self>) -> Self {
+        Self::List(list)
+    }
+}
@@ -0,0 +1,277 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::task::Poll;
+use std::io::{Read, Write};
+
+use crate::incremental::{IncrementalParser, Step};
+use crate::{
+    serialize_tree, OwnedParsedItem, ParseError, ParserOptions, Serializer, SerializerStyle,
+    TreeNode,
+};
+
+/// Maximum size of a message used by [`MessageReader::new`] and
+/// [`MessageWriter::new`] (16 MiB).
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Error returned by [`MessageReader`] and [`MessageWriter`].
+#[derive(Debug)]
+pub enum CodecError {
+    /// Reading from or writing to the underlying stream failed
+    Io(std::io::Error),
+    /// The received data is not valid SISE
+    Parse(ParseError),
+    /// A message is larger than the maximum size. `pos` is the byte
+    /// offset in the stream where the message begins (when reading,
+    /// including the whitespace and comments that precede it).
+    MessageTooLarge { pos: usize },
+}
+
+impl core::fmt::Display for CodecError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            CodecError::Io(ref e) => write!(f, "I/O error: {}", e),
+            CodecError::Parse(ref e) => e.fmt(f),
+            CodecError::MessageTooLarge { pos } => {
+                write!(f, "message at byte {} is too large", pos)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            CodecError::Io(ref e) => Some(e),
+            CodecError::Parse(ref e) => Some(e),
+            CodecError::MessageTooLarge { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CodecError {
+    #[inline]
+    fn from(e: std::io::Error) -> Self {
+        CodecError::Io(e)
+    }
+}
+
+impl From<ParseError> for CodecError {
+    #[inline]
+    fn from(e: ParseError) -> Self {
+        CodecError::Parse(e)
+    }
+}
+
+/// Reads a stream of messages, where each message is a top-level node.
+///
+/// Messages do not need any delimiter besides the SISE syntax itself,
+/// and they can be split arbitrarily between reads. The size of a
+/// message is counted from the end of the previous one, so it includes
+/// any whitespace and comments that precede it. Byte offsets in errors
+/// are relative to the beginning of the stream.
+///
+/// Any error is fatal: after it, [`read_message`](Self::read_message)
+/// returns `Ok(None)`.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let data: &[u8] = b"(ping 1)\n(ping 2)\n";
+/// let mut reader = sise::MessageReader::new(data);
+/// assert_eq!(reader.read_message().unwrap(), Some(sise_tree!(["ping", "1"])));
+/// assert_eq!(reader.read_message().unwrap(), Some(sise_tree!(["ping", "2"])));
+/// assert_eq!(reader.read_message().unwrap(), None);
+/// ```
+pub struct MessageReader<R> {
+    reader: R,
+    inner: IncrementalParser,
+    max_message_size: usize,
+}
+
+impl<R: Read> MessageReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader,
+            inner: IncrementalParser::new(options),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Sets the maximum size of a message in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let data: &[u8] = b"(short) (very-long-message)";
+    /// let mut reader = sise::MessageReader::new(data);
+    /// reader.set_max_message_size(10);
+    /// assert!(reader.read_message().unwrap().is_some());
+    /// assert!(matches!(
+    ///     reader.read_message(),
+    ///     Err(sise::CodecError::MessageTooLarge { pos: 7 }),
+    /// ));
+    /// ```
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.max_message_size = max_message_size;
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next message, blocking until it is complete. Returns
+    /// `Ok(None)` when the stream ends between two messages.
+    pub fn read_message(&mut self) -> Result<Option<TreeNode>, CodecError> {
+        let message_start = self.inner.pos();
+        // Lists that are being built
+        let mut stack: Vec<Vec<TreeNode>> = Vec::new();
+        loop {
+            let node = match self.inner.next_step()? {
+                Step::Item(OwnedParsedItem::Atom(atom, _)) => TreeNode::Atom(atom),
+                Step::Item(OwnedParsedItem::ListStart(_)) => {
+                    stack.push(Vec::new());
+                    continue;
+                }
+                Step::Item(OwnedParsedItem::ListEnd(_)) => TreeNode::List(stack.pop().unwrap()),
+                Step::NeedInput => {
+                    self.check_size(message_start, self.inner.read_end())?;
+                    let reader = &mut self.reader;
+                    let result = self.inner.read_with(|buf| Poll::Ready(reader.read(buf)));
+                    if let Poll::Ready(Err(e)) = result {
+                        self.inner.abort();
+                        return Err(CodecError::Io(e));
+                    }
+                    continue;
+                }
+                Step::End => return Ok(None),
+            };
+
+            if let Some(parent) = stack.last_mut() {
+                parent.push(node);
+            } else {
+                self.check_size(message_start, self.inner.pos())?;
+                return Ok(Some(node));
+            }
+        }
+    }
+
+    fn check_size(&mut self, message_start: usize, message_end: usize) -> Result<(), CodecError> {
+        if message_end - message_start > self.max_message_size {
+            self.inner.abort();
+            Err(CodecError::MessageTooLarge { pos: message_start })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<R: Read> Iterator for MessageReader<R> {
+    type Item = Result<TreeNode, CodecError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_message().transpose()
+    }
+}
+
+/// Writes messages to a stream, in a form that [`MessageReader`] can
+/// read back.
+///
+/// Each message is serialized in a single line.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let mut writer = sise::MessageWriter::new(Vec::new());
+/// writer.write_message(&sise_tree!(["ping", "1"])).unwrap();
+/// writer.write_message(&sise_tree!("pong")).unwrap();
+/// assert_eq!(writer.into_inner(), b"(ping 1)\npong\n");
+/// ```
+pub struct MessageWriter<W> {
+    writer: W,
+    max_message_size: usize,
+    // Amount of bytes written so far
+    pos: usize,
+    message_buf: String,
+}
+
+impl<W: Write> MessageWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            pos: 0,
+            message_buf: String::new(),
+        }
+    }
+
+    /// Sets the maximum size of a message in bytes, including the
+    /// line break that follows it.
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.max_message_size = max_message_size;
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes `message`. Nothing is written if it is too large.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `message` contains an invalid atom.
+    pub fn write_message(&mut self, message: &TreeNode) -> Result<(), CodecError> {
+        const STYLE: SerializerStyle<'static> = SerializerStyle {
+            line_break: "\n",
+            indentation: "",
+        };
+
+        self.message_buf.clear();
+        let mut serializer = Serializer::new(STYLE, &mut self.message_buf);
+        serialize_tree(&mut serializer, message, usize::MAX);
+        serializer.finish(true);
+
+        if self.message_buf.len() > self.max_message_size {
+            return Err(CodecError::MessageTooLarge { pos: self.pos });
+        }
+        self.writer.write_all(self.message_buf.as_bytes())?;
+        self.pos += self.message_buf.len();
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), CodecError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use alloc::string::{String, ToString as _};
+use alloc::vec::Vec;
+
+use crate::{check_atom, TreeNode};
+
+/// Error returned when reading a canonical S-expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CsexpError {
+    /// There is a byte that cannot appear at this position
+    UnexpectedByte { pos: usize },
+
+    /// A length prefix has leading zeros or it is too large
+    InvalidLength { pos: usize },
+
+    /// The bytes of an atom are not a valid SISE atom (e.g.,
+    /// they contain spaces or they are empty)
+    InvalidAtom { pos: usize },
+
+    /// The transport form is not valid base64
+    InvalidBase64 { pos: usize },
+
+    /// Unexpected end-of-file
+    UnexpectedEof { pos: usize },
+
+    /// Found data after the end of the expression
+    ExpectedEof { pos: usize },
+}
+
+impl core::fmt::Display for CsexpError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            CsexpError::UnexpectedByte { pos } => write!(f, "unexpected byte at {}", pos),
+            CsexpError::InvalidLength { pos } => write!(f, "invalid length at byte {}", pos),
+            CsexpError::InvalidAtom { pos } => write!(f, "invalid atom at byte {}", pos),
+            CsexpError::InvalidBase64 { pos } => write!(f, "invalid base64 at byte {}", pos),
+            CsexpError::UnexpectedEof { pos } => {
+                write!(f, "unexpected end-of-file at byte {}", pos)
+            }
+            CsexpError::ExpectedEof { pos } => write!(f, "expected end-of-file at byte {}", pos),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CsexpError {}
+
+/// Encodes a tree as a canonical S-expression.
+///
+/// Each atom is written as its length in bytes, in decimal, followed
+/// by `:` and its bytes (e.g., `3:foo`), and lists are enclosed with
+/// `(` and `)` without any separator. The encoding of a tree is unique,
+/// which makes it suitable for hashing and signing.
+///
+/// # Panics
+///
+/// Panics if the tree contains an invalid atom.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree = sise_tree!(["sig", ["alg", "ed25519"], "\"a b\""]);
+/// assert_eq!(sise::to_csexp(&tree), b"(3:sig(3:alg7:ed25519)5:\"a b\")");
+/// ```
+pub fn to_csexp(node: &TreeNode) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_node(node, &mut out);
+    out
+}
+
+fn write_node(node: &TreeNode, out: &mut Vec<u8>) {
+    match *node {
+        TreeNode::Atom(ref atom) => {
+            assert!(check_atom(atom), "invalid atom {:?}", atom);
+            out.extend_from_slice(atom.len().to_string().as_bytes());
+            out.push(b':');
+            out.extend_from_slice(atom.as_bytes());
+        }
+        TreeNode::List(ref list) => {
+            out.push(b'(');
+            for item in list.iter() {
+                write_node(item, out);
+            }
+            out.push(b')');
+        }
+    }
+}
+
+/// Decodes a tree from a canonical S-expression.
+///
+/// Atoms must be valid SISE atoms, so any tree that is read can also
+/// be written as text.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let data = b"(3:sig(3:alg7:ed25519)5:\"a b\")";
+/// assert_eq!(
+///     sise::from_csexp(data),
+///     Ok(sise_tree!(["sig", ["alg", "ed25519"], "\"a b\""])),
+/// );
+///
+/// assert_eq!(
+///     sise::from_csexp(b"(3:a b)"),
+///     Err(sise::CsexpError::InvalidAtom { pos: 3 }),
+/// );
+/// ```
+pub fn from_csexp(data: &[u8]) -> Result<TreeNode, CsexpError> {
+    let mut pos = 0;
+    // Lists that are being built
+    let mut stack: Vec<Vec<TreeNode>> = Vec::new();
+    loop {
+        let node = match data.get(pos) {
+            None => return Err(CsexpError::UnexpectedEof { pos }),
+            Some(b'(') => {
+                pos += 1;
+                stack.push(Vec::new());
+                continue;
+            }
+            Some(b')') if !stack.is_empty() => {
+                pos += 1;
+                TreeNode::List(stack.pop().unwrap())
+            }
+            Some(b'0'..=b'9') => {
+                let (atom, end_pos) = read_atom(data, pos)?;
+                pos = end_pos;
+                TreeNode::Atom(atom)
+            }
+            Some(_) => return Err(CsexpError::UnexpectedByte { pos }),
+        };
+
+        if let Some(parent) = stack.last_mut() {
+            parent.push(node);
+        } else if pos == data.len() {
+            return Ok(node);
+        } else {
+            return Err(CsexpError::ExpectedEof { pos });
+        }
+    }
+}
+
+/// Reads an atom whose length prefix starts at `pos`. Returns the atom
+/// and the position after it.
+fn read_atom(data: &[u8], pos: usize) -> Result<(String, usize), CsexpError> {
+    let mut len: usize = 0;
+    let mut i = pos;
+    loop {
+        match data.get(i) {
+            None => return Err(CsexpError::UnexpectedEof { pos: i }),
+            Some(b':') => break,
+            Some(&digit @ b'0'..=b'9') => {
+                if i != pos && len == 0 {
+                    // Leading zero
+                    return Err(CsexpError::InvalidLength { pos });
+                }
+                len = len
+                    .checked_mul(10)
+                    .and_then(|len| len.checked_add(usize::from(digit - b'0')))
+                    .ok_or(CsexpError::InvalidLength { pos })?;
+            }
+            Some(_) => return Err(CsexpError::UnexpectedByte { pos: i }),
+        }
+        i += 1;
+    }
+
+    let start = i + 1;
+    let bytes = data
+        .get(start..)
+        .and_then(|rem| rem.get(..len))
+        .ok_or(CsexpError::UnexpectedEof { pos: data.len() })?;
+    match core::str::from_utf8(bytes) {
+        Ok(atom) if check_atom(atom) => Ok((String::from(atom), start + len)),
+        _ => Err(CsexpError::InvalidAtom { pos: start }),
+    }
+}
+
+/// Encodes a tree as a canonical S-expression in transport form: the
+/// base64 encoding of [`to_csexp`] enclosed with `{` and `}`.
+///
+/// # Panics
+///
+/// Panics if the tree contains an invalid atom.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree = sise_tree!(["a", "bc"]);
+/// assert_eq!(sise::to_csexp_transport(&tree), "{KDE6YTI6YmMp}");
+/// ```
+pub fn to_csexp_transport(node: &TreeNode) -> String {
+    let canonical = to_csexp(node);
+    let mut out = String::with_capacity(canonical.len() / 3 * 4 + 6);
+    out.push('{');
+    encode_base64(&canonical, &mut out);
+    out.push('}');
+    out
+}
+
+/// Decodes a tree from a canonical S-expression in transport form.
+///
+/// Whitespace is allowed inside the braces. Positions in
+/// [`CsexpError::InvalidBase64`] refer to `data`, while positions in
+/// errors found after decoding refer to the decoded canonical bytes.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// assert_eq!(
+///     sise::from_csexp_transport(b"{KDE6YTI6\n YmMp}"),
+///     Ok(sise_tree!(["a", "bc"])),
+/// );
+/// ```
+pub fn from_csexp_transport(data: &[u8]) -> Result<TreeNode, CsexpError> {
+    match data.first() {
+        None => return Err(CsexpError::UnexpectedEof { pos: 0 }),
+        Some(b'{') => {}
+        Some(_) => return Err(CsexpError::UnexpectedByte { pos: 0 }),
+    }
+    let end = data
+        .iter()
+        .position(|&byte| byte == b'}')
+        .ok_or(CsexpError::UnexpectedEof { pos: data.len() })?;
+    if end != data.len() - 1 {
+        return Err(CsexpError::ExpectedEof { pos: end + 1 });
+    }
+    let canonical = decode_base64(&data[1..end], 1)?;
+    from_csexp(&canonical)
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(data: &[u8], out: &mut String) {
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = chunk.get(1).copied().unwrap_or(0);
+        let b2 = chunk.get(2).copied().unwrap_or(0);
+        let sextets = [
+            b0 >> 2,
+            ((b0 & 0x03) << 4) | (b1 >> 4),
+            ((b1 & 0x0F) << 2) | (b2 >> 6),
+            b2 & 0x3F,
+        ];
+        for (i, &sextet) in sextets.iter().enumerate() {
+            if i <= chunk.len() {
+                out.push(char::from(BASE64_CHARS[usize::from(sextet)]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+/// Decodes base64 that begins at `offset` in the input (to report
+/// error positions), ignoring ASCII whitespace.
+fn decode_base64(data: &[u8], offset: usize) -> Result<Vec<u8>, CsexpError> {
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    let mut acc: u32 = 0;
+    let mut num_sextets = 0;
+    let mut padding = 0;
+    let mut last_pos = offset;
+    for (i, &byte) in data.iter().enumerate() {
+        let pos = offset + i;
+        let sextet = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' if num_sextets % 4 >= 2 => {
+                padding += 1;
+                num_sextets += 1;
+                last_pos = pos;
+                continue;
+            }
+            b' ' | b'\t' | b'\n' | b'\r' => continue,
+            _ => return Err(CsexpError::InvalidBase64 { pos }),
+        };
+        if padding != 0 {
+            // Data after padding
+            return Err(CsexpError::InvalidBase64 { pos });
+        }
+        acc = (acc << 6) | u32::from(sextet);
+        num_sextets += 1;
+        last_pos = pos;
+        if num_sextets % 4 == 0 {
+            out.extend_from_slice(&acc.to_be_bytes()[1..]);
+            acc = 0;
+        }
+    }
+
+    match (num_sextets % 4, padding) {
+        (0, 0) => {}
+        (0, 1) => {
+            // 3 sextets and 1 padding char: 2 bytes
+            let acc = acc << 6;
+            check_unused_bits(acc, 8, last_pos)?;
+            out.extend_from_slice(&acc.to_be_bytes()[1..3]);
+        }
+        (0, 2) => {
+            // 2 sextets and 2 padding chars: 1 byte
+            let acc = acc << 12;
+            check_unused_bits(acc, 16, last_pos)?;
+            out.push(acc.to_be_bytes()[1]);
+        }
+        _ => return Err(CsexpError::InvalidBase64 { pos: last_pos }),
+    }
+    Ok(out)
+}
+
+fn check_unused_bits(acc: u32, num_bits: u32, pos: usize) -> Result<(), CsexpError> {
+    if acc & ((1 << num_bits) - 1) != 0 {
+        Err(CsexpError::InvalidBase64 { pos })
+    } else {
+        Ok(())
+    }
+}
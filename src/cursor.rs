@@ -0,0 +1,267 @@
+use alloc::vec::Vec;
+
+use crate::TreeNode;
+
+/// A position in a mutable tree, which can be moved around the tree and
+/// used to edit it in place.
+///
+/// The cursor keeps a mutable borrow of the root and the index path of
+/// the current node (as used by [`TreeNode::index_path`]). Movements
+/// return `false` and leave the cursor where it was when there is no
+/// node in that direction.
+///
+/// # Example
+///
+/// ```
+/// use sise::{sise_tree, Cursor, TreeNode};
+///
+/// let mut tree = sise_tree!(["config", ["port", "80"], ["host", "a"]]);
+/// let mut cursor = Cursor::new(&mut tree);
+/// assert!(cursor.down(1));
+/// assert!(cursor.down(1));
+/// assert_eq!(cursor.path(), [1, 1]);
+/// cursor.replace(TreeNode::from("8080"));
+/// assert!(cursor.up());
+/// assert!(cursor.right());
+/// cursor.insert_after(sise_tree!(["debug"]));
+/// assert_eq!(
+///     tree,
+///     sise_tree!(["config", ["port", "8080"], ["host", "a"], ["debug"]]),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Cursor<'a> {
+    root: &'a mut TreeNode,
+    path: Vec<usize>,
+}
+
+impl<'a> Cursor<'a> {
+    /// Creates a cursor at the root of a tree.
+    #[inline]
+    pub fn new(root: &'a mut TreeNode) -> Self {
+        Self {
+            root,
+            path: Vec::new(),
+        }
+    }
+
+    /// Returns the index path of the current node.
+    #[inline]
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Returns whether the cursor is at the root.
+    #[inline]
+    pub fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Returns the current node.
+    pub fn node(&self) -> &TreeNode {
+        self.root.index_path(&self.path).unwrap()
+    }
+
+    /// Returns the current node mutably.
+    pub fn node_mut(&mut self) -> &mut TreeNode {
+        let mut node = &mut *self.root;
+        for &index in self.path.iter() {
+            node = &mut node.as_mut_list().unwrap()[index];
+        }
+        node
+    }
+
+    /// Returns the root of the tree.
+    #[inline]
+    pub fn root(&self) -> &TreeNode {
+        self.root
+    }
+
+    /// Moves the cursor to the root.
+    #[inline]
+    pub fn to_root(&mut self) {
+        self.path.clear();
+    }
+
+    /// Consumes the cursor and returns the root of the tree.
+    #[inline]
+    pub fn into_root(self) -> &'a mut TreeNode {
+        self.root
+    }
+
+    /// Moves to the parent of the current node.
+    pub fn up(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    /// Moves to the element at `index` of the current node, if it is
+    /// a list.
+    pub fn down(&mut self, index: usize) -> bool {
+        let len = self.node().as_list().map_or(0, Vec::len);
+        if index < len {
+            self.path.push(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves to the previous element of the parent list.
+    pub fn left(&mut self) -> bool {
+        match self.path.last_mut() {
+            Some(index) if *index != 0 => {
+                *index -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves to the next element of the parent list.
+    pub fn right(&mut self) -> bool {
+        let len = match self.parent_len() {
+            Some(len) => len,
+            None => return false,
+        };
+        let index = self.path.last_mut().unwrap();
+        if *index + 1 < len {
+            *index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replaces the current node with `node`, returning the old node.
+    /// The cursor stays at the same path.
+    pub fn replace(&mut self, node: TreeNode) -> TreeNode {
+        core::mem::replace(self.node_mut(), node)
+    }
+
+    /// Inserts `node` before the current node. The cursor stays at the
+    /// current node, whose index increases by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is at the root.
+    pub fn insert_before(&mut self, node: TreeNode) {
+        let index = self.index();
+        self.parent_list().insert(index, node);
+        *self.path.last_mut().unwrap() += 1;
+    }
+
+    /// Inserts `node` after the current node. The cursor stays at the
+    /// current node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is at the root.
+    pub fn insert_after(&mut self, node: TreeNode) {
+        let index = self.index();
+        self.parent_list().insert(index + 1, node);
+    }
+
+    /// Replaces the current node with a list whose only element is the
+    /// current node. The cursor stays at the same path, which is now the
+    /// new list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::{sise_tree, Cursor};
+    ///
+    /// let mut tree = sise_tree!(["a", "b"]);
+    /// let mut cursor = Cursor::new(&mut tree);
+    /// cursor.down(1);
+    /// cursor.wrap();
+    /// assert_eq!(*cursor.node(), sise_tree!(["b"]));
+    /// assert_eq!(tree, sise_tree!(["a", ["b"]]));
+    /// ```
+    pub fn wrap(&mut self) {
+        let node = self.node_mut();
+        let inner = core::mem::replace(node, TreeNode::List(Vec::new()));
+        node.as_mut_list().unwrap().push(inner);
+    }
+
+    /// Replaces the current node with the nodes of `nodes`, returning
+    /// the old node. The cursor moves to the first inserted node or, if
+    /// `nodes` is empty, as [`delete`](Self::delete) does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is at the root.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::{sise_tree, Cursor};
+    ///
+    /// // Unwrap the list `(b c)`
+    /// let mut tree = sise_tree!(["a", ["b", "c"], "d"]);
+    /// let mut cursor = Cursor::new(&mut tree);
+    /// cursor.down(1);
+    /// let old = cursor.node().clone();
+    /// cursor.splice(old.into_list().unwrap());
+    /// assert_eq!(*cursor.node(), "b");
+    /// assert_eq!(tree, sise_tree!(["a", "b", "c", "d"]));
+    /// ```
+    pub fn splice<I: IntoIterator<Item = TreeNode>>(&mut self, nodes: I) -> TreeNode {
+        let index = self.index();
+        let list = self.parent_list();
+        let old_len = list.len();
+        let old = list.splice(index..(index + 1), nodes).next().unwrap();
+        let len = list.len();
+        if len < old_len {
+            self.after_removal(len);
+        }
+        old
+    }
+
+    /// Removes the current node and returns it. The cursor moves to the
+    /// next element of the parent list if there is one, otherwise to
+    /// the previous one, otherwise to the parent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor is at the root.
+    pub fn delete(&mut self) -> TreeNode {
+        let index = self.index();
+        let list = self.parent_list();
+        let old = list.remove(index);
+        let len = list.len();
+        self.after_removal(len);
+        old
+    }
+
+    /// Returns the index of the current node in its parent.
+    fn index(&self) -> usize {
+        *self.path.last().expect("cursor is at the root")
+    }
+
+    fn parent_len(&self) -> Option<usize> {
+        let (_, parent_path) = self.path.split_last()?;
+        Some(self.root.index_path(parent_path).unwrap().as_list()?.len())
+    }
+
+    fn parent_list(&mut self) -> &mut Vec<TreeNode> {
+        let (_, parent_path) = self.path.split_last().expect("cursor is at the root");
+        let mut node = &mut *self.root;
+        for &index in parent_path.iter() {
+            node = &mut node.as_mut_list().unwrap()[index];
+        }
+        node.as_mut_list().unwrap()
+    }
+
+    /// Fixes the path after removing the current node from its parent,
+    /// which now has `len` elements.
+    fn after_removal(&mut self, len: usize) {
+        let index = self.path.last_mut().unwrap();
+        if *index >= len {
+            if len == 0 {
+                self.path.pop();
+            } else {
+                *index = len - 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,583 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString as _};
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+use crate::TreeNode;
+
+/// An edit produced by [`diff`].
+///
+/// Paths are index paths as used by [`TreeNode::index_path`]. Each edit
+/// refers to the tree that results from applying the previous edits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Edit {
+    /// Inserts `node` so that it ends up at `path`. The last index can
+    /// be the length of the list to insert at its end.
+    Insert { path: Vec<usize>, node: TreeNode },
+    /// Deletes the node at `path`.
+    Delete { path: Vec<usize> },
+    /// Replaces the atom at `path` with another atom.
+    ReplaceAtom { path: Vec<usize>, atom: String },
+    /// Replaces the node at `path` with a node of a different kind (an
+    /// atom with a list or vice versa).
+    Replace { path: Vec<usize>, node: TreeNode },
+    /// Removes the node at `from` and inserts it so that it ends up at
+    /// `to`, within the same list. `to` refers to the list after the
+    /// removal.
+    Move { from: Vec<usize>, to: Vec<usize> },
+}
+
+/// Computes a list of edits that transforms `old` into `new`.
+///
+/// Elements of a list that are kept in the same relative order are not
+/// touched. Elements that are reordered become moves, and lists that
+/// begin with the same atom (e.g., `(port 80)` and `(port 8080)`) are
+/// matched even if they have been reordered, and diffed recursively.
+///
+/// Comparing two lists of N and M elements takes O((N + M) D) time,
+/// where D is the number of elements that are not kept in the same
+/// relative order. If D is greater than a thousand, only elements that
+/// appear once in each list are kept in order, which takes O((N + M)
+/// log(N + M)) time, but might result in more edits.
+///
+/// # Example
+///
+/// ```
+/// use sise::{sise_tree, Edit};
+///
+/// let old = sise_tree!(["server", ["host", "a"], ["port", "80"]]);
+/// let new = sise_tree!(["server", ["port", "8080"], ["host", "a"], ["tls"]]);
+/// assert_eq!(
+///     sise::diff(&old, &new),
+///     [
+///         Edit::Move { from: vec![2], to: vec![1] },
+///         Edit::Insert { path: vec![3], node: sise_tree!(["tls"]) },
+///         Edit::ReplaceAtom { path: vec![1, 1], atom: String::from("8080") },
+///     ],
+/// );
+/// ```
+pub fn diff(old: &TreeNode, new: &TreeNode) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut path = Vec::new();
+    diff_node(old, new, &mut path, &mut edits);
+    edits
+}
+
+fn diff_node(old: &TreeNode, new: &TreeNode, path: &mut Vec<usize>, edits: &mut Vec<Edit>) {
+    match (old, new) {
+        _ if old == new => {}
+        (TreeNode::Atom(_), TreeNode::Atom(new_atom)) => edits.push(Edit::ReplaceAtom {
+            path: path.clone(),
+            atom: new_atom.clone(),
+        }),
+        (TreeNode::List(old_list), TreeNode::List(new_list)) => {
+            diff_list(old_list, new_list, path, edits);
+        }
+        _ => edits.push(Edit::Replace {
+            path: path.clone(),
+            node: new.clone(),
+        }),
+    }
+}
+
+/// Beyond this number of differences between two lists, anchors are
+/// found with a faster method that does not always find the longest
+/// common subsequence.
+const MAX_DIFFERENCES: usize = 1000;
+
+fn diff_list(old: &[TreeNode], new: &[TreeNode], path: &mut Vec<usize>, edits: &mut Vec<Edit>) {
+    let (old_ids, new_ids, num_ids) = element_ids(old, new);
+
+    // For each new element, the index of the old element it comes
+    // from (if any) and whether it is an anchor (an element that is
+    // kept in the same relative order, which is never moved).
+    let mut sources: Vec<Option<usize>> = alloc::vec![None; new.len()];
+    let mut anchors = alloc::vec![false; new.len()];
+    let mut old_used = alloc::vec![false; old.len()];
+
+    let anchor_pairs = find_anchors(&old_ids, &new_ids, num_ids);
+    for &(i, j) in anchor_pairs.iter() {
+        sources[j] = Some(i);
+        anchors[j] = true;
+        old_used[i] = true;
+    }
+
+    // Elements that have been moved without changes. The old elements
+    // that are not anchors are sorted by id, so the ones equal to a new
+    // element can be taken in order.
+    let mut id_starts = alloc::vec![0; num_ids + 1];
+    for (i, &id) in old_ids.iter().enumerate() {
+        if !old_used[i] {
+            id_starts[id + 1] += 1;
+        }
+    }
+    for id in 0..num_ids {
+        id_starts[id + 1] += id_starts[id];
+    }
+    let mut id_next = id_starts.clone();
+    let mut by_id = alloc::vec![0; id_starts[num_ids]];
+    for (i, &id) in old_ids.iter().enumerate() {
+        if !old_used[i] {
+            by_id[id_next[id]] = i;
+            id_next[id] += 1;
+        }
+    }
+    for j in 0..new.len() {
+        let id = new_ids[j];
+        if sources[j].is_none() && id_starts[id] != id_next[id] {
+            let i = by_id[id_starts[id]];
+            id_starts[id] += 1;
+            sources[j] = Some(i);
+            old_used[i] = true;
+        }
+    }
+
+    // Lists with the same head, possibly moved. Indices are stored in
+    // reverse order so the first one can be popped.
+    let mut by_head: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for i in (0..old.len()).rev() {
+        if !old_used[i] {
+            if let Some(head) = old[i].head() {
+                by_head.entry(head).or_default().push(i);
+            }
+        }
+    }
+    for j in 0..new.len() {
+        if sources[j].is_none() {
+            if let Some(head) = new[j].head() {
+                if let Some(i) = by_head.get_mut(head).and_then(Vec::pop) {
+                    sources[j] = Some(i);
+                    old_used[i] = true;
+                }
+            }
+        }
+    }
+
+    // Other elements are paired in order if they are between the same
+    // anchors.
+    let old_gaps = gaps(old.len(), anchor_pairs.iter().map(|&(i, _)| i));
+    let new_gaps = gaps(new.len(), anchor_pairs.iter().map(|&(_, j)| j));
+    let mut next_old = 0;
+    for j in 0..new.len() {
+        if sources[j].is_some() {
+            continue;
+        }
+        while next_old < old.len() && (old_used[next_old] || old_gaps[next_old] < new_gaps[j]) {
+            next_old += 1;
+        }
+        if next_old < old.len() && old_gaps[next_old] == new_gaps[j] {
+            sources[j] = Some(next_old);
+            old_used[next_old] = true;
+        }
+    }
+
+    // Delete old elements that are not used, from the end so indices of
+    // the following deletions are not affected.
+    for i in (0..old.len()).rev() {
+        if !old_used[i] {
+            path.push(i);
+            edits.push(Edit::Delete { path: path.clone() });
+            path.pop();
+        }
+    }
+
+    // Place each element that is not an anchor right after the element
+    // that precedes it in `new`. Since anchors are in the right relative
+    // order, this results in the order of `new`.
+    //
+    // The order of the list being edited is tracked with a key for each
+    // element, compared as pairs. Old elements start with `(i + 1, 0)`
+    // and, once placed, an element gets the key of its predecessor with
+    // the second half increased, which sorts right after it (the first
+    // element gets `(0, 1)`). Since each element is the predecessor of
+    // only one, keys are unique, and the position of an element is the
+    // number of elements in the list with a lower key.
+    let mut new_keys = Vec::with_capacity(new.len());
+    for j in 0..new.len() {
+        let key = match (sources[j], j) {
+            (Some(i), _) if anchors[j] => (i + 1, 0),
+            (_, 0) => (0, 1),
+            _ => {
+                let (pred_major, pred_minor) = new_keys[j - 1];
+                (pred_major, pred_minor + 1)
+            }
+        };
+        new_keys.push(key);
+    }
+    let mut all_keys: Vec<(usize, usize)> = (0..old.len())
+        .filter(|&i| old_used[i])
+        .map(|i| (i + 1, 0))
+        .chain((0..new.len()).filter(|&j| !anchors[j]).map(|j| new_keys[j]))
+        .collect();
+    all_keys.sort_unstable();
+    let rank = |key: (usize, usize)| all_keys.binary_search(&key).unwrap();
+
+    let mut current = Fenwick::new(all_keys.len());
+    for (i, &used) in old_used.iter().enumerate() {
+        if used {
+            current.insert(rank((i + 1, 0)));
+        }
+    }
+    for j in 0..new.len() {
+        if anchors[j] {
+            continue;
+        }
+        let pred_pos = if j == 0 {
+            None
+        } else {
+            Some(current.count_before(rank(new_keys[j - 1])))
+        };
+        match sources[j] {
+            Some(i) => {
+                let old_rank = rank((i + 1, 0));
+                let pos = current.count_before(old_rank);
+                let target = match pred_pos {
+                    None => 0,
+                    Some(pred_pos) if pred_pos < pos => pred_pos + 1,
+                    Some(pred_pos) => pred_pos,
+                };
+                if target != pos {
+                    let mut from = path.clone();
+                    from.push(pos);
+                    let mut to = path.clone();
+                    to.push(target);
+                    edits.push(Edit::Move { from, to });
+                }
+                current.remove(old_rank);
+            }
+            None => {
+                let target = pred_pos.map_or(0, |pred_pos| pred_pos + 1);
+                path.push(target);
+                edits.push(Edit::Insert {
+                    path: path.clone(),
+                    node: new[j].clone(),
+                });
+                path.pop();
+            }
+        }
+        current.insert(rank(new_keys[j]));
+    }
+
+    // Now the list has the order of `new`, diff the elements that
+    // have changed.
+    for (j, new_item) in new.iter().enumerate() {
+        if let Some(i) = sources[j] {
+            path.push(j);
+            diff_node(&old[i], new_item, path, edits);
+            path.pop();
+        }
+    }
+}
+
+/// Gives an id to each element of `old` and `new`, so that elements
+/// are equal if and only if their ids are. Ids go from zero to the
+/// returned number of ids.
+fn element_ids<'a>(old: &'a [TreeNode], new: &'a [TreeNode]) -> (Vec<usize>, Vec<usize>, usize) {
+    // For each hash, the elements seen with that hash and their ids
+    let mut seen: BTreeMap<u64, Vec<(&'a TreeNode, usize)>> = BTreeMap::new();
+    let mut num_ids = 0;
+    let mut id_of = |node: &'a TreeNode| {
+        let mut hasher = FnvHasher(0xcbf2_9ce4_8422_2325);
+        Hash::hash(node, &mut hasher);
+        let bucket = seen.entry(hasher.finish()).or_default();
+        match bucket.iter().find(|&&(other, _)| other == node) {
+            Some(&(_, id)) => id,
+            None => {
+                bucket.push((node, num_ids));
+                num_ids += 1;
+                num_ids - 1
+            }
+        }
+    };
+    let old_ids = old.iter().map(&mut id_of).collect();
+    let new_ids = new.iter().map(&mut id_of).collect();
+    (old_ids, new_ids, num_ids)
+}
+
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// Returns the pairs of indices of a longest common subsequence of
+/// equal elements, given their ids.
+///
+/// If the lists have more than `MAX_DIFFERENCES` differences, the
+/// subsequence is only made of the elements that appear once in each
+/// list (besides the common prefix and suffix), so it might not be the
+/// longest one.
+fn find_anchors(old: &[usize], new: &[usize], num_ids: usize) -> Vec<(usize, usize)> {
+    let prefix_len = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix_len = old[prefix_len..]
+        .iter()
+        .rev()
+        .zip(new[prefix_len..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix_len..(old.len() - suffix_len)];
+    let new_mid = &new[prefix_len..(new.len() - suffix_len)];
+
+    let mut anchors: Vec<(usize, usize)> = (0..prefix_len).map(|i| (i, i)).collect();
+    let mid_anchors = shortest_edit_anchors(old_mid, new_mid, MAX_DIFFERENCES)
+        .unwrap_or_else(|| unique_anchors(old_mid, new_mid, num_ids));
+    anchors.extend(
+        mid_anchors
+            .into_iter()
+            .map(|(i, j)| (prefix_len + i, prefix_len + j)),
+    );
+
+    let old_suffix_start = old.len() - suffix_len;
+    let new_suffix_start = new.len() - suffix_len;
+    anchors.extend((0..suffix_len).map(|k| (old_suffix_start + k, new_suffix_start + k)));
+    anchors
+}
+
+/// Finds a longest common subsequence with the algorithm described by
+/// Eugene W. Myers in "An O(ND) Difference Algorithm and Its
+/// Variations", which takes O((N + M) D) time and O(D²) space, where D
+/// is the number of elements that are not in the subsequence.
+///
+/// Returns `None` if D is greater than `max_differences`.
+fn shortest_edit_anchors(
+    old: &[usize],
+    new: &[usize],
+    max_differences: usize,
+) -> Option<Vec<(usize, usize)>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    // For each number of differences `d`, the furthest x (index into
+    // `old`) reached on each diagonal k = x - y, for k in -d..=d.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let furthest = |v: &[isize], d: isize, k: isize| v[(k + d) as usize];
+    for d in 0..=(max_differences.min(old.len() + new.len()) as isize) {
+        let mut v = Vec::with_capacity(2 * d as usize + 1);
+        for k in (-d..=d).step_by(2) {
+            let mut x = if d == 0 {
+                0
+            } else {
+                let prev = &trace[d as usize - 1];
+                if k == -d
+                    || (k != d && furthest(prev, d - 1, k - 1) < furthest(prev, d - 1, k + 1))
+                {
+                    furthest(prev, d - 1, k + 1)
+                } else {
+                    furthest(prev, d - 1, k - 1) + 1
+                }
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v.push(x);
+            if k != d {
+                // Diagonals of the other parity are never read.
+                v.push(0);
+            }
+            if x >= n && y >= m {
+                trace.push(v);
+                return Some(backtrack(&trace, old, new));
+            }
+        }
+        trace.push(v);
+    }
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], old: &[usize], new: &[usize]) -> Vec<(usize, usize)> {
+    let furthest = |v: &[isize], d: isize, k: isize| v[(k + d) as usize];
+    let mut anchors = Vec::new();
+    let (mut x, mut y) = (old.len() as isize, new.len() as isize);
+    for d in (1..trace.len() as isize).rev() {
+        let prev = &trace[d as usize - 1];
+        let k = x - y;
+        let prev_k =
+            if k == -d || (k != d && furthest(prev, d - 1, k - 1) < furthest(prev, d - 1, k + 1)) {
+                k + 1
+            } else {
+                k - 1
+            };
+        let prev_x = furthest(prev, d - 1, prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            anchors.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        anchors.push((x as usize, y as usize));
+    }
+    anchors.reverse();
+    anchors
+}
+
+/// Returns the pairs of indices of a longest increasing subsequence of
+/// the elements that appear exactly once in each list, which takes
+/// O(N log N) time.
+fn unique_anchors(old: &[usize], new: &[usize], num_ids: usize) -> Vec<(usize, usize)> {
+    const MANY: usize = usize::MAX;
+    // For each id, the index in `old` if it appears once, or `MANY`
+    let mut old_index = alloc::vec![None; num_ids];
+    for (i, &id) in old.iter().enumerate() {
+        old_index[id] = if old_index[id].is_none() {
+            Some(i)
+        } else {
+            Some(MANY)
+        };
+    }
+    let mut new_count = alloc::vec![0u8; num_ids];
+    for &id in new.iter() {
+        new_count[id] = new_count[id].saturating_add(1);
+    }
+    let pairs: Vec<(usize, usize)> = new
+        .iter()
+        .enumerate()
+        .filter_map(|(j, &id)| match old_index[id] {
+            Some(i) if i != MANY && new_count[id] == 1 => Some((i, j)),
+            _ => None,
+        })
+        .collect();
+
+    // `tails[l]` is the index into `pairs` of the pair with the lowest
+    // old index that ends an increasing subsequence of length `l + 1`.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prevs = Vec::with_capacity(pairs.len());
+    for (p, &(i, _)) in pairs.iter().enumerate() {
+        let l = tails.partition_point(|&t| pairs[t].0 < i);
+        prevs.push(if l == 0 { None } else { Some(tails[l - 1]) });
+        if l == tails.len() {
+            tails.push(p);
+        } else {
+            tails[l] = p;
+        }
+    }
+    let mut anchors = Vec::with_capacity(tails.len());
+    let mut p = tails.last().copied();
+    while let Some(current) = p {
+        anchors.push(pairs[current]);
+        p = prevs[current];
+    }
+    anchors.reverse();
+    anchors
+}
+
+/// For each index of a list of `len` elements, returns how many of
+/// `anchors` come before it.
+fn gaps<I: Iterator<Item = usize>>(len: usize, anchors: I) -> Vec<usize> {
+    let mut is_anchor = alloc::vec![false; len];
+    for anchor in anchors {
+        is_anchor[anchor] = true;
+    }
+    let mut result = Vec::with_capacity(len);
+    let mut count = 0;
+    for &anchor in is_anchor.iter() {
+        result.push(count);
+        if anchor {
+            count += 1;
+        }
+    }
+    result
+}
+
+/// A Fenwick tree that counts which of a range of indices are present.
+struct Fenwick(Vec<usize>);
+
+impl Fenwick {
+    fn new(len: usize) -> Self {
+        Self(alloc::vec![0; len])
+    }
+
+    fn insert(&mut self, mut index: usize) {
+        while index < self.0.len() {
+            self.0[index] += 1;
+            index |= index + 1;
+        }
+    }
+
+    fn remove(&mut self, mut index: usize) {
+        while index < self.0.len() {
+            self.0[index] -= 1;
+            index |= index + 1;
+        }
+    }
+
+    /// Returns how many indices lower than `index` are present.
+    fn count_before(&self, mut index: usize) -> usize {
+        let mut count = 0;
+        while index > 0 {
+            count += self.0[index - 1];
+            index &= index - 1;
+        }
+        count
+    }
+}
+
+/// Renders a list of edits in SISE syntax, as a `diff` list with an
+/// element for each edit. Paths are written as `(path ...)`.
+///
+/// The result can be read back with
+/// [`Patch::from_tree`](crate::Patch::from_tree) to apply the edits.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let old = sise_tree!(["a", "b", ["c", "1"]]);
+/// let new = sise_tree!(["b", ["c", "2"], "d", "a"]);
+/// let edits = sise::diff(&old, &new);
+/// assert_eq!(
+///     sise::diff_to_tree(&edits).to_string(),
+///     "(diff (insert (path 3) d) (move (path 0) (path 3)) (replace-atom (path 1 1) 2))",
+/// );
+/// ```
+pub fn diff_to_tree(edits: &[Edit]) -> TreeNode {
+    let mut list = Vec::with_capacity(edits.len() + 1);
+    list.push(TreeNode::from("diff"));
+    for edit in edits.iter() {
+        let item = match *edit {
+            Edit::Insert { ref path, ref node } => {
+                alloc::vec![TreeNode::from("insert"), path_to_tree(path), node.clone()]
+            }
+            Edit::Delete { ref path } => alloc::vec![TreeNode::from("delete"), path_to_tree(path)],
+            Edit::ReplaceAtom { ref path, ref atom } => alloc::vec![
+                TreeNode::from("replace-atom"),
+                path_to_tree(path),
+                TreeNode::Atom(atom.clone()),
+            ],
+            Edit::Replace { ref path, ref node } => {
+                alloc::vec![TreeNode::from("replace"), path_to_tree(path), node.clone()]
+            }
+            Edit::Move { ref from, ref to } => {
+                alloc::vec![TreeNode::from("move"), path_to_tree(from), path_to_tree(to),]
+            }
+        };
+        list.push(TreeNode::List(item));
+    }
+    TreeNode::List(list)
+}
+
+pub(crate) fn path_to_tree(path: &[usize]) -> TreeNode {
+    let mut list = Vec::with_capacity(path.len() + 1);
+    list.push(TreeNode::from("path"));
+    list.extend(path.iter().map(|index| TreeNode::Atom(index.to_string())));
+    TreeNode::List(list)
+}
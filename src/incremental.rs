@@ -0,0 +1,191 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::task::Poll;
+
+use crate::lexer::{resume_unfinished, start_unfinished, Lexer, Token, Unfinished};
+use crate::parser::DatumCommentFilter;
+use crate::{OwnedParsedItem, ParseError, ParserOptions};
+
+/// Amount of bytes requested from the reader at once.
+const READ_SIZE: usize = 8192;
+
+/// Parser for a sequence of top-level nodes whose input arrives in
+/// pieces, shared by the readers built on top of `Read` and
+/// `AsyncRead`.
+///
+/// Only the input that has not been lexed yet is kept in memory. When a
+/// token or comment does not fit in the input read so far, the input is
+/// extended and only the new bytes are scanned until it seems to end,
+/// so reading a long token takes linear time.
+pub(crate) struct IncrementalParser {
+    options: ParserOptions,
+    buf: Vec<u8>,
+    // Amount of bytes at the beginning of `buf` that have already
+    // been lexed
+    consumed: usize,
+    // Offset of `buf[0]` in the whole input
+    buf_offset: usize,
+    eof: bool,
+    // Position in `buf` and state where scanning of an unfinished token
+    // or comment at `consumed` continues when more input is read
+    unfinished: Option<(usize, Unfinished)>,
+    filter: DatumCommentFilter,
+    depth: usize,
+    finished: bool,
+}
+
+pub(crate) enum Step {
+    Item(OwnedParsedItem),
+    /// The input has to be extended with `read_with`.
+    NeedInput,
+    /// End-of-file between top-level nodes, or after an error.
+    End,
+}
+
+impl IncrementalParser {
+    pub(crate) fn new(options: ParserOptions) -> Self {
+        Self {
+            options,
+            buf: Vec::new(),
+            consumed: 0,
+            buf_offset: 0,
+            eof: false,
+            unfinished: None,
+            filter: DatumCommentFilter::new(),
+            depth: 0,
+            finished: false,
+        }
+    }
+
+    /// Offset of the first byte that has not been lexed yet.
+    #[inline]
+    pub(crate) fn pos(&self) -> usize {
+        self.buf_offset + self.consumed
+    }
+
+    /// Offset of the end of the input that has been read so far.
+    #[inline]
+    pub(crate) fn read_end(&self) -> usize {
+        self.buf_offset + self.buf.len()
+    }
+
+    /// Makes following calls to `next_step` return `Step::End`, used
+    /// when reading fails.
+    pub(crate) fn abort(&mut self) {
+        self.finished = true;
+    }
+
+    pub(crate) fn next_step(&mut self) -> Result<Step, ParseError> {
+        while !self.finished {
+            if let Some((pos, state)) = self.unfinished.take() {
+                if !self.eof {
+                    self.unfinished = resume_unfinished(&self.buf, pos, state);
+                    if self.unfinished.is_some() {
+                        return Ok(Step::NeedInput);
+                    }
+                }
+            }
+
+            let mut lexer = Lexer::new(&self.buf[self.consumed..], self.options);
+            let result = if self.eof {
+                lexer.get_token().map(Some)
+            } else {
+                lexer.get_partial_token()
+            };
+            let offset = self.pos();
+            let (pos, token) = match result {
+                Ok(Some((pos, token))) => (pos + offset, token),
+                Ok(None) => {
+                    // Skip the whitespace and comments before the
+                    // unfinished token or comment.
+                    self.consumed += lexer.pos();
+                    self.unfinished = start_unfinished(&self.buf, self.consumed, self.options);
+                    return Ok(Step::NeedInput);
+                }
+                Err(e) => {
+                    self.finished = true;
+                    return Err(e.with_offset(offset));
+                }
+            };
+
+            let item = match self.filter.filter(pos, token) {
+                Ok(None) => None,
+                Ok(Some(Token::Eof)) => {
+                    if self.depth == 0 {
+                        self.finished = true;
+                        return Ok(Step::End);
+                    }
+                    Some(Err(ParseError::UnexpectedEof { pos }))
+                }
+                Ok(Some(Token::LeftParen)) => {
+                    self.depth += 1;
+                    Some(Ok(OwnedParsedItem::ListStart(pos)))
+                }
+                Ok(Some(Token::RightParen)) => {
+                    if self.depth == 0 {
+                        Some(Err(ParseError::UnexpectedRightParen { pos }))
+                    } else {
+                        self.depth -= 1;
+                        Some(Ok(OwnedParsedItem::ListEnd(pos)))
+                    }
+                }
+                Ok(Some(Token::Atom(atom))) => {
+                    Some(Ok(OwnedParsedItem::Atom(String::from(atom), pos)))
+                }
+                Ok(Some(Token::DatumComment)) => unreachable!(),
+                Err(e) => Some(Err(e)),
+            };
+            self.consumed += lexer.pos();
+
+            match item {
+                None => {}
+                Some(Ok(item)) => return Ok(Step::Item(item)),
+                Some(Err(e)) => {
+                    self.finished = true;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(Step::End)
+    }
+
+    /// Discards the bytes that have already been lexed and extends
+    /// the input with `read`, which has the same semantics as
+    /// `Read::read`. Reading is retried when interrupted.
+    pub(crate) fn read_with(
+        &mut self,
+        mut read: impl FnMut(&mut [u8]) -> Poll<std::io::Result<usize>>,
+    ) -> Poll<std::io::Result<()>> {
+        if self.consumed != 0 {
+            self.buf.drain(..self.consumed);
+            self.buf_offset += self.consumed;
+            if let Some((ref mut pos, _)) = self.unfinished {
+                *pos -= self.consumed;
+            }
+            self.consumed = 0;
+        }
+
+        let len = self.buf.len();
+        self.buf.resize(len + READ_SIZE, 0);
+        loop {
+            match read(&mut self.buf[len..]) {
+                Poll::Ready(Ok(n)) => {
+                    self.buf.truncate(len + n);
+                    if n == 0 {
+                        self.eof = true;
+                    }
+                    return Poll::Ready(Ok(()));
+                }
+                Poll::Ready(Err(e)) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(e)) => {
+                    self.buf.truncate(len);
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Pending => {
+                    self.buf.truncate(len);
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
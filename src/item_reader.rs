@@ -0,0 +1,48 @@
+use crate::{ParseError, ParsedItem, Parser};
+
+/// A source of [`ParsedItem`]s, such as [`Parser`] or
+/// [`BinaryReader`](crate::BinaryReader).
+///
+/// It allows [`parse_tree`](crate::parse_tree) and
+/// [`parse_tree_with`](crate::parse_tree_with) to read from either
+/// text or binary data.
+///
+/// # Example
+///
+/// ```
+/// use sise::ItemReader as _;
+///
+/// fn count_atoms<'a, R: sise::ItemReader<'a>>(reader: &mut R) -> Result<usize, R::Error> {
+///     let mut num_atoms = 0;
+///     let mut depth = 0usize;
+///     loop {
+///         match reader.next_item()? {
+///             sise::ParsedItem::Atom(..) => num_atoms += 1,
+///             sise::ParsedItem::ListStart(_) => depth += 1,
+///             sise::ParsedItem::ListEnd(_) => depth -= 1,
+///         }
+///         if depth == 0 {
+///             return Ok(num_atoms);
+///         }
+///     }
+/// }
+///
+/// let mut parser = sise::Parser::new("(a (b c) d)");
+/// assert_eq!(count_atoms(&mut parser), Ok(4));
+/// ```
+pub trait ItemReader<'a> {
+    /// The error returned when the input is not valid
+    type Error;
+
+    /// Reads the next item.
+    fn next_item(&mut self) -> Result<ParsedItem<'a>, Self::Error>;
+}
+
+impl<'a> ItemReader<'a> for Parser<'a> {
+    type Error = ParseError;
+
+    #[inline]
+    fn next_item(&mut self) -> Result<ParsedItem<'a>, ParseError> {
+        Parser::next_item(self)
+    }
+}
@@ -0,0 +1,310 @@
+use alloc::string::{String, ToString as _};
+use alloc::vec::Vec;
+
+use serde_json::{Map, Number, Value};
+
+use crate::{decode_string, encode_string, is_atom_chr, TreeNode};
+
+/// Converts a JSON value into a SISE tree.
+///
+/// The conversion follows this convention, which [`tree_to_json`]
+/// reverses:
+///
+/// * `null`, `true` and `false` are bare atoms with the same text.
+/// * Numbers are bare atoms with their JSON text.
+/// * Strings are quoted atoms encoded with [`encode_string`].
+/// * Arrays are lists of their elements.
+/// * Objects are keyword lists: each key, prefixed with `:`, is followed
+///   by its value. Keys made only of atom characters are written as
+///   they are (e.g., `:name`), other keys are encoded with
+///   [`encode_string`] (e.g., `:"first name"`). Since `()` is the empty
+///   array, the empty object is `(:)`.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let value = serde_json::json!({
+///     "name": "sise",
+///     "tags": ["s-expression", null],
+///     "version": 1.5,
+///     "first name": {},
+/// });
+/// let tree = sise::json_to_tree(&value);
+/// assert_eq!(
+///     tree,
+///     sise_tree!([
+///         ":\"first name\"",
+///         [":"],
+///         ":name",
+///         "\"sise\"",
+///         ":tags",
+///         ["\"s-expression\"", "null"],
+///         ":version",
+///         "1.5",
+///     ]),
+/// );
+/// assert_eq!(sise::tree_to_json(&tree).unwrap(), value);
+/// ```
+pub fn json_to_tree(value: &Value) -> TreeNode {
+    match *value {
+        Value::Null => TreeNode::Atom(String::from("null")),
+        Value::Bool(false) => TreeNode::Atom(String::from("false")),
+        Value::Bool(true) => TreeNode::Atom(String::from("true")),
+        Value::Number(ref number) => TreeNode::Atom(number.to_string()),
+        Value::String(ref s) => TreeNode::Atom(encode_string(s)),
+        Value::Array(ref array) => TreeNode::List(array.iter().map(json_to_tree).collect()),
+        Value::Object(ref object) => {
+            if object.is_empty() {
+                return TreeNode::List(alloc::vec![TreeNode::Atom(String::from(":"))]);
+            }
+            let mut list = Vec::with_capacity(object.len() * 2);
+            for (key, value) in object.iter() {
+                list.push(TreeNode::Atom(encode_key(key)));
+                list.push(json_to_tree(value));
+            }
+            TreeNode::List(list)
+        }
+    }
+}
+
+fn encode_key(key: &str) -> String {
+    let mut atom = String::from(":");
+    if !key.is_empty() && key.chars().all(is_atom_chr) {
+        atom.push_str(key);
+    } else {
+        atom.push_str(&encode_string(key));
+    }
+    atom
+}
+
+fn decode_key(atom: &str) -> Option<String> {
+    let key = atom.strip_prefix(':')?;
+    if key.starts_with('"') {
+        decode_string(key)
+    } else if !key.is_empty() && key.chars().all(is_atom_chr) {
+        Some(String::from(key))
+    } else {
+        None
+    }
+}
+
+/// Error returned by [`tree_to_json`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonError {
+    /// Indices of the offending node, starting from the root (e.g.,
+    /// `[2, 0]` is the first element of the third element of the root)
+    pub path: Vec<usize>,
+    pub kind: JsonErrorKind,
+}
+
+/// The kind of a [`JsonError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    /// The atom is not `null`, `true`, `false`, a number or a string
+    /// encoded with [`encode_string`]
+    InvalidAtom,
+    /// The list is a keyword list, but this element is not a key
+    InvalidKey,
+    /// The key is not followed by a value
+    MissingValue,
+    /// The key has already appeared in the same keyword list
+    DuplicateKey,
+}
+
+impl core::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.kind {
+            JsonErrorKind::InvalidAtom => f.write_str("invalid atom")?,
+            JsonErrorKind::InvalidKey => f.write_str("invalid key")?,
+            JsonErrorKind::MissingValue => f.write_str("key without value")?,
+            JsonErrorKind::DuplicateKey => f.write_str("duplicate key")?,
+        }
+        f.write_str(" at (")?;
+        for (i, index) in self.path.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", index)?;
+        }
+        f.write_str(")")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonError {}
+
+/// Converts a SISE tree into a JSON value, following the convention
+/// described at [`json_to_tree`].
+///
+/// A list is a keyword list when its first element is an atom that
+/// begins with `:`.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree = sise_tree!([":a", "1", ":b", ["true", "\"x\""]]);
+/// assert_eq!(
+///     sise::tree_to_json(&tree).unwrap(),
+///     serde_json::json!({ "a": 1, "b": [true, "x"] }),
+/// );
+///
+/// let tree = sise_tree!([":a", "1", ":b", ["true", "x"]]);
+/// assert_eq!(
+///     sise::tree_to_json(&tree).unwrap_err(),
+///     sise::JsonError {
+///         path: vec![3, 1],
+///         kind: sise::JsonErrorKind::InvalidAtom,
+///     },
+/// );
+/// ```
+pub fn tree_to_json(node: &TreeNode) -> Result<Value, JsonError> {
+    let mut path = Vec::new();
+    node_to_json(node, &mut path).map_err(|kind| JsonError { path, kind })
+}
+
+/// On error, `path` is left pointing to the offending node.
+fn node_to_json(node: &TreeNode, path: &mut Vec<usize>) -> Result<Value, JsonErrorKind> {
+    match *node {
+        TreeNode::Atom(ref atom) => atom_to_json(atom).ok_or(JsonErrorKind::InvalidAtom),
+        TreeNode::List(ref list) => {
+            if !is_keyword_list(list) {
+                let mut array = Vec::with_capacity(list.len());
+                for (i, item) in list.iter().enumerate() {
+                    path.push(i);
+                    array.push(node_to_json(item, path)?);
+                    path.pop();
+                }
+                return Ok(Value::Array(array));
+            }
+
+            let mut object = Map::new();
+            if is_empty_object(list) {
+                return Ok(Value::Object(object));
+            }
+            for (i, pair) in list.chunks(2).enumerate() {
+                path.push(i * 2);
+                let key = pair[0]
+                    .as_atom()
+                    .and_then(|atom| decode_key(atom))
+                    .ok_or(JsonErrorKind::InvalidKey)?;
+                if object.contains_key(&key) {
+                    return Err(JsonErrorKind::DuplicateKey);
+                }
+                let value = pair.get(1).ok_or(JsonErrorKind::MissingValue)?;
+                *path.last_mut().unwrap() += 1;
+                let value = node_to_json(value, path)?;
+                path.pop();
+                object.insert(key, value);
+            }
+            Ok(Value::Object(object))
+        }
+    }
+}
+
+/// Converts a SISE tree into a JSON value without failing.
+///
+/// It follows the convention described at [`json_to_tree`] where
+/// possible, and otherwise:
+///
+/// * Atoms that are not `null`, `true`, `false`, a number or a string
+///   encoded with [`encode_string`] become strings with their text.
+/// * Keyword lists with invalid keys or an odd number of elements become
+///   arrays.
+/// * When a key is repeated, the last value is kept.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree = sise_tree!(["define", "x", ["+", "1", "2"], [":a", "1", ":a", "2"]]);
+/// assert_eq!(
+///     sise::tree_to_json_lossy(&tree),
+///     serde_json::json!(["define", "x", ["+", 1, 2], { "a": 2 }]),
+/// );
+/// ```
+pub fn tree_to_json_lossy(node: &TreeNode) -> Value {
+    match *node {
+        TreeNode::Atom(ref atom) => {
+            atom_to_json(atom).unwrap_or_else(|| Value::String(atom.clone()))
+        }
+        TreeNode::List(ref list) => {
+            if is_keyword_list(list) {
+                if is_empty_object(list) {
+                    return Value::Object(Map::new());
+                }
+                if list.len() % 2 == 0 {
+                    let object: Option<Map<String, Value>> = list
+                        .chunks(2)
+                        .map(|pair| {
+                            let key = pair[0].as_atom().and_then(|atom| decode_key(atom))?;
+                            Some((key, tree_to_json_lossy(&pair[1])))
+                        })
+                        .collect();
+                    if let Some(object) = object {
+                        return Value::Object(object);
+                    }
+                }
+            }
+            Value::Array(list.iter().map(tree_to_json_lossy).collect())
+        }
+    }
+}
+
+fn is_keyword_list(list: &[TreeNode]) -> bool {
+    match list.first() {
+        Some(TreeNode::Atom(atom)) => atom.starts_with(':'),
+        _ => false,
+    }
+}
+
+fn is_empty_object(list: &[TreeNode]) -> bool {
+    list.len() == 1 && list[0] == ":"
+}
+
+fn atom_to_json(atom: &str) -> Option<Value> {
+    match atom {
+        "null" => Some(Value::Null),
+        "false" => Some(Value::Bool(false)),
+        "true" => Some(Value::Bool(true)),
+        _ if atom.starts_with('"') => decode_string(atom).map(Value::String),
+        _ => parse_number(atom).map(Value::Number),
+    }
+}
+
+fn parse_number(atom: &str) -> Option<Number> {
+    // `Number::from_str` accepts some inputs that are not JSON numbers
+    // (e.g., with a leading `+`), so check the grammar first.
+    let mut rem = atom.strip_prefix('-').unwrap_or(atom);
+    rem = match rem.strip_prefix('0') {
+        Some(rem) => rem,
+        None => skip_digits(rem, 1)?,
+    };
+    if let Some(frac) = rem.strip_prefix('.') {
+        rem = skip_digits(frac, 1)?;
+    }
+    if let Some(exp) = rem.strip_prefix(|chr| chr == 'e' || chr == 'E') {
+        let exp = exp
+            .strip_prefix(|chr| chr == '+' || chr == '-')
+            .unwrap_or(exp);
+        rem = skip_digits(exp, 1)?;
+    }
+    if !rem.is_empty() {
+        return None;
+    }
+    atom.parse().ok()
+}
+
+/// Skips at least `min` ASCII digits at the beginning of `s`.
+fn skip_digits(s: &str, min: usize) -> Option<&str> {
+    let len = s.bytes().take_while(u8::is_ascii_digit).count();
+    if len >= min {
+        Some(&s[len..])
+    } else {
+        None
+    }
+}
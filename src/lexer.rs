@@ -0,0 +1,646 @@
+//! Lexer shared by the parsers.
+//!
+//! Runs of whitespace, string characters and comment characters are
+//! scanned a word (8 bytes) at a time, using bit tricks to find the
+//! first byte of the word that ends the run, if any. Everything else
+//! is classified with a lookup table. This keeps the code portable and
+//! free of `unsafe`.
+
+use crate::{is_atom_chr, is_atom_string_chr, ParseError, ParserOptions};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    Eof,
+    LeftParen,
+    RightParen,
+    Atom(&'a str),
+    DatumComment,
+}
+
+pub(crate) struct Lexer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    options: ParserOptions,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(input: &'a [u8], options: ParserOptions) -> Self {
+        Lexer {
+            input,
+            pos: 0,
+            options,
+        }
+    }
+
+    /// Returns the position where the next token will be searched.
+    #[cfg(feature = "std")]
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves the lexer to `pos`, which must be the start of a token or
+    /// of whitespace or comments.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_pos(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    /// Like `get_token`, but for an input that might continue after
+    /// the end of the current one. Returns `Ok(None)` when the next
+    /// token cannot be determined without more input. In that case,
+    /// the whitespace and comments before it are skipped, and the lexer
+    /// is left at the beginning of the unfinished token or comment (or
+    /// at the end of the input, if there is none).
+    #[cfg(feature = "std")]
+    pub(crate) fn get_partial_token(&mut self) -> Result<Option<(usize, Token<'a>)>, ParseError> {
+        let input = self.input;
+        loop {
+            let chr_pos = self.pos;
+            match input.get(chr_pos) {
+                None => return Ok(None),
+                Some(b' ' | b'\t' | b'\n' | b'\r') => {
+                    self.pos = skip_whitespace(input, chr_pos + 1);
+                }
+                Some(b';') => {
+                    let end_pos = skip_comment_chars(input, chr_pos + 1);
+                    match input.get(end_pos) {
+                        // The comment might continue
+                        None => return Ok(None),
+                        Some(b'\n' | b'\r') => self.pos = end_pos + 1,
+                        // Let `get_token` report the error
+                        Some(_) => break,
+                    }
+                }
+                Some(b'#')
+                    if self.options.block_comments && input.get(chr_pos + 1) == Some(&b'|') =>
+                {
+                    match self.skip_block_comment(chr_pos + 2) {
+                        Ok(end_pos) => self.pos = end_pos,
+                        Err(ParseError::UnfinishedBlockComment { .. }) => return Ok(None),
+                        Err(ParseError::InvalidUtf8 { pos }) if is_split_utf8(input, pos) => {
+                            return Ok(None);
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Some(_) => break,
+            }
+        }
+
+        let start_pos = self.pos;
+        let result = self.get_token();
+        let incomplete = match result {
+            // The atom might continue
+            Ok((_, Token::Atom(_))) => self.pos == self.input.len(),
+            Ok(_) => false,
+            Err(ParseError::UnfinishedString { .. }) => true,
+            Err(ParseError::InvalidUtf8 { pos }) => is_split_utf8(input, pos),
+            Err(_) => false,
+        };
+        if incomplete {
+            self.pos = start_pos;
+            Ok(None)
+        } else {
+            result.map(Some)
+        }
+    }
+
+    /// Builds the error for an illegal character that starts at `pos`,
+    /// or `ParseError::InvalidUtf8` if there is not a valid UTF-8
+    /// character there.
+    #[cold]
+    fn illegal_chr(&self, pos: usize, make_error: fn(usize, char) -> ParseError) -> ParseError {
+        let bytes = &self.input[pos..self.input.len().min(pos + 4)];
+        let valid = match core::str::from_utf8(bytes) {
+            Ok(valid) => valid,
+            Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap(),
+        };
+        match valid.chars().next() {
+            Some(chr) => make_error(pos, chr),
+            None => ParseError::InvalidUtf8 { pos },
+        }
+    }
+
+    pub(crate) fn get_token(&mut self) -> Result<(usize, Token<'a>), ParseError> {
+        let input = self.input;
+        loop {
+            let chr_pos = self.pos;
+            let byte = match input.get(chr_pos) {
+                // end-of-file
+                None => return Ok((chr_pos, Token::Eof)),
+                Some(&byte) => byte,
+            };
+            match byte {
+                b' ' | b'\t' | b'\n' | b'\r' => {
+                    // skip whitespace
+                    self.pos = skip_whitespace(input, chr_pos + 1);
+                }
+                b';' => {
+                    // skip comments
+                    let end_pos = skip_comment_chars(input, chr_pos + 1);
+                    match input.get(end_pos) {
+                        None => {
+                            self.pos = end_pos;
+                            return Ok((end_pos, Token::Eof));
+                        }
+                        Some(b'\n' | b'\r') => self.pos = end_pos + 1,
+                        Some(_) => {
+                            return Err(self.illegal_chr(end_pos, |pos, chr| {
+                                ParseError::IllegalChrInComment { pos, chr }
+                            }));
+                        }
+                    }
+                }
+                b'(' => {
+                    self.pos += 1;
+                    return Ok((chr_pos, Token::LeftParen));
+                }
+                b')' => {
+                    self.pos += 1;
+                    return Ok((chr_pos, Token::RightParen));
+                }
+                b'#' if self.options.block_comments && input.get(chr_pos + 1) == Some(&b'|') => {
+                    self.pos = self.skip_block_comment(chr_pos + 2)?;
+                }
+                b'#' if self.options.datum_comments && input.get(chr_pos + 1) == Some(&b';') => {
+                    self.pos += 2;
+                    return Ok((chr_pos, Token::DatumComment));
+                }
+                _ if byte == b'"' || is_atom_byte(byte) => {
+                    let end_pos = self.lex_atom(chr_pos)?;
+                    self.pos = end_pos;
+                    // Atoms are made only of ASCII characters
+                    let atom = core::str::from_utf8(&input[chr_pos..end_pos]).unwrap();
+                    return Ok((chr_pos, Token::Atom(atom)));
+                }
+                _ => {
+                    // invalid character
+                    return Err(
+                        self.illegal_chr(chr_pos, |pos, chr| ParseError::IllegalChr { pos, chr })
+                    );
+                }
+            }
+        }
+    }
+
+    /// Skips a block comment whose body starts at `pos` and returns
+    /// the position after the closing `|#`.
+    fn skip_block_comment(&self, mut pos: usize) -> Result<usize, ParseError> {
+        let input = self.input;
+        let mut depth = 1;
+        loop {
+            pos = skip_block_comment_chars(input, pos);
+            match input.get(pos) {
+                None => return Err(ParseError::UnfinishedBlockComment { pos }),
+                Some(b'|') if input.get(pos + 1) == Some(&b'#') => {
+                    pos += 2;
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(pos);
+                    }
+                }
+                Some(b'#') if input.get(pos + 1) == Some(&b'|') => {
+                    pos += 2;
+                    depth += 1;
+                }
+                Some(b'|' | b'#') => pos += 1,
+                Some(_) => {
+                    return Err(self.illegal_chr(pos, |pos, chr| {
+                        ParseError::IllegalChrInComment { pos, chr }
+                    }));
+                }
+            }
+        }
+    }
+
+    /// Lexes an atom that starts at `pos` and returns its end position.
+    fn lex_atom(&self, mut pos: usize) -> Result<usize, ParseError> {
+        let input = self.input;
+        loop {
+            pos = skip_atom_chars(input, pos);
+            if input.get(pos) == Some(&b'"') {
+                pos = self.lex_string(pos + 1)?;
+            } else {
+                return Ok(pos);
+            }
+        }
+    }
+
+    /// Lexes the inside of a string that starts at `pos` and returns the
+    /// position after the closing `"`.
+    fn lex_string(&self, mut pos: usize) -> Result<usize, ParseError> {
+        let input = self.input;
+        loop {
+            pos = skip_string_chars(input, pos);
+            match input.get(pos) {
+                None => return Err(ParseError::UnfinishedString { pos }),
+                Some(b'"') => return Ok(pos + 1),
+                Some(b'\\') => match input.get(pos + 1) {
+                    None => return Err(ParseError::UnfinishedString { pos: pos + 1 }),
+                    Some(&byte) if byte == b'"' || byte == b'\\' || is_atom_string_byte(byte) => {
+                        pos += 2;
+                    }
+                    Some(_) => {
+                        return Err(self.illegal_chr(pos + 1, |pos, chr| {
+                            ParseError::IllegalChrInString { pos, chr }
+                        }));
+                    }
+                },
+                Some(_) => {
+                    return Err(self
+                        .illegal_chr(pos, |pos, chr| ParseError::IllegalChrInString { pos, chr }));
+                }
+            }
+        }
+    }
+}
+
+/// Finds the end of a list whose contents start at `pos`, and returns
+/// the position after its closing `)`, or `None` if the input ends
+/// before.
+///
+/// Only strings, comments and parentheses are taken into account,
+/// which makes it much faster than lexing, but the contents are not
+/// validated. For valid input, the result is the same as what lexing
+/// would find.
+#[cfg(feature = "std")]
+pub(crate) fn skip_list_unchecked(
+    input: &[u8],
+    mut pos: usize,
+    options: ParserOptions,
+) -> Option<usize> {
+    let mut depth = 1usize;
+    loop {
+        pos = scan(input, pos, CLASS_LIST_PLAIN, |word| {
+            non_ascii(word)
+                | equal_to(word, b'(')
+                | equal_to(word, b')')
+                | equal_to(word, b'"')
+                | equal_to(word, b';')
+                | equal_to(word, b'#')
+        });
+        match *input.get(pos)? {
+            b'(' => {
+                depth += 1;
+                pos += 1;
+            }
+            b')' => {
+                depth -= 1;
+                pos += 1;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            }
+            b'"' => pos = skip_string_unchecked(input, pos + 1)?,
+            b';' => pos = skip_comment_unchecked(input, pos + 1),
+            b'#' if options.block_comments && input.get(pos + 1) == Some(&b'|') => {
+                pos = skip_block_comment_unchecked(input, pos + 2)?;
+            }
+            _ => pos += 1,
+        }
+    }
+}
+
+/// Returns the position after the `"` that closes a string whose
+/// contents start at `pos`.
+#[cfg(feature = "std")]
+fn skip_string_unchecked(input: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        pos = skip_string_chars(input, pos);
+        match *input.get(pos)? {
+            b'"' => return Some(pos + 1),
+            b'\\' if pos + 1 < input.len() => pos += 2,
+            b'\\' => return None,
+            _ => pos += 1,
+        }
+    }
+}
+
+/// Returns the position after the line break that ends a comment whose
+/// contents start at `pos`, or the end of the input.
+#[cfg(feature = "std")]
+fn skip_comment_unchecked(input: &[u8], mut pos: usize) -> usize {
+    loop {
+        pos = skip_comment_chars(input, pos);
+        match input.get(pos) {
+            None => return pos,
+            Some(b'\n' | b'\r') => return pos + 1,
+            Some(_) => pos += 1,
+        }
+    }
+}
+
+/// Returns the position after the `|#` that closes a block comment
+/// whose body starts at `pos`.
+#[cfg(feature = "std")]
+fn skip_block_comment_unchecked(input: &[u8], mut pos: usize) -> Option<usize> {
+    let mut depth = 1usize;
+    loop {
+        pos = skip_block_comment_chars(input, pos);
+        match *input.get(pos)? {
+            b'|' if input.get(pos + 1) == Some(&b'#') => {
+                pos += 2;
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            }
+            b'#' if input.get(pos + 1) == Some(&b'|') => {
+                pos += 2;
+                depth += 1;
+            }
+            _ => pos += 1,
+        }
+    }
+}
+
+/// Whether the input ends in the middle of a UTF-8 character that
+/// starts at `pos`.
+#[cfg(feature = "std")]
+fn is_split_utf8(input: &[u8], pos: usize) -> bool {
+    core::str::from_utf8(&input[pos..])
+        .err()
+        .map_or(false, |e| e.valid_up_to() == 0 && e.error_len().is_none())
+}
+
+/// What an unfinished token or comment at the end of a partial input
+/// is in the middle of.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Unfinished {
+    Atom,
+    String,
+    Comment,
+    BlockComment { depth: usize },
+}
+
+/// Starts scanning the unfinished token or comment that begins at `pos`,
+/// where `Lexer::get_partial_token` left the lexer.
+///
+/// See `resume_unfinished`.
+#[cfg(feature = "std")]
+pub(crate) fn start_unfinished(
+    input: &[u8],
+    pos: usize,
+    options: ParserOptions,
+) -> Option<(usize, Unfinished)> {
+    match input.get(pos)? {
+        b';' => resume_unfinished(input, pos + 1, Unfinished::Comment),
+        b'#' if options.block_comments && input.get(pos + 1) == Some(&b'|') => {
+            resume_unfinished(input, pos + 2, Unfinished::BlockComment { depth: 1 })
+        }
+        _ => resume_unfinished(input, pos, Unfinished::Atom),
+    }
+}
+
+/// Continues scanning an unfinished token or comment from `pos`, in the
+/// given state, without validating it.
+///
+/// If it still reaches the end of the input, returns where to continue
+/// (and in which state) once the input is extended, so each byte is
+/// only scanned once no matter how many times the input is extended.
+/// Otherwise, returns `None`: the token or comment has probably ended,
+/// and it has to be lexed (again) from its beginning.
+#[cfg(feature = "std")]
+pub(crate) fn resume_unfinished(
+    input: &[u8],
+    mut pos: usize,
+    mut state: Unfinished,
+) -> Option<(usize, Unfinished)> {
+    loop {
+        match state {
+            Unfinished::Atom => {
+                pos = skip_atom_chars(input, pos);
+                match input.get(pos) {
+                    None => return Some((pos, state)),
+                    Some(b'"') => {
+                        pos += 1;
+                        state = Unfinished::String;
+                    }
+                    Some(_) => return None,
+                }
+            }
+            Unfinished::String => {
+                pos = skip_string_chars(input, pos);
+                match input.get(pos) {
+                    None => return Some((pos, state)),
+                    Some(b'"') => {
+                        pos += 1;
+                        state = Unfinished::Atom;
+                    }
+                    // Escaped character
+                    Some(b'\\') if pos + 1 < input.len() => pos += 2,
+                    Some(b'\\') => return Some((pos, state)),
+                    Some(_) => return None,
+                }
+            }
+            Unfinished::Comment => {
+                pos = skip_comment_chars(input, pos);
+                return if pos == input.len() {
+                    Some((pos, state))
+                } else {
+                    None
+                };
+            }
+            Unfinished::BlockComment { depth } => {
+                pos = skip_block_comment_chars(input, pos);
+                match input.get(pos) {
+                    None => return Some((pos, state)),
+                    // Might be the beginning of `|#` or `#|`
+                    Some(b'|' | b'#') if pos + 1 == input.len() => return Some((pos, state)),
+                    Some(b'|') if input[pos + 1] == b'#' => {
+                        if depth == 1 {
+                            return None;
+                        }
+                        pos += 2;
+                        state = Unfinished::BlockComment { depth: depth - 1 };
+                    }
+                    Some(b'#') if input[pos + 1] == b'|' => {
+                        pos += 2;
+                        state = Unfinished::BlockComment { depth: depth + 1 };
+                    }
+                    Some(b'|' | b'#') => pos += 1,
+                    Some(_) => return None,
+                }
+            }
+        }
+    }
+}
+
+const CLASS_WHITESPACE: u8 = 1 << 0;
+const CLASS_ATOM: u8 = 1 << 1;
+const CLASS_STRING: u8 = 1 << 2;
+const CLASS_COMMENT: u8 = 1 << 3;
+const CLASS_BLOCK_COMMENT: u8 = 1 << 4;
+/// Bytes that do not affect the nesting of lists, outside strings and
+/// comments.
+const CLASS_LIST_PLAIN: u8 = 1 << 5;
+
+static BYTE_CLASSES: [u8; 256] = byte_classes();
+
+const fn byte_classes() -> [u8; 256] {
+    let mut classes = [0; 256];
+    let mut i = 0;
+    while i < 128 {
+        let byte = i as u8;
+        let mut class = 0;
+        if matches!(byte, b' ' | b'\t' | b'\n' | b'\r') {
+            class |= CLASS_WHITESPACE;
+        }
+        if is_atom_chr(byte as char) {
+            class |= CLASS_ATOM;
+        }
+        if is_atom_string_chr(byte as char) {
+            class |= CLASS_STRING;
+        }
+        if matches!(byte, b'\t' | b' '..=b'~') {
+            class |= CLASS_COMMENT;
+        }
+        if matches!(byte, b'\t' | b'\n' | b'\r' | b' '..=b'~') && byte != b'|' && byte != b'#' {
+            class |= CLASS_BLOCK_COMMENT;
+        }
+        if !matches!(byte, b'(' | b')' | b'"' | b';' | b'#') {
+            class |= CLASS_LIST_PLAIN;
+        }
+        classes[i] = class;
+        i += 1;
+    }
+    classes
+}
+
+#[inline]
+pub(crate) fn is_atom_byte(byte: u8) -> bool {
+    BYTE_CLASSES[usize::from(byte)] & CLASS_ATOM != 0
+}
+
+#[inline]
+pub(crate) fn is_atom_string_byte(byte: u8) -> bool {
+    BYTE_CLASSES[usize::from(byte)] & CLASS_STRING != 0
+}
+
+const WORD_LEN: usize = 8;
+const ONES: u64 = u64::from_ne_bytes([0x01; WORD_LEN]);
+const LOWS: u64 = u64::from_ne_bytes([0x7F; WORD_LEN]);
+const HIGHS: u64 = u64::from_ne_bytes([0x80; WORD_LEN]);
+
+#[inline]
+fn repeat_byte(byte: u8) -> u64 {
+    ONES * u64::from(byte)
+}
+
+// The following functions return a mask with the highest bit of each
+// byte of `word` that meets a condition set, and the other bits clear.
+// They are exact: unlike the usual tricks to find whether any byte
+// meets the condition, bytes do not affect each other, so the position
+// of the first one is known without checking the word again.
+
+/// Bytes that are 128 or greater.
+#[cfg(feature = "std")]
+#[inline]
+fn non_ascii(word: u64) -> u64 {
+    word & HIGHS
+}
+
+/// Bytes that are less than `n` (`n <= 128`).
+#[inline]
+fn less_than(word: u64, n: u8) -> u64 {
+    !(((word & LOWS) + repeat_byte(128 - n)) | word) & HIGHS
+}
+
+/// Bytes that are greater than `n` (`n <= 127`).
+#[inline]
+fn greater_than(word: u64, n: u8) -> u64 {
+    (((word & LOWS) + repeat_byte(127 - n)) | word) & HIGHS
+}
+
+/// Bytes that are equal to `byte`.
+#[inline]
+fn equal_to(word: u64, byte: u8) -> u64 {
+    less_than(word ^ repeat_byte(byte), 1)
+}
+
+/// Returns the position of the first byte at or after `pos` that is not
+/// in `class`.
+///
+/// `stops` must return the mask of the bytes of a word that are not in
+/// `class`, as the functions above do.
+#[inline(always)]
+fn scan(input: &[u8], mut pos: usize, class: u8, stops: impl Fn(u64) -> u64) -> usize {
+    while let Some(chunk) = input.get(pos..(pos + WORD_LEN)) {
+        let word = u64::from_le_bytes(<[u8; WORD_LEN]>::try_from(chunk).unwrap());
+        let mask = stops(word);
+        if mask != 0 {
+            // The first byte of the input is the lowest one of `word`.
+            return pos + (mask.trailing_zeros() / 8) as usize;
+        }
+        pos += WORD_LEN;
+    }
+
+    while let Some(&byte) = input.get(pos) {
+        if BYTE_CLASSES[usize::from(byte)] & class == 0 {
+            break;
+        }
+        pos += 1;
+    }
+    pos
+}
+
+fn skip_whitespace(input: &[u8], pos: usize) -> usize {
+    // Runs are often empty or a single space between atoms, so check
+    // the first two bytes alone.
+    let is_whitespace = |pos: usize| match input.get(pos) {
+        Some(&byte) => BYTE_CLASSES[usize::from(byte)] & CLASS_WHITESPACE != 0,
+        None => false,
+    };
+    if !is_whitespace(pos) {
+        return pos;
+    }
+    if !is_whitespace(pos + 1) {
+        return pos + 1;
+    }
+    scan(input, pos + 2, CLASS_WHITESPACE, |word| {
+        // Indentation is the most common case
+        if word == repeat_byte(b' ') {
+            return 0;
+        }
+        let whitespace = equal_to(word, b' ')
+            | equal_to(word, b'\n')
+            | equal_to(word, b'\t')
+            | equal_to(word, b'\r');
+        !whitespace & HIGHS
+    })
+}
+
+fn skip_atom_chars(input: &[u8], mut pos: usize) -> usize {
+    // Atoms are usually short, so check byte by byte.
+    while let Some(&byte) = input.get(pos) {
+        if !is_atom_byte(byte) {
+            break;
+        }
+        pos += 1;
+    }
+    pos
+}
+
+fn skip_string_chars(input: &[u8], pos: usize) -> usize {
+    scan(input, pos, CLASS_STRING, |word| {
+        less_than(word, b' ')
+            | greater_than(word, b'~')
+            | equal_to(word, b'"')
+            | equal_to(word, b'\\')
+    })
+}
+
+fn skip_comment_chars(input: &[u8], pos: usize) -> usize {
+    scan(input, pos, CLASS_COMMENT, |word| {
+        (less_than(word, b' ') & !equal_to(word, b'\t')) | greater_than(word, b'~')
+    })
+}
+
+fn skip_block_comment_chars(input: &[u8], pos: usize) -> usize {
+    scan(input, pos, CLASS_BLOCK_COMMENT, |word| {
+        let controls = less_than(word, b' ')
+            & !(equal_to(word, b'\t') | equal_to(word, b'\n') | equal_to(word, b'\r'));
+        controls | greater_than(word, b'~') | equal_to(word, b'|') | equal_to(word, b'#')
+    })
+}
@@ -22,19 +22,72 @@ extern crate std;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "async")]
+mod async_parser;
+mod atom;
+mod atom_encoding;
+mod binary;
+mod canonical;
+mod checked_tree;
+#[cfg(feature = "std")]
+mod codec;
+mod csexp;
+mod cursor;
+mod diff;
+#[cfg(feature = "std")]
+mod incremental;
+mod item_reader;
+#[cfg(feature = "json")]
+mod json;
+mod lexer;
+mod list_builder;
+mod merge;
+mod owned_item;
+#[cfg(feature = "std")]
+mod parallel;
 mod parse_tree;
 mod parser;
+mod patch;
+mod replay;
+mod schema;
 mod serialize_tree;
 mod serializer;
+mod tokenizer;
 mod tree;
+mod tree_builder;
 mod util;
 
-pub use parse_tree::parse_tree;
-pub use parser::{ParseError, ParsedItem, Parser};
+#[cfg(feature = "async")]
+pub use async_parser::{AsyncParseError, AsyncParser, AsyncTreeParser};
+pub use atom::{Atom, AtomStr};
+pub use atom_encoding::{decode_string, encode_string, suggest_atom, Quoted};
+pub use binary::{to_binary, BinaryError, BinaryOptions, BinaryReader};
+pub use canonical::{canonicalize, canonicalize_tree, canonicalize_with, hash_tree};
+pub use checked_tree::{CheckedTreeError, CheckedTreeNode};
+#[cfg(feature = "std")]
+pub use codec::{CodecError, MessageReader, MessageWriter, DEFAULT_MAX_MESSAGE_SIZE};
+pub use csexp::{from_csexp, from_csexp_transport, to_csexp, to_csexp_transport, CsexpError};
+pub use cursor::Cursor;
+pub use diff::{diff, diff_to_tree, Edit};
+pub use item_reader::ItemReader;
+#[cfg(feature = "json")]
+pub use json::{json_to_tree, tree_to_json, tree_to_json_lossy, JsonError, JsonErrorKind};
+pub use list_builder::{ListBuilder, SerializerSink};
+pub use merge::{merge, ListStrategy, MergeConflict, MergeOptions, MergeResult, Provenance};
+pub use owned_item::{record_node, OwnedParsedItem};
+#[cfg(feature = "std")]
+pub use parallel::parse_trees_parallel;
+pub use parse_tree::{parse_tree, parse_tree_with};
+pub use parser::{ParseError, ParsedItem, Parser, ParserOptions};
+pub use patch::{apply_patch, Patch, PatchError, PatchErrorKind, PatchFormatError, PatchOp};
+pub use replay::{replay_into_builder, replay_into_serializer, replay_into_tree};
+pub use schema::{Schema, SchemaError, SchemaErrorKind, Violation, ViolationKind};
 pub use serialize_tree::serialize_tree;
 pub use serializer::{Serializer, SerializerStyle};
-pub use tree::TreeNode;
-pub use util::{check_atom, is_atom_chr, is_atom_string_chr};
+pub use tokenizer::{Token, TokenKind, Tokenizer};
+pub use tree::{from_str, to_string_pretty, Entries, TreeNode};
+pub use tree_builder::{TreeBuilder, TreeNodeBuilder};
+pub use util::{check_atom, is_atom_chr, is_atom_string_chr, validate_atom, AtomError};
 
 /// Macro to define trees of nodes with a lighter syntax.
 ///
@@ -75,12 +128,55 @@ pub use util::{check_atom, is_atom_chr, is_atom_string_chr};
 /// let value2 = sise_tree!(["atom", ["1", "2", "3"], ["a", "b", "c"]]);
 /// assert_eq!(value1, value2);
 /// ```
+///
+/// Any expression whose value implements `Into<TreeNode>` can be used
+/// as an element by enclosing it in parentheses. Integers and booleans
+/// are converted to atoms, and [`Quoted`] encodes a string as a quoted
+/// atom. `..expr` inserts all the elements of an iterator of values
+/// that implement `Into<TreeNode>`.
+///
+/// ```
+/// use sise::{sise_tree, Quoted};
+///
+/// let port = 8080;
+/// let hosts = vec!["a", "b"];
+/// let flags = [sise_tree!(["debug"]), sise_tree!(["verbose", (true)])];
+/// let tree = sise_tree!([
+///     "server",
+///     ["name", (Quoted("main server"))],
+///     ["port", (port)],
+///     ["hosts", ..hosts],
+///     ..flags,
+/// ]);
+/// assert_eq!(
+///     tree.to_string(),
+///     "(server (name \"main server\") (port 8080) (hosts a b) (debug) (verbose true))",
+/// );
+/// ```
 #[macro_export]
 macro_rules! sise_tree {
-    ([$($item:tt),*]) => { $crate::TreeNode::List($crate::__vec![$($crate::sise_tree!($item)),*]) };
-    ([$($item:tt,)*]) => { $crate::TreeNode::List($crate::__vec![$($crate::sise_tree!($item)),*]) };
+    ([]) => { $crate::TreeNode::List($crate::__vec::Vec::new()) };
+    ([$($item:tt),+ $(,)?]) => {
+        $crate::TreeNode::List($crate::__vec![$($crate::sise_tree!($item)),+])
+    };
+    ([$($first:tt $($splice:expr)?),+ $(,)?]) => {{
+        let mut list = $crate::__vec::Vec::<$crate::TreeNode>::new();
+        $($crate::__sise_tree_item!(list; $first $($splice)?);)+
+        $crate::TreeNode::List(list)
+    }};
     ($node:expr) => { $crate::TreeNode::from($node) };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __sise_tree_item {
+    ($list:ident; .. $items:expr) => {
+        $list.extend(::core::iter::IntoIterator::into_iter($items).map($crate::TreeNode::from));
+    };
+    ($list:ident; $item:tt) => {
+        $list.push($crate::sise_tree!($item));
+    };
+}
+
 #[doc(hidden)]
 pub use alloc::vec as __vec;
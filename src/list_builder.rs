@@ -0,0 +1,217 @@
+use alloc::string::ToString as _;
+
+use crate::{check_atom, encode_string, Serializer, TreeBuilder, TreeNode, TreeNodeBuilder};
+
+/// Builds a list fluently, validating atoms as they are added.
+///
+/// Nodes are passed to a [`TreeBuilder`] as soon as they are added, so
+/// the list can be built as a [`TreeNode`] (with [`ListBuilder::new`])
+/// or written directly into a [`Serializer`] (with [`SerializerSink`])
+/// without building an intermediate tree. The builder begins the list
+/// when it is created and ends it in [`build`](Self::build).
+///
+/// # Example
+///
+/// ```
+/// use sise::{sise_tree, ListBuilder};
+///
+/// let tree = ListBuilder::new()
+///     .atom("define")
+///     .quoted("name")
+///     .list(|l| l.atom("port").int(-80))
+///     .list(|l| l.atom("debug").bool(true))
+///     .build();
+/// assert_eq!(
+///     tree,
+///     sise_tree!(["define", "\"name\"", ["port", "-80"], ["debug", "true"]]),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct ListBuilder<B = TreeNodeBuilder> {
+    sink: B,
+}
+
+impl ListBuilder {
+    /// Creates a builder that builds a [`TreeNode`].
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_sink(TreeNodeBuilder::new())
+    }
+}
+
+impl Default for ListBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B> ListBuilder<B>
+where
+    B: for<'x> TreeBuilder<'x>,
+{
+    /// Creates a builder that passes the nodes to `sink`.
+    pub fn with_sink(mut sink: B) -> Self {
+        sink.begin_list(0);
+        Self { sink }
+    }
+
+    /// Adds an atom.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `atom` is not a valid atom.
+    pub fn atom(mut self, atom: &str) -> Self {
+        assert!(check_atom(atom), "invalid atom {:?}", atom);
+        self.sink.atom(atom, 0);
+        self
+    }
+
+    /// Adds an arbitrary string, encoded as a quoted atom with
+    /// [`encode_string`].
+    pub fn quoted(mut self, s: &str) -> Self {
+        self.sink.atom(&encode_string(s), 0);
+        self
+    }
+
+    /// Adds a signed integer as a decimal atom.
+    pub fn int(mut self, value: i64) -> Self {
+        self.sink.atom(&value.to_string(), 0);
+        self
+    }
+
+    /// Adds an unsigned integer as a decimal atom.
+    pub fn uint(mut self, value: u64) -> Self {
+        self.sink.atom(&value.to_string(), 0);
+        self
+    }
+
+    /// Adds a boolean as `true` or `false`.
+    pub fn bool(mut self, value: bool) -> Self {
+        self.sink.atom(if value { "true" } else { "false" }, 0);
+        self
+    }
+
+    /// Adds a nested list, whose elements are added by `f`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::{sise_tree, ListBuilder};
+    ///
+    /// let hosts = ["a", "b"];
+    /// let tree = ListBuilder::new()
+    ///     .atom("hosts")
+    ///     .list(|mut l| {
+    ///         for host in hosts.iter() {
+    ///             l = l.quoted(host);
+    ///         }
+    ///         l
+    ///     })
+    ///     .build();
+    /// assert_eq!(tree, sise_tree!(["hosts", ["\"a\"", "\"b\""]]));
+    /// ```
+    pub fn list<F: FnOnce(Self) -> Self>(mut self, f: F) -> Self {
+        self.sink.begin_list(0);
+        let mut this = f(self);
+        this.sink.end_list(0);
+        this
+    }
+
+    /// Adds an existing tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree contains an invalid atom.
+    pub fn node(mut self, node: &TreeNode) -> Self {
+        self.push_node(node);
+        self
+    }
+
+    fn push_node(&mut self, node: &TreeNode) {
+        match *node {
+            TreeNode::Atom(ref atom) => {
+                assert!(check_atom(atom), "invalid atom {:?}", atom);
+                self.sink.atom(atom, 0);
+            }
+            TreeNode::List(ref list) => {
+                self.sink.begin_list(0);
+                for item in list.iter() {
+                    self.push_node(item);
+                }
+                self.sink.end_list(0);
+            }
+        }
+    }
+
+    /// Ends the list and returns the output of the sink.
+    pub fn build(mut self) -> <B as TreeBuilder<'static>>::Output {
+        self.sink.end_list(0);
+        self.sink.finish()
+    }
+}
+
+/// A [`TreeBuilder`] that writes the nodes into a [`Serializer`],
+/// placing line breaks as [`serialize_tree`](crate::serialize_tree)
+/// does.
+///
+/// # Example
+///
+/// ```
+/// let style = sise::SerializerStyle {
+///     line_break: "\n",
+///     indentation: " ",
+/// };
+///
+/// let mut result = String::new();
+/// let mut serializer = sise::Serializer::new(style, &mut result);
+/// sise::ListBuilder::with_sink(sise::SerializerSink::new(&mut serializer, 12))
+///     .atom("example")
+///     .list(|l| l.int(1).int(2))
+///     .list(|l| l.atom("a").atom("b"))
+///     .build();
+/// serializer.finish(false);
+///
+/// assert_eq!(result, "(example (1 2)\n (a b)\n)");
+/// ```
+pub struct SerializerSink<'s, 'a, 'b> {
+    serializer: &'s mut Serializer<'a, 'b>,
+    break_line_at: usize,
+    list_beginning: bool,
+}
+
+impl<'s, 'a, 'b> SerializerSink<'s, 'a, 'b> {
+    #[inline]
+    pub fn new(serializer: &'s mut Serializer<'a, 'b>, break_line_at: usize) -> Self {
+        Self {
+            serializer,
+            break_line_at,
+            list_beginning: false,
+        }
+    }
+}
+
+impl<'x> TreeBuilder<'x> for SerializerSink<'_, '_, '_> {
+    type Output = ();
+
+    fn atom(&mut self, atom: &'x str, _pos: usize) {
+        if self.list_beginning {
+            self.serializer.put_atom(atom, usize::MAX);
+        } else {
+            self.serializer.put_atom(atom, self.break_line_at);
+        }
+        self.list_beginning = false;
+    }
+
+    fn begin_list(&mut self, _pos: usize) {
+        self.serializer.begin_list(self.break_line_at);
+        self.list_beginning = true;
+    }
+
+    fn end_list(&mut self, _pos: usize) {
+        self.serializer.end_list();
+        self.list_beginning = false;
+    }
+
+    fn finish(self) {}
+}
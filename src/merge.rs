@@ -0,0 +1,241 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::TreeNode;
+
+/// How [`merge`] combines two lists that are not merged by key.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ListStrategy {
+    /// The list from the later layer replaces the earlier one.
+    Replace,
+    /// The elements of the list from the later layer are appended to
+    /// the earlier one. If both lists begin with the same atom, it is
+    /// not repeated.
+    Append,
+}
+
+/// Options for [`merge`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MergeOptions {
+    pub list_strategy: ListStrategy,
+    /// Whether to return the [`Provenance`] of the nodes of the result.
+    pub record_provenance: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            list_strategy: ListStrategy::Replace,
+            record_provenance: false,
+        }
+    }
+}
+
+/// A node of the result of [`merge`] that has been replaced by a
+/// different node from a later layer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// Indices of the node in the merged tree
+    pub path: Vec<usize>,
+    /// Index of the layer that replaced the node
+    pub layer: usize,
+}
+
+/// Records which layer each node of the result of [`merge`] comes from.
+///
+/// It has the same shape as the merged tree: `children` has an element
+/// for each element of a list, and it is empty for atoms. A list that
+/// has been merged by key comes from the first layer where it appears,
+/// while its elements can come from later layers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    pub layer: usize,
+    pub children: Vec<Provenance>,
+}
+
+impl Provenance {
+    fn uniform(node: &TreeNode, layer: usize) -> Self {
+        let children = match *node {
+            TreeNode::Atom(_) => Vec::new(),
+            TreeNode::List(ref list) => {
+                list.iter().map(|item| Self::uniform(item, layer)).collect()
+            }
+        };
+        Self { layer, children }
+    }
+
+    /// Returns the provenance of the node at `path`, with the indices
+    /// used by [`TreeNode::index_path`].
+    pub fn index_path(&self, path: &[usize]) -> Option<&Self> {
+        let mut current = self;
+        for &index in path.iter() {
+            current = current.children.get(index)?;
+        }
+        Some(current)
+    }
+}
+
+/// Result of [`merge`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeResult {
+    pub tree: TreeNode,
+    /// Nodes that have been replaced, in the order in which they were
+    /// found
+    pub conflicts: Vec<MergeConflict>,
+    /// Only present if [`MergeOptions::record_provenance`] is set
+    pub provenance: Option<Provenance>,
+}
+
+/// Merges configuration layers, where each layer overrides the
+/// previous ones.
+///
+/// Keyword lists, whose first element is an atom key and whose other
+/// elements are lists that also begin with an atom key (e.g.,
+/// `(server (host a) (port 80))`), are merged recursively when they
+/// have the same key: each entry of the later list is merged with the
+/// entry of the earlier list with the same key, or appended if there is
+/// none. When a key appears more than once in a list, the n-th entry
+/// with that key is merged with the n-th entry with that key of the
+/// earlier list. Entries are found by key in O(log n) time. Other lists
+/// are combined according to [`MergeOptions::list_strategy`], and atoms
+/// are replaced.
+///
+/// # Panics
+///
+/// Panics if `layers` is empty.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let base = sise_tree!(["server", ["host", "a"], ["port", "80"], ["flags", "x"]]);
+/// let local = sise_tree!(["server", ["port", "8080"], ["debug"]]);
+///
+/// let result = sise::merge(&[base, local], sise::MergeOptions::default());
+/// assert_eq!(
+///     result.tree,
+///     sise_tree!(["server", ["host", "a"], ["port", "8080"], ["flags", "x"], ["debug"]]),
+/// );
+/// assert_eq!(
+///     result.conflicts,
+///     [sise::MergeConflict { path: vec![2], layer: 1 }],
+/// );
+/// ```
+pub fn merge(layers: &[TreeNode], options: MergeOptions) -> MergeResult {
+    let (first, rest) = layers.split_first().expect("no layers to merge");
+    let mut tree = first.clone();
+    let mut provenance = Provenance::uniform(first, 0);
+    let mut conflicts = Vec::new();
+    let mut path = Vec::new();
+    for (i, layer) in rest.iter().enumerate() {
+        merge_node(
+            &mut tree,
+            &mut provenance,
+            layer,
+            i + 1,
+            options.list_strategy,
+            &mut path,
+            &mut conflicts,
+        );
+    }
+    MergeResult {
+        tree,
+        conflicts,
+        provenance: if options.record_provenance {
+            Some(provenance)
+        } else {
+            None
+        },
+    }
+}
+
+fn merge_node(
+    base: &mut TreeNode,
+    base_provenance: &mut Provenance,
+    overlay: &TreeNode,
+    layer: usize,
+    list_strategy: ListStrategy,
+    path: &mut Vec<usize>,
+    conflicts: &mut Vec<MergeConflict>,
+) {
+    let base_key = keyword_list_key(base);
+    if base_key.is_some() && base_key == keyword_list_key(overlay) {
+        let base_list = base.as_mut_list().unwrap();
+        let overlay_list = overlay.as_list().unwrap();
+        // Indices of the entries of the base list with each key, in
+        // reverse order, so the next one to match is the last one.
+        let mut base_entries: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, entry) in base_list.iter().enumerate().skip(1).rev() {
+            let key = entry.head().unwrap();
+            match base_entries.get_mut(key) {
+                Some(indices) => indices.push(i),
+                None => {
+                    base_entries.insert(String::from(key), alloc::vec![i]);
+                }
+            }
+        }
+        for entry in overlay_list[1..].iter() {
+            let key = entry.head().unwrap();
+            let found = base_entries.get_mut(key).and_then(Vec::pop);
+            if let Some(i) = found {
+                path.push(i);
+                merge_node(
+                    &mut base_list[i],
+                    &mut base_provenance.children[i],
+                    entry,
+                    layer,
+                    list_strategy,
+                    path,
+                    conflicts,
+                );
+                path.pop();
+            } else {
+                base_list.push(entry.clone());
+                base_provenance
+                    .children
+                    .push(Provenance::uniform(entry, layer));
+            }
+        }
+        return;
+    }
+
+    match (base, overlay) {
+        (&mut TreeNode::List(ref mut base_list), TreeNode::List(overlay_list))
+            if list_strategy == ListStrategy::Append =>
+        {
+            let skip = match (base_list.first(), overlay_list.first()) {
+                (Some(TreeNode::Atom(a)), Some(TreeNode::Atom(b))) if a == b => 1,
+                _ => 0,
+            };
+            for item in overlay_list[skip..].iter() {
+                base_list.push(item.clone());
+                base_provenance
+                    .children
+                    .push(Provenance::uniform(item, layer));
+            }
+        }
+        (base, overlay) => {
+            if *base != *overlay {
+                conflicts.push(MergeConflict {
+                    path: path.clone(),
+                    layer,
+                });
+                *base = overlay.clone();
+                *base_provenance = Provenance::uniform(overlay, layer);
+            }
+        }
+    }
+}
+
+/// Returns the key of a keyword list.
+fn keyword_list_key(node: &TreeNode) -> Option<&str> {
+    let key = node.head()?;
+    let list = node.as_list()?;
+    if list[1..].iter().all(|entry| entry.head().is_some()) {
+        Some(key)
+    } else {
+        None
+    }
+}
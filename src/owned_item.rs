@@ -0,0 +1,108 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{ParseError, ParsedItem, Parser};
+
+/// Owned version of [`ParsedItem`], which does not borrow from the
+/// input.
+///
+/// # Example
+///
+/// ```
+/// let item = sise::ParsedItem::Atom("atom", 1);
+/// let owned_item = sise::OwnedParsedItem::from(item);
+/// assert_eq!(owned_item, sise::OwnedParsedItem::Atom(String::from("atom"), 1));
+/// assert_eq!(owned_item.as_item(), item);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OwnedParsedItem {
+    /// An atom
+    ///
+    /// The `usize` specifies its byte offset in the input file
+    Atom(String, usize),
+    /// The start of a list (`(`)
+    ///
+    /// The `usize` specifies its byte offset in the input file
+    ListStart(usize),
+    /// The end of a list (`)`)
+    ///
+    /// The `usize` specifies its byte offset in the input file
+    ListEnd(usize),
+}
+
+impl OwnedParsedItem {
+    /// Returns a `ParsedItem` that borrows from `self`.
+    #[inline]
+    pub fn as_item(&self) -> ParsedItem<'_> {
+        match *self {
+            Self::Atom(ref atom, pos) => ParsedItem::Atom(atom, pos),
+            Self::ListStart(pos) => ParsedItem::ListStart(pos),
+            Self::ListEnd(pos) => ParsedItem::ListEnd(pos),
+        }
+    }
+}
+
+impl<'a> From<ParsedItem<'a>> for OwnedParsedItem {
+    #[inline]
+    fn from(item: ParsedItem<'a>) -> Self {
+        match item {
+            ParsedItem::Atom(atom, pos) => Self::Atom(String::from(atom), pos),
+            ParsedItem::ListStart(pos) => Self::ListStart(pos),
+            ParsedItem::ListEnd(pos) => Self::ListEnd(pos),
+        }
+    }
+}
+
+/// Records the items of the next node (an atom or a whole list)
+/// from `parser`.
+///
+/// Like [`parse_tree`](crate::parse_tree), it does not consume the
+/// parser, so it can also be used to record a sub-tree.
+///
+/// # Panics
+///
+/// Panics if the next item is the end of a list, since there is no
+/// node to record.
+///
+/// # Example
+///
+/// ```
+/// use sise::OwnedParsedItem;
+///
+/// let data = "(test (1 2))";
+/// let mut parser = sise::Parser::new(data);
+/// let items = sise::record_node(&mut parser).unwrap();
+/// parser.finish().unwrap();
+/// assert_eq!(
+///     items,
+///     [
+///         OwnedParsedItem::ListStart(0),
+///         OwnedParsedItem::Atom(String::from("test"), 1),
+///         OwnedParsedItem::ListStart(6),
+///         OwnedParsedItem::Atom(String::from("1"), 7),
+///         OwnedParsedItem::Atom(String::from("2"), 9),
+///         OwnedParsedItem::ListEnd(10),
+///         OwnedParsedItem::ListEnd(11),
+///     ],
+/// );
+/// ```
+pub fn record_node(parser: &mut Parser<'_>) -> Result<Vec<OwnedParsedItem>, ParseError> {
+    if let ParsedItem::ListEnd(_) = parser.peek_item()? {
+        panic!("no node to record");
+    }
+
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    loop {
+        let item = parser.next_item()?;
+        match item {
+            ParsedItem::Atom(..) => {}
+            ParsedItem::ListStart(_) => depth += 1,
+            ParsedItem::ListEnd(_) => depth -= 1,
+        }
+        items.push(OwnedParsedItem::from(item));
+        if depth == 0 {
+            return Ok(items);
+        }
+    }
+}
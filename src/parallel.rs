@@ -0,0 +1,247 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::lexer::{skip_list_unchecked, Lexer, Token};
+use crate::parser::DatumCommentFilter;
+use crate::{parse_tree, ParseError, Parser, ParserOptions, TreeNode};
+
+/// Parses a file made of a sequence of top-level nodes (records) using
+/// up to `num_threads` threads.
+///
+/// The file is scanned first to find where each record begins. This
+/// scan runs on a single thread, but it only looks at parentheses,
+/// strings and comments, so it is several times faster than parsing.
+/// Then, the records are split into groups of similar size, which are
+/// validated and parsed concurrently. The nodes are returned in the
+/// same order as they appear in the file.
+///
+/// If the file is not valid, the error is the same one that parsing the
+/// records one after another would return (i.e., the first one in the
+/// file), and its byte offset is relative to the start of `data`. All
+/// the threads have finished when it returns.
+///
+/// Unlike [`Parser`], which expects exactly one root node, a file with
+/// no records (e.g., empty or only with comments) is valid and results
+/// in an empty vector.
+///
+/// Since threads cannot borrow `data`, each group but the first one is
+/// copied before being handed to its thread.
+///
+/// # Panics
+///
+/// Panics if `num_threads` is zero.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let data = "(a 1)\n(b \"(\") ; )\nc\n";
+/// let nodes = sise::parse_trees_parallel(data, sise::ParserOptions::default(), 2).unwrap();
+/// assert_eq!(
+///     nodes,
+///     [sise_tree!(["a", "1"]), sise_tree!(["b", "\"(\""]), sise_tree!("c")],
+/// );
+///
+/// let data = "(a 1)\n(b [)\n";
+/// assert_eq!(
+///     sise::parse_trees_parallel(data, sise::ParserOptions::default(), 2),
+///     Err(sise::ParseError::IllegalChr { pos: 9, chr: '[' }),
+/// );
+///
+/// assert_eq!(
+///     sise::parse_trees_parallel("", sise::ParserOptions::default(), 2),
+///     Ok(Vec::new()),
+/// );
+/// ```
+pub fn parse_trees_parallel(
+    data: &str,
+    options: ParserOptions,
+    num_threads: usize,
+) -> Result<Vec<TreeNode>, ParseError> {
+    assert!(num_threads != 0, "number of threads must not be zero");
+
+    let records = match split_records(data, options) {
+        Some(records) => records,
+        None => {
+            // The file is not valid, find the error.
+            let records = validate_records(data, options)?;
+            return parse_records(data, &records, 0, options);
+        }
+    };
+    let groups = group_records(&records, data.len(), num_threads);
+    if groups.len() <= 1 {
+        return parse_records(data, &records, 0, options);
+    }
+
+    let mut groups = groups.into_iter();
+    let first_group = groups.next().unwrap();
+    let handles: Vec<_> = groups
+        .map(|group| {
+            let group_records = &records[group];
+            let offset = group_records[0].start;
+            let end = group_records[group_records.len() - 1].end;
+            let group_data = String::from(&data[offset..end]);
+            let group_records: Vec<_> = group_records
+                .iter()
+                .map(|record| (record.start - offset)..(record.end - offset))
+                .collect();
+            std::thread::spawn(move || parse_records(&group_data, &group_records, offset, options))
+        })
+        .collect();
+
+    // Every thread is joined, even after an error, so none of them keeps
+    // running after returning.
+    let mut result = parse_records(data, &records[first_group], 0, options);
+    let mut first_panic = None;
+    for handle in handles {
+        match handle.join() {
+            Ok(group_result) => match (&mut result, group_result) {
+                (Ok(nodes), Ok(group_nodes)) => nodes.extend(group_nodes),
+                (Ok(_), Err(e)) => result = Err(e),
+                (Err(_), _) => {}
+            },
+            Err(panic) => {
+                if first_panic.is_none() {
+                    first_panic = Some(panic);
+                }
+            }
+        }
+    }
+    if let Some(panic) = first_panic {
+        std::panic::resume_unwind(panic);
+    }
+    result
+}
+
+/// Returns the byte range of each record, without validating the
+/// contents of lists, or `None` if the file is found not to be valid.
+///
+/// The range of a record includes the comments and whitespace that
+/// follow it (and, for the first one, those that precede it), so the
+/// ranges cover the whole file. Nodes commented out with `#;` at the
+/// top level are kept together with the previous record.
+///
+/// Everything at the top level is lexed normally, and lists are skipped
+/// with `skip_list_unchecked`. For a valid file, the ranges are the same
+/// ones that `validate_records` would return. For a file that is not
+/// valid, parsing each range reports the first error of the file in the
+/// range that contains it, since the ranges before it are right.
+fn split_records(data: &str, options: ParserOptions) -> Option<Vec<Range<usize>>> {
+    let input = data.as_bytes();
+    let mut lexer = Lexer::new(input, options);
+    let mut starts = Vec::new();
+    // Number of top-level nodes that are commented out with `#;`
+    let mut pending_skips = 0usize;
+    loop {
+        let (pos, token) = lexer.get_token().ok()?;
+        match token {
+            Token::Eof => {
+                if pending_skips != 0 {
+                    return None;
+                }
+                break;
+            }
+            Token::LeftParen | Token::Atom(_) => {
+                if pending_skips == 0 {
+                    starts.push(pos);
+                } else {
+                    pending_skips -= 1;
+                }
+                if token == Token::LeftParen {
+                    lexer.set_pos(skip_list_unchecked(input, lexer.pos(), options)?);
+                }
+            }
+            Token::RightParen => return None,
+            Token::DatumComment => pending_skips += 1,
+        }
+    }
+    Some(records_from_starts(&starts, data.len()))
+}
+
+/// Validates the whole file and returns the byte range of each record,
+/// like `split_records`.
+fn validate_records(data: &str, options: ParserOptions) -> Result<Vec<Range<usize>>, ParseError> {
+    let mut lexer = Lexer::new(data.as_bytes(), options);
+    let mut filter = DatumCommentFilter::new();
+    let mut starts = Vec::new();
+    let mut depth = 0;
+    loop {
+        let (pos, token) = lexer.get_token()?;
+        match filter.filter(pos, token)? {
+            None => {}
+            Some(Token::Eof) => {
+                if depth != 0 {
+                    return Err(ParseError::UnexpectedEof { pos });
+                }
+                break;
+            }
+            Some(token @ (Token::LeftParen | Token::Atom(_))) => {
+                if depth == 0 {
+                    starts.push(pos);
+                }
+                if token == Token::LeftParen {
+                    depth += 1;
+                }
+            }
+            Some(Token::RightParen) => {
+                if depth == 0 {
+                    return Err(ParseError::UnexpectedRightParen { pos });
+                }
+                depth -= 1;
+            }
+            Some(Token::DatumComment) => unreachable!(),
+        }
+    }
+
+    Ok(records_from_starts(&starts, data.len()))
+}
+
+fn records_from_starts(starts: &[usize], len: usize) -> Vec<Range<usize>> {
+    let mut records = Vec::with_capacity(starts.len());
+    for (i, &start) in starts.iter().enumerate() {
+        let start = if i == 0 { 0 } else { start };
+        let end = starts.get(i + 1).copied().unwrap_or(len);
+        records.push(start..end);
+    }
+    records
+}
+
+/// Splits the records into at most `num_groups` groups of consecutive
+/// records with a similar amount of bytes. Returns ranges of indices
+/// into `records`.
+fn group_records(records: &[Range<usize>], len: usize, num_groups: usize) -> Vec<Range<usize>> {
+    let mut groups = Vec::new();
+    let mut group_start = 0;
+    for (i, record) in records.iter().enumerate() {
+        let target_end = len / num_groups * (groups.len() + 1);
+        if record.end >= target_end && groups.len() + 1 < num_groups {
+            groups.push(group_start..(i + 1));
+            group_start = i + 1;
+        }
+    }
+    if group_start != records.len() {
+        groups.push(group_start..records.len());
+    }
+    groups
+}
+
+/// Parses the records at `records` in `data`. Error offsets are moved by
+/// `offset`, which is the position of `data` in the whole file.
+fn parse_records(
+    data: &str,
+    records: &[Range<usize>],
+    offset: usize,
+    options: ParserOptions,
+) -> Result<Vec<TreeNode>, ParseError> {
+    records
+        .iter()
+        .map(|record| {
+            let mut parser = Parser::with_options(&data[record.clone()], options);
+            parse_tree(&mut parser)
+                .and_then(|node| parser.finish().map(|()| node))
+                .map_err(|e| e.with_offset(offset + record.start))
+        })
+        .collect()
+}
@@ -1,9 +1,15 @@
-use alloc::vec::Vec;
-
-use crate::{ParseError, ParsedItem, Parser, TreeNode};
+use crate::{ItemReader, ParsedItem, TreeBuilder, TreeNode, TreeNodeBuilder};
 
 /// Parses into a tree of `TreeNode`.
 ///
+/// Items can be read from any [`ItemReader`], such as a [`Parser`](crate::Parser)
+/// or a [`BinaryReader`](crate::BinaryReader).
+///
+/// # Panics
+///
+/// Panics if the next item is the end of a list, since there is no
+/// node to parse.
+///
 /// # Example
 ///
 /// ```
@@ -47,65 +53,58 @@ use crate::{ParseError, ParsedItem, Parser, TreeNode};
 /// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::ListEnd(18));
 /// parser.finish().unwrap();
 /// ```
-pub fn parse_tree(parser: &mut Parser<'_>) -> Result<TreeNode, ParseError> {
-    struct StackItem {
-        list_items: Vec<TreeNode>,
-    }
-
-    enum State {
-        Beginning,
-        Parsing {
-            stack: Vec<StackItem>,
-            current: StackItem,
-        },
-        Finished(TreeNode),
-    }
-
-    let mut state = State::Beginning;
+pub fn parse_tree<'a, R>(parser: &mut R) -> Result<TreeNode, R::Error>
+where
+    R: ItemReader<'a>,
+{
+    parse_tree_with(parser, TreeNodeBuilder::new())
+}
 
+/// Parses the next node from `parser` into `builder`.
+///
+/// Like [`parse_tree`], it does not consume the parser, so it can
+/// also be used to parse a sub-tree.
+///
+/// # Panics
+///
+/// Panics if the next item is the end of a list, since there is no
+/// node to parse.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let data = "(test (1 2 3))";
+/// let mut parser = sise::Parser::new(data);
+/// let root_node = sise::parse_tree_with(&mut parser, sise::TreeNodeBuilder::new()).unwrap();
+/// parser.finish().unwrap();
+/// let expected_result = sise_tree!(["test", ["1", "2", "3"]]);
+/// assert_eq!(root_node, expected_result);
+/// ```
+pub fn parse_tree_with<'a, R, B>(parser: &mut R, mut builder: B) -> Result<B::Output, R::Error>
+where
+    R: ItemReader<'a>,
+    B: TreeBuilder<'a>,
+{
+    let mut depth = 0usize;
     loop {
-        match state {
-            State::Beginning => match parser.next_item()? {
-                ParsedItem::Atom(atom, _) => {
-                    let root_node = TreeNode::Atom(atom.into());
-                    state = State::Finished(root_node);
-                }
-                ParsedItem::ListStart(_) => {
-                    state = State::Parsing {
-                        stack: Vec::new(),
-                        current: StackItem {
-                            list_items: Vec::new(),
-                        },
-                    };
-                }
-                ParsedItem::ListEnd(_) => unreachable!(),
-            },
-            State::Parsing {
-                ref mut stack,
-                ref mut current,
-            } => match parser.next_item()? {
-                ParsedItem::Atom(atom, _) => {
-                    current.list_items.push(TreeNode::Atom(atom.into()));
+        match parser.next_item()? {
+            ParsedItem::Atom(atom, pos) => builder.atom(atom, pos),
+            ParsedItem::ListStart(pos) => {
+                builder.begin_list(pos);
+                depth += 1;
+            }
+            ParsedItem::ListEnd(pos) => {
+                if depth == 0 {
+                    panic!("no node to parse");
                 }
-                ParsedItem::ListStart(_) => {
-                    let new_current = StackItem {
-                        list_items: Vec::new(),
-                    };
-                    stack.push(core::mem::replace(current, new_current));
-                }
-                ParsedItem::ListEnd(_) => {
-                    if let Some(previous) = stack.pop() {
-                        let old_current = core::mem::replace(current, previous);
-                        current
-                            .list_items
-                            .push(TreeNode::List(old_current.list_items));
-                    } else {
-                        let root_node = TreeNode::List(core::mem::take(&mut current.list_items));
-                        state = State::Finished(root_node);
-                    }
-                }
-            },
-            State::Finished(root_node) => return Ok(root_node),
+                builder.end_list(pos);
+                depth -= 1;
+            }
+        }
+        if depth == 0 {
+            return Ok(builder.finish());
         }
     }
 }
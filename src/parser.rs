@@ -1,5 +1,6 @@
-use crate::is_atom_chr;
-use crate::is_atom_string_chr;
+use alloc::vec::Vec;
+
+use crate::lexer::{Lexer, Token};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ParsedItem<'a> {
@@ -29,9 +30,17 @@ pub enum ParseError {
     /// There is an invalid character inside a comment
     IllegalChrInComment { pos: usize, chr: char },
 
+    /// There is a byte sequence that is not valid UTF-8 (only possible
+    /// with [`Parser::from_bytes`])
+    InvalidUtf8 { pos: usize },
+
     /// End-of-file is reached before finding the closing `"`
     UnfinishedString { pos: usize },
 
+    /// End-of-file is reached before finding the closing `|#` of a
+    /// block comment
+    UnfinishedBlockComment { pos: usize },
+
     /// Unexpected end-of-file
     UnexpectedEof { pos: usize },
 
@@ -54,7 +63,11 @@ impl core::fmt::Display for ParseError {
             ParseError::IllegalChrInComment { pos, chr } => {
                 write!(f, "illegal character {:?} in comment at byte {}", chr, pos)
             }
+            ParseError::InvalidUtf8 { pos } => write!(f, "invalid UTF-8 at byte {}", pos),
             ParseError::UnfinishedString { pos } => write!(f, "unfinished string at byte {}", pos),
+            ParseError::UnfinishedBlockComment { pos } => {
+                write!(f, "unfinished block comment at byte {}", pos)
+            }
             ParseError::UnexpectedEof { pos } => {
                 write!(f, "unexpected end-of-file at byte {}", pos)
             }
@@ -66,9 +79,74 @@ impl core::fmt::Display for ParseError {
     }
 }
 
+impl ParseError {
+    /// Moves the position of the error forward by `offset` bytes, used
+    /// when a fragment of a larger input has been parsed on its own.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_offset(self, offset: usize) -> Self {
+        match self {
+            ParseError::IllegalChr { pos, chr } => ParseError::IllegalChr {
+                pos: pos + offset,
+                chr,
+            },
+            ParseError::IllegalChrInString { pos, chr } => ParseError::IllegalChrInString {
+                pos: pos + offset,
+                chr,
+            },
+            ParseError::IllegalChrInComment { pos, chr } => ParseError::IllegalChrInComment {
+                pos: pos + offset,
+                chr,
+            },
+            ParseError::InvalidUtf8 { pos } => ParseError::InvalidUtf8 { pos: pos + offset },
+            ParseError::UnfinishedString { pos } => {
+                ParseError::UnfinishedString { pos: pos + offset }
+            }
+            ParseError::UnfinishedBlockComment { pos } => {
+                ParseError::UnfinishedBlockComment { pos: pos + offset }
+            }
+            ParseError::UnexpectedEof { pos } => ParseError::UnexpectedEof { pos: pos + offset },
+            ParseError::UnexpectedRightParen { pos } => {
+                ParseError::UnexpectedRightParen { pos: pos + offset }
+            }
+            ParseError::ExpectedEof { pos } => ParseError::ExpectedEof { pos: pos + offset },
+        }
+    }
+}
+
 #[cfg(feature = "std")]
 impl std::error::Error for ParseError {}
 
+/// Optional syntax extensions accepted by [`Parser`].
+///
+/// All of them are disabled by default, since they change the meaning
+/// of some inputs that are valid without them (`#` is an atom character).
+///
+/// # Example
+///
+/// ```
+/// let options = sise::ParserOptions {
+///     block_comments: true,
+///     datum_comments: true,
+/// };
+///
+/// let data = "(1 #| block #| nested |# comment |# 2 #;(3 4) 5)";
+/// let mut parser = sise::Parser::with_options(data, options);
+/// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::ListStart(0));
+/// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::Atom("1", 1));
+/// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::Atom("2", 36));
+/// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::Atom("5", 46));
+/// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::ListEnd(47));
+/// parser.finish().unwrap();
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Accept nestable block comments, enclosed between `#|` and `|#`.
+    pub block_comments: bool,
+    /// Accept datum comments: `#;` comments out the node (atom or
+    /// whole list) that follows it.
+    pub datum_comments: bool,
+}
+
 /// Parser that decodes a SISE file into a sequence of [`ParsedItem`].
 ///
 /// # Example
@@ -89,29 +167,168 @@ impl std::error::Error for ParseError {}
 /// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::ListEnd(13));
 /// parser.finish().unwrap();
 /// ```
+///
+/// It also implements [`Iterator`]. Iteration ends after the root node
+/// once the trailing end-of-file has been checked, or after the first
+/// error:
+///
+/// ```
+/// let data = "(test (1 2 3))";
+/// let parser = sise::Parser::new(data);
+/// let atoms: Vec<_> = parser
+///     .filter_map(|item| match item.unwrap() {
+///         sise::ParsedItem::Atom(atom, _) => Some(atom),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(atoms, ["test", "1", "2", "3"]);
+///
+/// let data = "(1 2) 3";
+/// let items: Vec<_> = sise::Parser::new(data).collect();
+/// assert_eq!(
+///     items,
+///     [
+///         Ok(sise::ParsedItem::ListStart(0)),
+///         Ok(sise::ParsedItem::Atom("1", 1)),
+///         Ok(sise::ParsedItem::Atom("2", 3)),
+///         Ok(sise::ParsedItem::ListEnd(4)),
+///         Err(sise::ParseError::ExpectedEof { pos: 6 }),
+///     ],
+/// );
+/// ```
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
     state: State,
+    peeked: Option<Result<ParsedItem<'a>, ParseError>>,
 }
 
 enum State {
     Beginning,
     Parsing { depth: usize },
     Finishing,
+    // Only reached when used as an iterator
+    Finished,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(data: &'a str) -> Self {
+        Self::with_options(data, ParserOptions::default())
+    }
+
+    pub fn with_options(data: &'a str, options: ParserOptions) -> Self {
+        Self::from_bytes_with_options(data.as_bytes(), options)
+    }
+
+    /// Creates a parser that reads from a byte slice, which does not
+    /// need to be valid UTF-8.
+    ///
+    /// Since only ASCII characters are allowed outside comments and
+    /// strings, atoms are still returned as `&str`. Characters that
+    /// are not allowed are reported as errors with their offset, and
+    /// byte sequences that are not valid UTF-8 are reported as
+    /// [`ParseError::InvalidUtf8`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let data = b"(test \xFF)";
+    /// let mut parser = sise::Parser::from_bytes(data);
+    /// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::ListStart(0));
+    /// assert_eq!(
+    ///     parser.next_item().unwrap(),
+    ///     sise::ParsedItem::Atom("test", 1),
+    /// );
+    /// assert_eq!(
+    ///     parser.next_item().unwrap_err(),
+    ///     sise::ParseError::InvalidUtf8 { pos: 6 },
+    /// );
+    /// ```
+    pub fn from_bytes(data: &'a [u8]) -> Self {
+        Self::from_bytes_with_options(data, ParserOptions::default())
+    }
+
+    pub fn from_bytes_with_options(data: &'a [u8], options: ParserOptions) -> Self {
         Self {
-            lexer: Lexer::new(data),
+            lexer: Lexer::new(data, options),
             state: State::Beginning,
+            peeked: None,
         }
     }
 
     pub fn next_item(&mut self) -> Result<ParsedItem<'a>, ParseError> {
+        if let Some(peeked) = self.peeked.take() {
+            peeked
+        } else {
+            self.read_item()
+        }
+    }
+
+    /// Returns the item that the next call to [`next_item`](Self::next_item)
+    /// will return, without consuming it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let data = "(1 2)";
+    /// let mut parser = sise::Parser::new(data);
+    /// assert_eq!(parser.peek_item().unwrap(), sise::ParsedItem::ListStart(0));
+    /// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::ListStart(0));
+    /// assert_eq!(parser.peek_item().unwrap(), sise::ParsedItem::Atom("1", 1));
+    /// assert_eq!(parser.peek_item().unwrap(), sise::ParsedItem::Atom("1", 1));
+    /// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::Atom("1", 1));
+    /// ```
+    pub fn peek_item(&mut self) -> Result<ParsedItem<'a>, ParseError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read_item());
+        }
+        self.peeked.clone().unwrap()
+    }
+
+    /// Skips the next node (an atom or a whole list, including its
+    /// sub-lists) without building it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the next item is the end of a list, since there is no
+    /// node to skip.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let data = "(head (1 (2 3) 4) tail)";
+    /// let mut parser = sise::Parser::new(data);
+    /// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::ListStart(0));
+    /// parser.skip_node().unwrap();
+    /// parser.skip_node().unwrap();
+    /// assert_eq!(
+    ///     parser.next_item().unwrap(),
+    ///     sise::ParsedItem::Atom("tail", 18),
+    /// );
+    /// assert_eq!(parser.next_item().unwrap(), sise::ParsedItem::ListEnd(22));
+    /// parser.finish().unwrap();
+    /// ```
+    pub fn skip_node(&mut self) -> Result<(), ParseError> {
+        if let ParsedItem::ListEnd(_) = self.peek_item()? {
+            panic!("no node to skip");
+        }
+
+        let mut depth = 0usize;
+        loop {
+            match self.next_item()? {
+                ParsedItem::Atom(..) => {}
+                ParsedItem::ListStart(_) => depth += 1,
+                ParsedItem::ListEnd(_) => depth -= 1,
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn read_item(&mut self) -> Result<ParsedItem<'a>, ParseError> {
         match self.state {
             State::Beginning => {
-                let (pos, token) = self.lexer.get_token()?;
+                let (pos, token) = self.get_token()?;
                 match token {
                     Token::Eof => Err(ParseError::UnexpectedEof { pos }),
                     Token::LeftParen => {
@@ -123,176 +340,156 @@ impl<'a> Parser<'a> {
                         self.state = State::Finishing;
                         Ok(ParsedItem::Atom(atom, pos))
                     }
+                    Token::DatumComment => unreachable!(),
                 }
             }
-            State::Parsing { ref mut depth } => {
-                let (pos, token) = self.lexer.get_token()?;
+            State::Parsing { depth } => {
+                let (pos, token) = self.get_token()?;
                 match token {
                     Token::Eof => Err(ParseError::UnexpectedEof { pos }),
                     Token::LeftParen => {
-                        *depth += 1;
+                        self.state = State::Parsing { depth: depth + 1 };
                         Ok(ParsedItem::ListStart(pos))
                     }
                     Token::RightParen => {
-                        if *depth == 0 {
+                        if depth == 0 {
                             self.state = State::Finishing;
                         } else {
-                            *depth -= 1;
+                            self.state = State::Parsing { depth: depth - 1 };
                         }
                         Ok(ParsedItem::ListEnd(pos))
                     }
                     Token::Atom(atom) => Ok(ParsedItem::Atom(atom, pos)),
+                    Token::DatumComment => unreachable!(),
                 }
             }
-            State::Finishing => panic!("parsing finished"),
+            State::Finishing | State::Finished => panic!("parsing finished"),
         }
     }
 
     pub fn finish(mut self) -> Result<(), ParseError> {
         match self.state {
-            State::Finishing => {
-                let (pos, token) = self.lexer.get_token()?;
+            State::Finishing if self.peeked.is_none() => {
+                let (pos, token) = self.get_token()?;
                 match token {
                     Token::Eof => Ok(()),
                     _ => Err(ParseError::ExpectedEof { pos }),
                 }
             }
+            State::Finished => panic!("parsing finished"),
             _ => panic!("parsing not finished yet"),
         }
     }
-}
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum Token<'a> {
-    Eof,
-    LeftParen,
-    RightParen,
-    Atom(&'a str),
-}
-
-struct Lexer<'a> {
-    rem_input: &'a str,
-    rem_offset: usize,
-}
 
-impl<'a> Lexer<'a> {
-    fn new(input: &'a str) -> Self {
-        Lexer {
-            rem_input: input,
-            rem_offset: 0,
+    /// Gets the next token from the lexer, skipping the nodes that
+    /// are commented out with `#;`.
+    fn get_token(&mut self) -> Result<(usize, Token<'a>), ParseError> {
+        let (pos, token) = self.lexer.get_token()?;
+        if !matches!(token, Token::DatumComment) {
+            // Nothing is commented out, which is the common case and
+            // does not need the filter.
+            return Ok((pos, token));
         }
-    }
 
-    #[must_use]
-    #[inline]
-    fn eat_any_char(&mut self) -> Option<char> {
-        let mut iter = self.rem_input.chars();
-        if let Some(chr) = iter.next() {
-            let new_rem = iter.as_str();
-            self.rem_offset += self.rem_input.len() - new_rem.len();
-            self.rem_input = new_rem;
-            Some(chr)
-        } else {
-            None
+        let mut filter = DatumCommentFilter::new();
+        filter.filter(pos, token)?;
+        loop {
+            let (pos, token) = self.lexer.get_token()?;
+            if let Some(token) = filter.filter(pos, token)? {
+                return Ok((pos, token));
+            }
         }
     }
+}
 
-    #[must_use]
-    #[inline]
-    fn eat_char(&mut self, chr: char) -> bool {
-        if let Some(new_rem) = self.rem_input.strip_prefix(chr) {
-            self.rem_offset += self.rem_input.len() - new_rem.len();
-            self.rem_input = new_rem;
-            true
-        } else {
-            false
+/// Removes the nodes that are commented out with `#;` from a sequence
+/// of tokens.
+pub(crate) struct DatumCommentFilter {
+    // Each item holds a nesting depth (relative to the first `#;`)
+    // and the number of nodes that still have to be skipped at
+    // that depth.
+    pending_skips: Vec<(usize, usize)>,
+    depth: usize,
+}
+
+impl DatumCommentFilter {
+    pub(crate) fn new() -> Self {
+        Self {
+            pending_skips: Vec::new(),
+            depth: 0,
         }
     }
 
-    #[must_use]
-    #[inline]
-    fn eat_char_if(&mut self, pred: impl FnMut(char) -> bool) -> bool {
-        if let Some(new_rem) = self.rem_input.strip_prefix(pred) {
-            self.rem_offset += self.rem_input.len() - new_rem.len();
-            self.rem_input = new_rem;
-            true
-        } else {
-            false
+    /// Returns `token` if it is not commented out, or `None` if it
+    /// has to be skipped.
+    pub(crate) fn filter<'a>(
+        &mut self,
+        pos: usize,
+        token: Token<'a>,
+    ) -> Result<Option<Token<'a>>, ParseError> {
+        if token == Token::DatumComment {
+            match self.pending_skips.last_mut() {
+                Some(last) if last.0 == self.depth => last.1 += 1,
+                _ => self.pending_skips.push((self.depth, 1)),
+            }
+            return Ok(None);
         }
-    }
 
-    fn get_token(&mut self) -> Result<(usize, Token<'a>), ParseError> {
-        loop {
-            let start_str = self.rem_input;
-            let chr_pos = self.rem_offset;
-            if self.eat_char(' ')
-                || self.eat_char('\t')
-                || self.eat_char('\n')
-                || self.eat_char('\r')
-            {
-                // skip whitespace
-            } else if self.eat_char(';') {
-                // skip comments
-                loop {
-                    let chr_pos = self.rem_offset;
-                    match self.eat_any_char() {
-                        None => return Ok((self.rem_offset, Token::Eof)),
-                        Some('\n' | '\r') => break,
-                        Some('\t' | ' '..='~') => {}
-                        Some(chr) => {
-                            return Err(ParseError::IllegalChrInComment { chr, pos: chr_pos });
-                        }
-                    }
-                }
-            } else if self.eat_char('(') {
-                return Ok((chr_pos, Token::LeftParen));
-            } else if self.eat_char(')') {
-                return Ok((chr_pos, Token::RightParen));
-            } else if let Some(chr) = self.eat_any_char() {
-                if is_atom_chr(chr) || chr == '"' {
-                    let begin_pos = chr_pos;
-                    let end_pos = self.lex_atom(chr)?;
-                    let atom = &start_str[..(end_pos - begin_pos)];
-                    return Ok((begin_pos, Token::Atom(atom)));
-                } else {
-                    // invalid character
-                    return Err(ParseError::IllegalChr { chr, pos: chr_pos });
+        let skip_depth = match self.pending_skips.last() {
+            None => return Ok(Some(token)),
+            Some(&(skip_depth, _)) => skip_depth,
+        };
+
+        let node_ended = match token {
+            Token::Eof => return Err(ParseError::UnexpectedEof { pos }),
+            Token::LeftParen => {
+                self.depth += 1;
+                false
+            }
+            Token::RightParen => {
+                if skip_depth == self.depth {
+                    // `#;` not followed by a node
+                    return Err(ParseError::UnexpectedRightParen { pos });
                 }
-            } else {
-                // end-of-file
-                return Ok((self.rem_offset, Token::Eof));
+                self.depth -= 1;
+                true
+            }
+            Token::Atom(_) => true,
+            Token::DatumComment => unreachable!(),
+        };
+
+        if node_ended && skip_depth == self.depth {
+            let last = self.pending_skips.last_mut().unwrap();
+            last.1 -= 1;
+            if last.1 == 0 {
+                self.pending_skips.pop();
             }
         }
+        Ok(None)
     }
+}
 
-    fn lex_atom(&mut self, first_chr: char) -> Result<usize, ParseError> {
-        let mut in_string = first_chr == '"';
-        loop {
-            let chr_pos = self.rem_offset;
-            if in_string {
-                if self.eat_char('"') {
-                    in_string = false;
-                } else if self.eat_char('\\') {
-                    let chr_pos = self.rem_offset;
-                    if let Some(chr) = self.eat_any_char() {
-                        if chr != '"' && chr != '\\' && !is_atom_string_chr(chr) {
-                            return Err(ParseError::IllegalChrInString { chr, pos: chr_pos });
-                        }
-                    } else {
-                        return Err(ParseError::UnfinishedString { pos: chr_pos });
-                    }
-                } else if let Some(chr) = self.eat_any_char() {
-                    if !is_atom_string_chr(chr) {
-                        return Err(ParseError::IllegalChrInString { chr, pos: chr_pos });
-                    }
-                } else {
-                    return Err(ParseError::UnfinishedString { pos: chr_pos });
-                }
-            } else if self.eat_char('"') {
-                in_string = true;
-            } else if !self.eat_char_if(is_atom_chr) {
-                return Ok(chr_pos);
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<ParsedItem<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match self.state {
+            State::Finishing if self.peeked.is_none() => {
+                self.state = State::Finished;
+                return match self.get_token() {
+                    Ok((_, Token::Eof)) => None,
+                    Ok((pos, _)) => Some(Err(ParseError::ExpectedEof { pos })),
+                    Err(e) => Some(Err(e)),
+                };
             }
+            State::Finished => return None,
+            _ => self.next_item(),
+        };
+        if result.is_err() {
+            self.state = State::Finished;
         }
+        Some(result)
     }
 }
+
+impl core::iter::FusedIterator for Parser<'_> {}
@@ -0,0 +1,418 @@
+use alloc::vec::Vec;
+
+use crate::diff::path_to_tree;
+use crate::{Edit, TreeNode};
+
+/// A list of operations to apply to a tree, in the spirit of JSON
+/// Patch.
+///
+/// In SISE, a patch is written as a `patch` list with an element for
+/// each operation, where paths are written as `(path ...)` with the
+/// indices used by [`TreeNode::index_path`]:
+///
+/// * `(replace PATH VALUE)` replaces the node at `PATH` with `VALUE`.
+/// * `(insert PATH VALUE)` inserts `VALUE` so that it ends up at `PATH`.
+///   The last index can be the length of the list to insert at its end.
+/// * `(remove PATH)` removes the node at `PATH`.
+/// * `(test PATH VALUE)` checks that the node at `PATH` is equal to
+///   `VALUE`.
+/// * `(move FROM TO)` removes the node at `FROM` and inserts it so that
+///   it ends up at `TO`, which refers to the tree after the removal.
+///
+/// Each operation refers to the tree that results from the previous
+/// ones.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let patch = sise::from_str(
+///     "(patch
+///        (test (path 1 0) port)
+///        (replace (path 1 1) 8080)
+///        (insert (path 2) (tls on))
+///        (remove (path 0)))",
+/// )
+/// .unwrap();
+/// let patch = sise::Patch::from_tree(&patch).unwrap();
+///
+/// let mut tree = sise_tree!(["server", ["port", "80"]]);
+/// sise::apply_patch(&mut tree, &patch).unwrap();
+/// assert_eq!(tree, sise_tree!([["port", "8080"], ["tls", "on"]]));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Patch {
+    pub ops: Vec<PatchOp>,
+}
+
+/// An operation of a [`Patch`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchOp {
+    Replace { path: Vec<usize>, value: TreeNode },
+    Insert { path: Vec<usize>, value: TreeNode },
+    Remove { path: Vec<usize> },
+    Test { path: Vec<usize>, value: TreeNode },
+    Move { from: Vec<usize>, to: Vec<usize> },
+}
+
+/// Error returned by [`Patch::from_tree`] when a tree is not a valid
+/// patch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchFormatError {
+    /// Indices of the offending node, starting from the root
+    pub path: Vec<usize>,
+}
+
+impl core::fmt::Display for PatchFormatError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("invalid patch at (")?;
+        for (i, index) in self.path.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", index)?;
+        }
+        f.write_str(")")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatchFormatError {}
+
+/// Error returned by [`apply_patch`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchError {
+    /// Index of the operation that failed
+    pub op_index: usize,
+    pub kind: PatchErrorKind,
+}
+
+/// The kind of a [`PatchError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchErrorKind {
+    /// An index of the path is out of range
+    PathNotFound,
+    /// The path goes through an atom
+    NotAList,
+    /// The operation removes the root or inserts at the root
+    RootNotAllowed,
+    /// The node is not equal to the value of a `test` operation
+    TestFailed,
+}
+
+impl core::fmt::Display for PatchError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "patch operation {} failed: ", self.op_index)?;
+        match self.kind {
+            PatchErrorKind::PathNotFound => f.write_str("path not found"),
+            PatchErrorKind::NotAList => f.write_str("path goes through an atom"),
+            PatchErrorKind::RootNotAllowed => f.write_str("operation not allowed on the root"),
+            PatchErrorKind::TestFailed => f.write_str("test failed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatchError {}
+
+impl Patch {
+    /// Creates a patch that applies the edits returned by
+    /// [`diff`](crate::diff).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// let old = sise_tree!(["a", "b", ["c"]]);
+    /// let new = sise_tree!([["c", "d"], "a"]);
+    /// let patch = sise::Patch::from_diff(&sise::diff(&old, &new));
+    ///
+    /// let mut tree = old.clone();
+    /// sise::apply_patch(&mut tree, &patch).unwrap();
+    /// assert_eq!(tree, new);
+    /// ```
+    pub fn from_diff(edits: &[Edit]) -> Self {
+        let ops = edits
+            .iter()
+            .map(|edit| match *edit {
+                Edit::Insert { ref path, ref node } => PatchOp::Insert {
+                    path: path.clone(),
+                    value: node.clone(),
+                },
+                Edit::Delete { ref path } => PatchOp::Remove { path: path.clone() },
+                Edit::ReplaceAtom { ref path, ref atom } => PatchOp::Replace {
+                    path: path.clone(),
+                    value: TreeNode::Atom(atom.clone()),
+                },
+                Edit::Replace { ref path, ref node } => PatchOp::Replace {
+                    path: path.clone(),
+                    value: node.clone(),
+                },
+                Edit::Move { ref from, ref to } => PatchOp::Move {
+                    from: from.clone(),
+                    to: to.clone(),
+                },
+            })
+            .collect();
+        Self { ops }
+    }
+
+    /// Reads a patch written in SISE.
+    ///
+    /// Besides the `patch` format, it also accepts the output of
+    /// [`diff_to_tree`](crate::diff_to_tree): a `diff` list whose
+    /// elements are `insert`, `delete`, `replace-atom`, `replace` or
+    /// `move` edits. Each format only accepts its own operation names.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// let old = sise_tree!(["a", "b", ["c"]]);
+    /// let new = sise_tree!([["c", "d"], "x"]);
+    /// let diff_tree = sise::diff_to_tree(&sise::diff(&old, &new));
+    /// let patch = sise::Patch::from_tree(&diff_tree).unwrap();
+    ///
+    /// let mut tree = old.clone();
+    /// sise::apply_patch(&mut tree, &patch).unwrap();
+    /// assert_eq!(tree, new);
+    /// ```
+    pub fn from_tree(node: &TreeNode) -> Result<Self, PatchFormatError> {
+        let invalid = |path: &[usize]| PatchFormatError {
+            path: path.to_vec(),
+        };
+
+        let list = node.as_list().ok_or_else(|| invalid(&[]))?;
+        let is_diff = match list.first() {
+            Some(head) if *head == "patch" => false,
+            Some(head) if *head == "diff" => true,
+            _ => return Err(invalid(&[0])),
+        };
+
+        let mut ops = Vec::with_capacity(list.len() - 1);
+        for (i, item) in list.iter().enumerate().skip(1) {
+            let op_list = item.as_list().ok_or_else(|| invalid(&[i]))?;
+            let name = op_list
+                .first()
+                .and_then(TreeNode::as_atom)
+                .ok_or_else(|| invalid(&[i, 0]))?;
+            let num_args = match (is_diff, name.as_str()) {
+                (false, "remove") | (true, "delete") => 1,
+                (false, "replace" | "insert" | "test" | "move") => 2,
+                (true, "replace-atom" | "replace" | "insert" | "move") => 2,
+                _ => return Err(invalid(&[i, 0])),
+            };
+            if op_list.len() != num_args + 1 {
+                return Err(invalid(&[i]));
+            }
+            let path = parse_path(&op_list[1]).ok_or_else(|| invalid(&[i, 1]))?;
+            let op = match name.as_str() {
+                "replace" => PatchOp::Replace {
+                    path,
+                    value: op_list[2].clone(),
+                },
+                "replace-atom" => {
+                    if !op_list[2].is_atom() {
+                        return Err(invalid(&[i, 2]));
+                    }
+                    PatchOp::Replace {
+                        path,
+                        value: op_list[2].clone(),
+                    }
+                }
+                "insert" => PatchOp::Insert {
+                    path,
+                    value: op_list[2].clone(),
+                },
+                "remove" | "delete" => PatchOp::Remove { path },
+                "test" => PatchOp::Test {
+                    path,
+                    value: op_list[2].clone(),
+                },
+                "move" => PatchOp::Move {
+                    from: path,
+                    to: parse_path(&op_list[2]).ok_or_else(|| invalid(&[i, 2]))?,
+                },
+                _ => unreachable!(),
+            };
+            ops.push(op);
+        }
+        Ok(Self { ops })
+    }
+
+    /// Writes the patch in SISE.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let patch = sise::Patch {
+    ///     ops: vec![
+    ///         sise::PatchOp::Remove { path: vec![0, 1] },
+    ///         sise::PatchOp::Move { from: vec![2], to: vec![0] },
+    ///     ],
+    /// };
+    /// let tree = patch.to_tree();
+    /// assert_eq!(
+    ///     tree.to_string(),
+    ///     "(patch (remove (path 0 1)) (move (path 2) (path 0)))",
+    /// );
+    /// assert_eq!(sise::Patch::from_tree(&tree).unwrap(), patch);
+    /// ```
+    pub fn to_tree(&self) -> TreeNode {
+        let mut list = Vec::with_capacity(self.ops.len() + 1);
+        list.push(TreeNode::from("patch"));
+        for op in self.ops.iter() {
+            let item = match *op {
+                PatchOp::Replace {
+                    ref path,
+                    ref value,
+                } => alloc::vec![TreeNode::from("replace"), path_to_tree(path), value.clone()],
+                PatchOp::Insert {
+                    ref path,
+                    ref value,
+                } => alloc::vec![TreeNode::from("insert"), path_to_tree(path), value.clone()],
+                PatchOp::Remove { ref path } => {
+                    alloc::vec![TreeNode::from("remove"), path_to_tree(path)]
+                }
+                PatchOp::Test {
+                    ref path,
+                    ref value,
+                } => alloc::vec![TreeNode::from("test"), path_to_tree(path), value.clone()],
+                PatchOp::Move { ref from, ref to } => {
+                    alloc::vec![TreeNode::from("move"), path_to_tree(from), path_to_tree(to),]
+                }
+            };
+            list.push(TreeNode::List(item));
+        }
+        TreeNode::List(list)
+    }
+}
+
+fn parse_path(node: &TreeNode) -> Option<Vec<usize>> {
+    let list = node.as_list()?;
+    if *list.first()? != "path" {
+        return None;
+    }
+    list[1..]
+        .iter()
+        .map(|index| {
+            let index = index.as_atom()?;
+            if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            index.parse().ok()
+        })
+        .collect()
+}
+
+/// Applies `patch` to `root`.
+///
+/// It is atomic: if an operation fails, `root` is left unchanged and
+/// the error reports which operation failed and why.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let patch = sise::Patch {
+///     ops: vec![
+///         sise::PatchOp::Remove { path: vec![0] },
+///         sise::PatchOp::Test { path: vec![0], value: sise_tree!("x") },
+///     ],
+/// };
+///
+/// let mut tree = sise_tree!(["a", "b"]);
+/// assert_eq!(
+///     sise::apply_patch(&mut tree, &patch),
+///     Err(sise::PatchError {
+///         op_index: 1,
+///         kind: sise::PatchErrorKind::TestFailed,
+///     }),
+/// );
+/// assert_eq!(tree, sise_tree!(["a", "b"]));
+/// ```
+pub fn apply_patch(root: &mut TreeNode, patch: &Patch) -> Result<(), PatchError> {
+    let mut patched = root.clone();
+    for (op_index, op) in patch.ops.iter().enumerate() {
+        apply_op(&mut patched, op).map_err(|kind| PatchError { op_index, kind })?;
+    }
+    *root = patched;
+    Ok(())
+}
+
+fn apply_op(root: &mut TreeNode, op: &PatchOp) -> Result<(), PatchErrorKind> {
+    match *op {
+        PatchOp::Replace {
+            ref path,
+            ref value,
+        } => {
+            *node_at(root, path)? = value.clone();
+        }
+        PatchOp::Insert {
+            ref path,
+            ref value,
+        } => insert(root, path, value.clone())?,
+        PatchOp::Remove { ref path } => {
+            remove(root, path)?;
+        }
+        PatchOp::Test {
+            ref path,
+            ref value,
+        } => {
+            if *node_at(root, path)? != *value {
+                return Err(PatchErrorKind::TestFailed);
+            }
+        }
+        PatchOp::Move { ref from, ref to } => {
+            let node = remove(root, from)?;
+            insert(root, to, node)?;
+        }
+    }
+    Ok(())
+}
+
+fn node_at<'a>(root: &'a mut TreeNode, path: &[usize]) -> Result<&'a mut TreeNode, PatchErrorKind> {
+    let mut node = root;
+    for &index in path.iter() {
+        node = node
+            .as_mut_list()
+            .ok_or(PatchErrorKind::NotAList)?
+            .get_mut(index)
+            .ok_or(PatchErrorKind::PathNotFound)?;
+    }
+    Ok(node)
+}
+
+/// Returns the list that contains the node at `path` and the index of
+/// the node in it.
+fn parent_at<'a>(
+    root: &'a mut TreeNode,
+    path: &[usize],
+) -> Result<(&'a mut Vec<TreeNode>, usize), PatchErrorKind> {
+    let (&last, parent_path) = path.split_last().ok_or(PatchErrorKind::RootNotAllowed)?;
+    let parent = node_at(root, parent_path)?
+        .as_mut_list()
+        .ok_or(PatchErrorKind::NotAList)?;
+    Ok((parent, last))
+}
+
+fn insert(root: &mut TreeNode, path: &[usize], node: TreeNode) -> Result<(), PatchErrorKind> {
+    let (parent, index) = parent_at(root, path)?;
+    if index > parent.len() {
+        return Err(PatchErrorKind::PathNotFound);
+    }
+    parent.insert(index, node);
+    Ok(())
+}
+
+fn remove(root: &mut TreeNode, path: &[usize]) -> Result<TreeNode, PatchErrorKind> {
+    let (parent, index) = parent_at(root, path)?;
+    if index >= parent.len() {
+        return Err(PatchErrorKind::PathNotFound);
+    }
+    Ok(parent.remove(index))
+}
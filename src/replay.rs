@@ -0,0 +1,102 @@
+use crate::{ParsedItem, Serializer, SerializerSink, TreeBuilder, TreeNode, TreeNodeBuilder};
+
+/// Writes a sequence of items into `serializer`.
+///
+/// Line breaks are placed as [`serialize_tree`](crate::serialize_tree)
+/// does. Items can come from a [`Parser`](crate::Parser) or from
+/// recorded [`OwnedParsedItem`](crate::OwnedParsedItem)s, which makes
+/// it possible to filter items without building a tree.
+///
+/// # Panics
+///
+/// Panics if the items do not form a valid sequence for `serializer`.
+///
+/// # Example
+///
+/// ```
+/// let data = "(example (1 secret 2) secret (a b))";
+/// let parser = sise::Parser::new(data);
+/// let items = parser
+///     .map(Result::unwrap)
+///     .filter(|item| !matches!(item, sise::ParsedItem::Atom("secret", _)));
+///
+/// let style = sise::SerializerStyle {
+///     line_break: "\n",
+///     indentation: " ",
+/// };
+///
+/// let mut result = String::new();
+/// let mut serializer = sise::Serializer::new(style, &mut result);
+/// sise::replay_into_serializer(items, &mut serializer, usize::MAX);
+/// serializer.finish(false);
+///
+/// assert_eq!(result, "(example (1 2) (a b))");
+/// ```
+pub fn replay_into_serializer<'a, I>(
+    items: I,
+    serializer: &mut Serializer<'_, '_>,
+    break_line_at: usize,
+) where
+    I: IntoIterator<Item = ParsedItem<'a>>,
+{
+    replay_into_builder(items, SerializerSink::new(serializer, break_line_at));
+}
+
+/// Builds a tree from a sequence of items that form a single node.
+///
+/// # Panics
+///
+/// Panics if the items do not form exactly one node.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let data = "(test (1 2 3))";
+/// let mut parser = sise::Parser::new(data);
+/// let items = sise::record_node(&mut parser).unwrap();
+/// parser.finish().unwrap();
+///
+/// let tree = sise::replay_into_tree(items.iter().map(sise::OwnedParsedItem::as_item));
+/// assert_eq!(tree, sise_tree!(["test", ["1", "2", "3"]]));
+/// ```
+pub fn replay_into_tree<'a, I>(items: I) -> TreeNode
+where
+    I: IntoIterator<Item = ParsedItem<'a>>,
+{
+    replay_into_builder(items, TreeNodeBuilder::new())
+}
+
+/// Feeds a sequence of items into `builder` and returns its output.
+///
+/// The items must form a single node, otherwise `builder` might
+/// panic or produce an incomplete output.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let items = [
+///     sise::ParsedItem::ListStart(0),
+///     sise::ParsedItem::Atom("a", 1),
+///     sise::ParsedItem::ListEnd(2),
+/// ];
+/// let tree = sise::replay_into_builder(items.iter().copied(), sise::TreeNodeBuilder::new());
+/// assert_eq!(tree, sise_tree!(["a"]));
+/// ```
+pub fn replay_into_builder<'a, I, B>(items: I, mut builder: B) -> B::Output
+where
+    I: IntoIterator<Item = ParsedItem<'a>>,
+    B: TreeBuilder<'a>,
+{
+    for item in items {
+        match item {
+            ParsedItem::Atom(atom, pos) => builder.atom(atom, pos),
+            ParsedItem::ListStart(pos) => builder.begin_list(pos),
+            ParsedItem::ListEnd(pos) => builder.end_list(pos),
+        }
+    }
+    builder.finish()
+}
@@ -0,0 +1,1132 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{decode_string, parse_tree_with, ItemReader, TreeBuilder, TreeNode, TreeNodeBuilder};
+
+/// Lists nested deeper than this are not validated, so validation does
+/// not overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+/// Names of the built-in types, which cannot be redefined.
+const BUILTIN_TYPES: &[&str] = &["any", "atom", "list", "int", "float", "bool", "string"];
+
+/// A compiled schema, which describes the structure of a tree.
+///
+/// A schema is written in SISE as a `schema` list that contains a
+/// `(root PATTERN)` element, which is the pattern that the root node
+/// must match, and any number of `(define NAME PATTERN)` elements,
+/// which give names to patterns. Definitions can be used before they
+/// are defined, and can be recursive as long as the recursion goes
+/// through a list.
+///
+/// These patterns match a single node:
+///
+/// * `any` matches any node, `atom` any atom and `list` any list.
+/// * `int` matches a decimal integer with an optional sign, and
+///   `(int MIN MAX)` one between `MIN` and `MAX` (inclusive).
+/// * `float` matches a decimal number with an optional fraction and
+///   exponent (e.g., `-1.5e3`).
+/// * `bool` matches `true` or `false`.
+/// * `string` matches a quoted string that can be decoded with
+///   [`decode_string`].
+/// * `(enum ATOM...)` matches any of the atoms.
+/// * `(keyword ATOM)` matches exactly that atom.
+/// * `(pattern "REGEX")` matches an atom whose whole text matches a
+///   regular expression, which supports literal characters, `.`,
+///   classes such as `[a-z_]` or `[^0-9]`, the quantifiers `*`, `+`
+///   and `?`, and `\` to escape a special character. Groups and
+///   alternations are not supported.
+/// * `(choice PATTERN...)` matches if any of the patterns matches.
+/// * `NAME` matches the pattern defined with that name.
+/// * `(list ELEMENT...)` matches a list whose elements match the
+///   sequence of `ELEMENT`s, where each `ELEMENT` is a pattern that
+///   matches one element, `(optional PATTERN)` that matches zero or one,
+///   or `(repeat PATTERN [MIN [MAX]])` that matches from `MIN` (by
+///   default 0) to `MAX` (by default unlimited). Repetitions are
+///   matched greedily, without backtracking. An element that does not
+///   match a repetition, but cannot be taken by any of the following
+///   `ELEMENT`s either, is reported as not matching the repetition,
+///   which then goes on.
+/// * `(record HEAD ENTRY...)` matches a keyword list such as
+///   `(HEAD (KEY VALUE...) ...)` whose entries can appear in any order.
+///   Each `ENTRY` is `(KIND KEY ELEMENT...)`, where `KIND` is
+///   `required` (exactly once), `optional` (at most once) or `repeated`
+///   (any number of times), and the `ELEMENT`s describe the elements
+///   of the entry after the key, as in `list`. Entries with other keys
+///   are not allowed.
+///
+/// # Example
+///
+/// ```
+/// use sise::Schema;
+///
+/// let schema = sise::from_str(
+///     r#"(schema
+///          (define port (int 1 65535))
+///          (define host (pattern "[a-z0-9.-]+"))
+///          (root
+///            (record server
+///              (required name string)
+///              (required listen host port)
+///              (optional tls bool)
+///              (repeated alias host)
+///              (optional flags (repeat (enum debug verbose))))))"#,
+/// )
+/// .unwrap();
+/// let schema = Schema::from_tree(&schema).unwrap();
+///
+/// let tree = sise::from_str(r#"(server (name "main") (listen example.com 8080) (flags debug))"#);
+/// assert!(schema.is_valid(&tree.unwrap()));
+///
+/// let tree = sise::from_str("(server (listen example.com 0) (tls maybe) (color red))");
+/// let violations = schema.validate(&tree.unwrap());
+/// let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     messages,
+///     [
+///         "at (1 2): expected int from 1 to 65535",
+///         "at (2 1): expected bool",
+///         "at (3): unknown entry color",
+///         "at (): missing entry name",
+///     ],
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Schema {
+    names: Vec<String>,
+    defs: Vec<Pattern>,
+    root: Pattern,
+}
+
+#[derive(Clone, Debug)]
+enum Pattern {
+    Any,
+    Atom,
+    List,
+    Int(Option<(i64, i64)>),
+    Float,
+    Bool,
+    String,
+    Enum(Vec<String>),
+    Keyword(String),
+    Regex(Regex),
+    Choice(Vec<Pattern>),
+    /// Index of a definition
+    Ref(usize),
+    Seq(Vec<Element>),
+    Record {
+        head: String,
+        entries: Vec<EntrySpec>,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct Element {
+    pattern: Pattern,
+    min: usize,
+    max: Option<usize>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Occurs {
+    Required,
+    Optional,
+    Repeated,
+}
+
+#[derive(Clone, Debug)]
+struct EntrySpec {
+    key: String,
+    occurs: Occurs,
+    elements: Vec<Element>,
+}
+
+/// Error returned by [`Schema::from_tree`] when a tree is not a valid
+/// schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaError {
+    /// Indices of the offending node, starting from the root
+    pub path: Vec<usize>,
+    pub kind: SchemaErrorKind,
+}
+
+/// The kind of a [`SchemaError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaErrorKind {
+    /// The node does not have the syntax expected at its position
+    InvalidSyntax,
+    /// A pattern refers to a name that is not defined
+    UnknownType { name: String },
+    /// A name is defined more than once, or it is a built-in type
+    DuplicateDefinition { name: String },
+    /// A definition refers to itself without going through a list
+    RecursiveDefinition { name: String },
+    /// A regular expression of a `pattern` is not valid
+    InvalidRegex,
+    /// There is no `root` element
+    MissingRoot,
+}
+
+impl core::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("invalid schema at ")?;
+        write_path(f, &self.path)?;
+        f.write_str(": ")?;
+        match self.kind {
+            SchemaErrorKind::InvalidSyntax => f.write_str("invalid syntax"),
+            SchemaErrorKind::UnknownType { ref name } => write!(f, "unknown type {}", name),
+            SchemaErrorKind::DuplicateDefinition { ref name } => {
+                write!(f, "duplicate definition of {}", name)
+            }
+            SchemaErrorKind::RecursiveDefinition { ref name } => {
+                write!(f, "recursive definition of {}", name)
+            }
+            SchemaErrorKind::InvalidRegex => f.write_str("invalid regular expression"),
+            SchemaErrorKind::MissingRoot => f.write_str("missing root"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SchemaError {}
+
+/// A place where a tree does not match a [`Schema`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// Indices of the offending node, starting from the root
+    pub path: Vec<usize>,
+    /// Byte offset of the offending node (its atom or `(`) in the input,
+    /// when validated with [`Schema::validate_parsed`]
+    pub pos: Option<usize>,
+    pub kind: ViolationKind,
+}
+
+/// The kind of a [`Violation`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The node does not match the pattern described by `expected`
+    Mismatch { expected: String },
+    /// The list ends before an element described by `expected`
+    MissingElement { expected: String },
+    /// The list has an element that is not allowed by its pattern
+    UnexpectedElement,
+    /// A `required` entry is not present in the record
+    MissingEntry { key: String },
+    /// A `required` or `optional` entry appears more than once
+    DuplicateEntry { key: String },
+    /// The record has an entry whose key is not in its pattern
+    UnknownEntry { key: String },
+    /// The list is nested too deeply to be validated, so validation
+    /// stopped
+    TooDeep,
+}
+
+impl core::fmt::Display for Violation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("at ")?;
+        write_path(f, &self.path)?;
+        if let Some(pos) = self.pos {
+            write!(f, ", byte {}", pos)?;
+        }
+        f.write_str(": ")?;
+        match self.kind {
+            ViolationKind::Mismatch { ref expected } => write!(f, "expected {}", expected),
+            ViolationKind::MissingElement { ref expected } => {
+                write!(f, "missing element, expected {}", expected)
+            }
+            ViolationKind::UnexpectedElement => f.write_str("unexpected element"),
+            ViolationKind::MissingEntry { ref key } => write!(f, "missing entry {}", key),
+            ViolationKind::DuplicateEntry { ref key } => write!(f, "duplicate entry {}", key),
+            ViolationKind::UnknownEntry { ref key } => write!(f, "unknown entry {}", key),
+            ViolationKind::TooDeep => f.write_str("nested too deeply"),
+        }
+    }
+}
+
+fn write_path(f: &mut core::fmt::Formatter<'_>, path: &[usize]) -> core::fmt::Result {
+    f.write_str("(")?;
+    for (i, index) in path.iter().enumerate() {
+        if i != 0 {
+            f.write_str(" ")?;
+        }
+        write!(f, "{}", index)?;
+    }
+    f.write_str(")")
+}
+
+impl Schema {
+    /// Compiles a schema written in SISE, as described at [`Schema`].
+    pub fn from_tree(tree: &TreeNode) -> Result<Self, SchemaError> {
+        let list = match tree.as_list() {
+            Some(list) if tree.head() == Some("schema") => list,
+            _ => return Err(syntax_error(&[])),
+        };
+
+        // Collect the names first, so they can be used before being
+        // defined.
+        let mut names = Vec::new();
+        let mut name_indices = BTreeMap::new();
+        let mut def_nodes = Vec::new();
+        let mut root_node = None;
+        for (i, item) in list.iter().enumerate().skip(1) {
+            let parts = item.as_list().map_or(&[][..], |parts| &parts[..]);
+            match (item.head(), parts) {
+                (Some("define"), [_, TreeNode::Atom(name), def]) => {
+                    if BUILTIN_TYPES.contains(&name.as_str())
+                        || name_indices.insert(name.as_str(), names.len()).is_some()
+                    {
+                        return Err(SchemaError {
+                            path: alloc::vec![i, 1],
+                            kind: SchemaErrorKind::DuplicateDefinition { name: name.clone() },
+                        });
+                    }
+                    names.push(name.clone());
+                    def_nodes.push((i, def));
+                }
+                (Some("root"), [_, root]) if root_node.is_none() => root_node = Some((i, root)),
+                _ => return Err(syntax_error(&[i])),
+            }
+        }
+        let (root_index, root_node) = root_node.ok_or(SchemaError {
+            path: Vec::new(),
+            kind: SchemaErrorKind::MissingRoot,
+        })?;
+
+        let compiler = Compiler {
+            names: name_indices,
+        };
+        let mut defs = Vec::with_capacity(def_nodes.len());
+        for &(i, def) in def_nodes.iter() {
+            defs.push(compiler.pattern(def, &mut alloc::vec![i, 2])?);
+        }
+        let root = compiler.pattern(root_node, &mut alloc::vec![root_index, 1])?;
+
+        // Check that following references without going through a list
+        // never leads back to the same definition.
+        let mut state = alloc::vec![VisitState::New; defs.len()];
+        for start in 0..defs.len() {
+            if find_cycle(&defs, start, &mut state) {
+                return Err(SchemaError {
+                    path: alloc::vec![def_nodes[start].0, 1],
+                    kind: SchemaErrorKind::RecursiveDefinition {
+                        name: names[start].clone(),
+                    },
+                });
+            }
+        }
+
+        Ok(Self { names, defs, root })
+    }
+
+    /// Validates a tree, returning all the places where it does not
+    /// match the schema, in the order in which they are found. The
+    /// result is empty if the tree is valid.
+    ///
+    /// Whether a list matches a pattern is found at most once, even when
+    /// it is tried against several patterns (e.g., in a `choice`), so
+    /// validation takes polynomial time.
+    ///
+    /// Validation stops at the first list that is nested more than 256
+    /// levels deep, which is reported as [`ViolationKind::TooDeep`]
+    /// after the violations found until then.
+    pub fn validate(&self, tree: &TreeNode) -> Vec<Violation> {
+        let mut validator = Validator::new(self);
+        validator.node(&self.root, tree);
+        if let Some(path) = validator.too_deep {
+            validator.violations.push(Violation {
+                path,
+                pos: None,
+                kind: ViolationKind::TooDeep,
+            });
+        }
+        validator.violations
+    }
+
+    /// Parses the next node from `parser`, like
+    /// [`parse_tree`](crate::parse_tree), and validates it like
+    /// [`Schema::validate`], also setting the byte offset of each
+    /// violation.
+    ///
+    /// It does not consume the parser, so `finish` still has to be
+    /// called on it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::Schema;
+    ///
+    /// let schema = sise::from_str("(schema (root (list (keyword point) int int)))").unwrap();
+    /// let schema = Schema::from_tree(&schema).unwrap();
+    ///
+    /// let mut parser = sise::Parser::new("(point 1\n  x)");
+    /// let violations = schema.validate_parsed(&mut parser).unwrap();
+    /// parser.finish().unwrap();
+    /// assert_eq!(violations[0].path, [2]);
+    /// assert_eq!(violations[0].pos, Some(11));
+    /// assert_eq!(violations[0].to_string(), "at (2), byte 11: expected int");
+    /// ```
+    pub fn validate_parsed<'a, R>(&self, parser: &mut R) -> Result<Vec<Violation>, R::Error>
+    where
+        R: ItemReader<'a>,
+    {
+        let (tree, nodes) = parse_tree_with(parser, SpannedBuilder::default())?;
+        let mut violations = self.validate(&tree);
+        for violation in violations.iter_mut() {
+            let mut index = 0;
+            for &i in violation.path.iter() {
+                index = nodes[index].1[i];
+            }
+            violation.pos = Some(nodes[index].0);
+        }
+        Ok(violations)
+    }
+
+    /// Returns whether a tree matches the schema.
+    pub fn is_valid(&self, tree: &TreeNode) -> bool {
+        self.validate(tree).is_empty()
+    }
+
+    /// Returns a short description of what a pattern matches.
+    fn describe(&self, pattern: &Pattern) -> String {
+        match *pattern {
+            Pattern::Any => String::from("any node"),
+            Pattern::Atom => String::from("atom"),
+            Pattern::List | Pattern::Seq(_) => String::from("list"),
+            Pattern::Int(None) => String::from("int"),
+            Pattern::Int(Some((min, max))) => format!("int from {} to {}", min, max),
+            Pattern::Float => String::from("float"),
+            Pattern::Bool => String::from("bool"),
+            Pattern::String => String::from("string"),
+            Pattern::Enum(ref values) => format!("one of {}", values.join(", ")),
+            Pattern::Keyword(ref keyword) => format!("keyword {}", keyword),
+            Pattern::Regex(ref regex) => format!("atom matching {}", regex.source),
+            Pattern::Choice(ref choices) => {
+                let descriptions: Vec<String> = choices.iter().map(|p| self.describe(p)).collect();
+                descriptions.join(" or ")
+            }
+            Pattern::Ref(index) => self.names[index].clone(),
+            Pattern::Record { ref head, .. } => format!("({} ...)", head),
+        }
+    }
+}
+
+fn syntax_error(path: &[usize]) -> SchemaError {
+    SchemaError {
+        path: path.to_vec(),
+        kind: SchemaErrorKind::InvalidSyntax,
+    }
+}
+
+struct Compiler<'a> {
+    names: BTreeMap<&'a str, usize>,
+}
+
+impl Compiler<'_> {
+    /// Compiles the pattern at `path`.
+    fn pattern(&self, node: &TreeNode, path: &mut Vec<usize>) -> Result<Pattern, SchemaError> {
+        let list = match *node {
+            TreeNode::Atom(ref name) => {
+                return match name.as_str() {
+                    "any" => Ok(Pattern::Any),
+                    "atom" => Ok(Pattern::Atom),
+                    "list" => Ok(Pattern::List),
+                    "int" => Ok(Pattern::Int(None)),
+                    "float" => Ok(Pattern::Float),
+                    "bool" => Ok(Pattern::Bool),
+                    "string" => Ok(Pattern::String),
+                    _ => match self.names.get(name.as_str()) {
+                        Some(&index) => Ok(Pattern::Ref(index)),
+                        None => Err(SchemaError {
+                            path: path.clone(),
+                            kind: SchemaErrorKind::UnknownType { name: name.clone() },
+                        }),
+                    },
+                };
+            }
+            TreeNode::List(ref list) => list,
+        };
+        let args = node.tail().unwrap_or(&[]);
+        match node.head() {
+            Some("int") => match args {
+                [TreeNode::Atom(min), TreeNode::Atom(max)] => match (min.parse(), max.parse()) {
+                    (Ok(min), Ok(max)) if min <= max => Ok(Pattern::Int(Some((min, max)))),
+                    _ => Err(syntax_error(path)),
+                },
+                _ => Err(syntax_error(path)),
+            },
+            Some("enum") if !args.is_empty() => {
+                let values = self.atoms(args, path)?;
+                Ok(Pattern::Enum(values))
+            }
+            Some("keyword") => match args {
+                [TreeNode::Atom(keyword)] => Ok(Pattern::Keyword(keyword.clone())),
+                _ => Err(syntax_error(path)),
+            },
+            Some("pattern") => match args {
+                [TreeNode::Atom(atom)] => {
+                    path.push(1);
+                    let regex = decode_string(atom).ok_or_else(|| syntax_error(path))?;
+                    let regex = Regex::new(regex).ok_or_else(|| SchemaError {
+                        path: path.clone(),
+                        kind: SchemaErrorKind::InvalidRegex,
+                    })?;
+                    path.pop();
+                    Ok(Pattern::Regex(regex))
+                }
+                _ => Err(syntax_error(path)),
+            },
+            Some("choice") if !args.is_empty() => {
+                let mut choices = Vec::with_capacity(args.len());
+                for (i, arg) in args.iter().enumerate() {
+                    path.push(i + 1);
+                    choices.push(self.pattern(arg, path)?);
+                    path.pop();
+                }
+                Ok(Pattern::Choice(choices))
+            }
+            Some("list") => Ok(Pattern::Seq(self.elements(list, 1, path)?)),
+            Some("record") => match args.split_first() {
+                Some((TreeNode::Atom(head), entries)) => {
+                    let mut entry_specs: Vec<EntrySpec> = Vec::with_capacity(entries.len());
+                    for (i, entry) in entries.iter().enumerate() {
+                        path.push(i + 2);
+                        let spec = self.entry(entry, path)?;
+                        if entry_specs.iter().any(|other| other.key == spec.key) {
+                            return Err(syntax_error(path));
+                        }
+                        entry_specs.push(spec);
+                        path.pop();
+                    }
+                    Ok(Pattern::Record {
+                        head: head.clone(),
+                        entries: entry_specs,
+                    })
+                }
+                _ => Err(syntax_error(path)),
+            },
+            _ => Err(syntax_error(path)),
+        }
+    }
+
+    fn atoms(&self, nodes: &[TreeNode], path: &[usize]) -> Result<Vec<String>, SchemaError> {
+        let mut atoms = Vec::with_capacity(nodes.len());
+        for (i, node) in nodes.iter().enumerate() {
+            match *node {
+                TreeNode::Atom(ref atom) => atoms.push(atom.clone()),
+                TreeNode::List(_) => {
+                    let mut path = path.to_vec();
+                    path.push(i + 1);
+                    return Err(syntax_error(&path));
+                }
+            }
+        }
+        Ok(atoms)
+    }
+
+    /// Compiles the elements of `list` from index `start`.
+    fn elements(
+        &self,
+        list: &[TreeNode],
+        start: usize,
+        path: &mut Vec<usize>,
+    ) -> Result<Vec<Element>, SchemaError> {
+        let mut elements = Vec::with_capacity(list.len().saturating_sub(start));
+        for (i, node) in list.iter().enumerate().skip(start) {
+            path.push(i);
+            elements.push(self.element(node, path)?);
+            path.pop();
+        }
+        Ok(elements)
+    }
+
+    fn element(&self, node: &TreeNode, path: &mut Vec<usize>) -> Result<Element, SchemaError> {
+        let args = node.tail().unwrap_or(&[]);
+        let (min, max) = match node.head() {
+            Some("optional") if args.len() == 1 => (0, Some(1)),
+            Some("repeat") if (1..=3).contains(&args.len()) => {
+                let bounds = parse_usizes(&args[1..]).ok_or_else(|| syntax_error(path))?;
+                match bounds[..] {
+                    [] => (0, None),
+                    [min] => (min, None),
+                    [min, max] if min <= max => (min, Some(max)),
+                    _ => return Err(syntax_error(path)),
+                }
+            }
+            _ => {
+                return Ok(Element {
+                    pattern: self.pattern(node, path)?,
+                    min: 1,
+                    max: Some(1),
+                });
+            }
+        };
+        path.push(1);
+        let pattern = self.pattern(&args[0], path)?;
+        path.pop();
+        Ok(Element { pattern, min, max })
+    }
+
+    fn entry(&self, node: &TreeNode, path: &mut Vec<usize>) -> Result<EntrySpec, SchemaError> {
+        let occurs = match node.head() {
+            Some("required") => Occurs::Required,
+            Some("optional") => Occurs::Optional,
+            Some("repeated") => Occurs::Repeated,
+            _ => return Err(syntax_error(path)),
+        };
+        let list = node.as_list().unwrap();
+        let key = match list.get(1) {
+            Some(TreeNode::Atom(key)) => key.clone(),
+            _ => return Err(syntax_error(path)),
+        };
+        let elements = self.elements(list, 2, path)?;
+        Ok(EntrySpec {
+            key,
+            occurs,
+            elements,
+        })
+    }
+}
+
+fn parse_usizes(nodes: &[TreeNode]) -> Option<Vec<usize>> {
+    nodes
+        .iter()
+        .map(|node| node.as_atom()?.parse().ok())
+        .collect()
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum VisitState {
+    New,
+    Visiting,
+    Done,
+}
+
+/// Returns whether there is a cycle of references, not going through
+/// a list, from the definition `index`.
+fn find_cycle(defs: &[Pattern], index: usize, state: &mut [VisitState]) -> bool {
+    match state[index] {
+        VisitState::Visiting => return true,
+        VisitState::Done => return false,
+        VisitState::New => {}
+    }
+    state[index] = VisitState::Visiting;
+    let mut refs = Vec::new();
+    direct_refs(&defs[index], &mut refs);
+    for next in refs {
+        if find_cycle(defs, next, state) {
+            return true;
+        }
+    }
+    state[index] = VisitState::Done;
+    false
+}
+
+/// Collects the definitions that are matched against the same node as
+/// `pattern`.
+fn direct_refs(pattern: &Pattern, refs: &mut Vec<usize>) {
+    match *pattern {
+        Pattern::Ref(index) => refs.push(index),
+        Pattern::Choice(ref choices) => {
+            for choice in choices.iter() {
+                direct_refs(choice, refs);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Builds a `TreeNode` together with the position of each of its nodes.
+#[derive(Default)]
+struct SpannedBuilder {
+    tree: TreeNodeBuilder,
+    /// Byte offset of each node, in pre-order, and the indices of the
+    /// elements of lists
+    nodes: Vec<(usize, Vec<usize>)>,
+    /// Indices of the lists that are being built
+    stack: Vec<usize>,
+}
+
+impl SpannedBuilder {
+    fn add_node(&mut self, pos: usize) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push((pos, Vec::new()));
+        if let Some(&parent) = self.stack.last() {
+            self.nodes[parent].1.push(index);
+        }
+        index
+    }
+}
+
+impl<'a> TreeBuilder<'a> for SpannedBuilder {
+    type Output = (TreeNode, Vec<(usize, Vec<usize>)>);
+
+    fn atom(&mut self, atom: &'a str, pos: usize) {
+        self.tree.atom(atom, pos);
+        self.add_node(pos);
+    }
+
+    fn begin_list(&mut self, pos: usize) {
+        self.tree.begin_list(pos);
+        let index = self.add_node(pos);
+        self.stack.push(index);
+    }
+
+    fn end_list(&mut self, pos: usize) {
+        self.tree.end_list(pos);
+        self.stack.pop();
+    }
+
+    fn finish(self) -> Self::Output {
+        (self.tree.finish(), self.nodes)
+    }
+}
+
+struct Validator<'s> {
+    schema: &'s Schema,
+    path: Vec<usize>,
+    violations: Vec<Violation>,
+    /// Number of lists whose elements are being validated
+    depth: usize,
+    /// Path of the list that exceeded `MAX_DEPTH`, after which nothing
+    /// else is validated
+    too_deep: Option<Vec<usize>>,
+    /// Set while `matches` finds out whether a node matches, so
+    /// violations are not reported and only `failed` is set
+    quiet: bool,
+    failed: bool,
+    /// Whether each list matches each pattern, as found by `matches`,
+    /// so the same list is not validated against the same pattern more
+    /// than once
+    matches_cache: BTreeMap<(*const Pattern, *const TreeNode), bool>,
+}
+
+impl<'s> Validator<'s> {
+    fn new(schema: &'s Schema) -> Self {
+        Self {
+            schema,
+            path: Vec::new(),
+            violations: Vec::new(),
+            depth: 0,
+            too_deep: None,
+            quiet: false,
+            failed: false,
+            matches_cache: BTreeMap::new(),
+        }
+    }
+
+    fn report(&mut self, kind: ViolationKind) {
+        if self.quiet {
+            self.failed = true;
+        } else if self.too_deep.is_none() {
+            self.violations.push(Violation {
+                path: self.path.clone(),
+                pos: None,
+                kind,
+            });
+        }
+    }
+
+    fn mismatch(&mut self, pattern: &Pattern) {
+        let expected = self.schema.describe(pattern);
+        self.report(ViolationKind::Mismatch { expected });
+    }
+
+    /// Returns whether the node at the current path matches `pattern`,
+    /// without reporting anything.
+    fn matches(&mut self, pattern: &'s Pattern, node: &TreeNode) -> bool {
+        let key: (*const Pattern, *const TreeNode) = (pattern, node);
+        if let Some(&matches) = self.matches_cache.get(&key) {
+            return matches;
+        }
+
+        let (quiet, failed) = (self.quiet, self.failed);
+        self.quiet = true;
+        self.failed = false;
+        self.node(pattern, node);
+        let matches = !self.failed;
+        self.quiet = quiet;
+        self.failed = failed;
+
+        if self.too_deep.is_some() {
+            return true;
+        }
+        // Atoms are cheap to match again, since patterns can only
+        // recurse through lists.
+        if node.is_list() {
+            self.matches_cache.insert(key, matches);
+        }
+        matches
+    }
+
+    /// Validates an element of a list. While being quiet, only whether
+    /// it matches is needed, which is taken from the cache, so each list
+    /// is validated once for each pattern.
+    fn element(&mut self, pattern: &'s Pattern, item: &TreeNode) {
+        if !self.quiet {
+            self.node(pattern, item);
+        } else if !self.matches(pattern, item) {
+            self.failed = true;
+        }
+    }
+
+    /// Called before validating the elements of a list. Returns `false`
+    /// and stops validation if it would exceed `MAX_DEPTH`.
+    fn enter_list(&mut self) -> bool {
+        if self.depth == MAX_DEPTH {
+            self.too_deep = Some(self.path.clone());
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    fn node(&mut self, pattern: &'s Pattern, node: &TreeNode) {
+        if self.too_deep.is_some() || (self.quiet && self.failed) {
+            return;
+        }
+        let atom = node.as_atom().map(String::as_str);
+        let valid = match *pattern {
+            Pattern::Any => true,
+            Pattern::Atom => node.is_atom(),
+            Pattern::List => node.is_list(),
+            Pattern::Int(range) => atom.map_or(false, |atom| is_int(atom, range)),
+            Pattern::Float => atom.map_or(false, is_float),
+            Pattern::Bool => matches!(atom, Some("true" | "false")),
+            Pattern::String => atom.map_or(false, |atom| decode_string(atom).is_some()),
+            Pattern::Enum(ref values) => {
+                atom.map_or(false, |atom| values.iter().any(|v| v == atom))
+            }
+            Pattern::Keyword(ref keyword) => atom == Some(keyword.as_str()),
+            Pattern::Regex(ref regex) => atom.map_or(false, |atom| regex.is_match(atom)),
+            Pattern::Choice(ref choices) => choices.iter().any(|choice| self.matches(choice, node)),
+            Pattern::Ref(index) => {
+                let schema = self.schema;
+                self.node(&schema.defs[index], node);
+                true
+            }
+            Pattern::Seq(ref elements) => match node.as_list() {
+                Some(list) => {
+                    if self.enter_list() {
+                        self.sequence(elements, list, 0);
+                        self.depth -= 1;
+                    }
+                    true
+                }
+                None => false,
+            },
+            Pattern::Record {
+                ref head,
+                ref entries,
+            } => match node.as_list() {
+                Some(list) => {
+                    if self.enter_list() {
+                        self.record(head, entries, list);
+                        self.depth -= 1;
+                    }
+                    true
+                }
+                None => false,
+            },
+        };
+        if !valid {
+            self.mismatch(pattern);
+        }
+    }
+
+    /// Matches the elements of `list` from index `start` against
+    /// `elements`.
+    fn sequence(&mut self, elements: &'s [Element], list: &[TreeNode], start: usize) {
+        let mut index = start;
+        for (k, element) in elements.iter().enumerate() {
+            let mut count = 0;
+            if element.min != 1 || element.max != Some(1) {
+                while element.max.map_or(true, |max| count < max) {
+                    let item = match list.get(index) {
+                        Some(item) => item,
+                        None => break,
+                    };
+                    self.path.push(index);
+                    if !self.matches(&element.pattern, item) {
+                        // If no later element can take the item, it is
+                        // validated to report why it does not match,
+                        // and the repetition goes on.
+                        let later = &elements[(k + 1)..];
+                        if later.iter().any(|later| self.matches(&later.pattern, item)) {
+                            self.path.pop();
+                            break;
+                        }
+                        self.element(&element.pattern, item);
+                    }
+                    self.path.pop();
+                    index += 1;
+                    count += 1;
+                }
+            }
+            // Elements that are still needed are validated to report
+            // why they do not match.
+            while count < element.min {
+                match list.get(index) {
+                    Some(item) => {
+                        self.path.push(index);
+                        self.element(&element.pattern, item);
+                        self.path.pop();
+                        index += 1;
+                        count += 1;
+                    }
+                    None => {
+                        let expected = self.schema.describe(&element.pattern);
+                        self.report(ViolationKind::MissingElement { expected });
+                        break;
+                    }
+                }
+            }
+        }
+        for i in index..list.len() {
+            self.path.push(i);
+            self.report(ViolationKind::UnexpectedElement);
+            self.path.pop();
+        }
+    }
+
+    fn record(&mut self, head: &str, entries: &'s [EntrySpec], list: &[TreeNode]) {
+        let expected_head = || format!("keyword {}", head);
+        match list.first() {
+            Some(TreeNode::Atom(atom)) if atom == head => {}
+            Some(_) => {
+                self.path.push(0);
+                self.report(ViolationKind::Mismatch {
+                    expected: expected_head(),
+                });
+                self.path.pop();
+            }
+            None => {
+                self.report(ViolationKind::MissingElement {
+                    expected: expected_head(),
+                });
+                return;
+            }
+        }
+
+        let mut counts = alloc::vec![0usize; entries.len()];
+        for (i, item) in list.iter().enumerate().skip(1) {
+            self.path.push(i);
+            match item.head() {
+                None => self.report(ViolationKind::UnexpectedElement),
+                Some(key) => match entries.iter().position(|entry| entry.key == key) {
+                    None => self.report(ViolationKind::UnknownEntry {
+                        key: String::from(key),
+                    }),
+                    Some(j) => {
+                        counts[j] += 1;
+                        if counts[j] > 1 && entries[j].occurs != Occurs::Repeated {
+                            self.report(ViolationKind::DuplicateEntry {
+                                key: String::from(key),
+                            });
+                        } else {
+                            self.sequence(&entries[j].elements, item.as_list().unwrap(), 1);
+                        }
+                    }
+                },
+            }
+            self.path.pop();
+        }
+
+        for (entry, &count) in entries.iter().zip(counts.iter()) {
+            if entry.occurs == Occurs::Required && count == 0 {
+                self.report(ViolationKind::MissingEntry {
+                    key: entry.key.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Returns whether `atom` is a decimal integer within `range`.
+fn is_int(atom: &str, range: Option<(i64, i64)>) -> bool {
+    let digits = atom.strip_prefix(&['+', '-'][..]).unwrap_or(atom);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    match range {
+        None => true,
+        Some((min, max)) => match atom.parse::<i64>() {
+            Ok(value) => min <= value && value <= max,
+            Err(_) => false,
+        },
+    }
+}
+
+/// Returns whether `atom` is a decimal number with an optional fraction
+/// and exponent.
+fn is_float(atom: &str) -> bool {
+    fn digits(s: &str) -> (usize, &str) {
+        let len = s.bytes().take_while(u8::is_ascii_digit).count();
+        (len, &s[len..])
+    }
+
+    let rest = atom.strip_prefix(&['+', '-'][..]).unwrap_or(atom);
+    let (int_len, mut rest) = digits(rest);
+    if int_len == 0 {
+        return false;
+    }
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let (fraction_len, fraction_rest) = digits(fraction);
+        if fraction_len == 0 {
+            return false;
+        }
+        rest = fraction_rest;
+    }
+    if let Some(exponent) = rest.strip_prefix(&['e', 'E'][..]) {
+        let exponent = exponent.strip_prefix(&['+', '-'][..]).unwrap_or(exponent);
+        let (exponent_len, exponent_rest) = digits(exponent);
+        if exponent_len == 0 {
+            return false;
+        }
+        rest = exponent_rest;
+    }
+    rest.is_empty()
+}
+
+/// A small regular expression, matched against a whole atom.
+#[derive(Clone, Debug)]
+struct Regex {
+    source: String,
+    items: Vec<(RegexItem, Quantifier)>,
+}
+
+#[derive(Clone, Debug)]
+enum RegexItem {
+    Any,
+    Chr(char),
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Quantifier {
+    One,
+    Optional,
+    Star,
+    Plus,
+}
+
+impl Regex {
+    fn new(source: String) -> Option<Self> {
+        let mut items = Vec::new();
+        let mut chars = source.chars().peekable();
+        while let Some(chr) = chars.next() {
+            let item = match chr {
+                '.' => RegexItem::Any,
+                '\\' => RegexItem::Chr(chars.next()?),
+                '[' => {
+                    let negated = chars.peek() == Some(&'^');
+                    if negated {
+                        chars.next();
+                    }
+                    let mut ranges = Vec::new();
+                    loop {
+                        let start = match chars.next()? {
+                            ']' if !ranges.is_empty() => break,
+                            ']' => return None,
+                            '\\' => chars.next()?,
+                            chr => chr,
+                        };
+                        let mut end = start;
+                        if chars.peek() == Some(&'-') {
+                            chars.next();
+                            end = match chars.next()? {
+                                '\\' => chars.next()?,
+                                // A `-` at the end of the class is literal
+                                ']' => {
+                                    ranges.push((start, start));
+                                    ranges.push(('-', '-'));
+                                    break;
+                                }
+                                chr => chr,
+                            };
+                            if end < start {
+                                return None;
+                            }
+                        }
+                        ranges.push((start, end));
+                    }
+                    RegexItem::Class { negated, ranges }
+                }
+                '*' | '+' | '?' | '(' | ')' | '|' | '{' | '}' | ']' => return None,
+                chr => RegexItem::Chr(chr),
+            };
+            let quantifier = match chars.peek() {
+                Some('*') => Quantifier::Star,
+                Some('+') => Quantifier::Plus,
+                Some('?') => Quantifier::Optional,
+                _ => Quantifier::One,
+            };
+            if !matches!(quantifier, Quantifier::One) {
+                chars.next();
+            }
+            items.push((item, quantifier));
+        }
+        Some(Self { source, items })
+    }
+
+    /// Matches the whole `text` by keeping the set of items that can
+    /// come next, so it takes O(items × length) time.
+    fn is_match(&self, text: &str) -> bool {
+        let num_items = self.items.len();
+        // `states[i]` is whether the text read so far can be followed
+        // by `items[i]` (or end there, for `i == num_items`).
+        let mut states = alloc::vec![false; num_items + 1];
+        let mut next_states = states.clone();
+        states[0] = true;
+        self.skip_optional(&mut states);
+        for chr in text.chars() {
+            next_states.iter_mut().for_each(|state| *state = false);
+            for (i, &(ref item, quantifier)) in self.items.iter().enumerate() {
+                if states[i] && item.matches(chr) {
+                    match quantifier {
+                        Quantifier::One | Quantifier::Optional => next_states[i + 1] = true,
+                        Quantifier::Star => next_states[i] = true,
+                        Quantifier::Plus => {
+                            next_states[i] = true;
+                            next_states[i + 1] = true;
+                        }
+                    }
+                }
+            }
+            self.skip_optional(&mut next_states);
+            core::mem::swap(&mut states, &mut next_states);
+        }
+        states[num_items]
+    }
+
+    /// Adds the states that follow items that can match nothing.
+    fn skip_optional(&self, states: &mut [bool]) {
+        for (i, &(_, quantifier)) in self.items.iter().enumerate() {
+            if states[i] && matches!(quantifier, Quantifier::Optional | Quantifier::Star) {
+                states[i + 1] = true;
+            }
+        }
+    }
+}
+
+impl RegexItem {
+    fn matches(&self, chr: char) -> bool {
+        match *self {
+            RegexItem::Any => true,
+            RegexItem::Chr(expected) => chr == expected,
+            RegexItem::Class {
+                negated,
+                ref ranges,
+            } => {
+                ranges
+                    .iter()
+                    .any(|&(start, end)| start <= chr && chr <= end)
+                    != negated
+            }
+        }
+    }
+}
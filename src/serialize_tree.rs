@@ -1,6 +1,6 @@
 use alloc::vec::Vec;
 
-use crate::{Serializer, TreeNode};
+use crate::{check_atom, Serializer, TreeNode};
 
 /// Serializes a tree of nodes into `serializer`.
 ///
@@ -87,6 +87,18 @@ pub fn serialize_tree(
     root_node: &TreeNode,
     break_line_at: usize,
 ) {
+    if let Err(atom) = try_serialize_tree(serializer, root_node, break_line_at) {
+        panic!("invalid atom {:?}", atom);
+    }
+}
+
+/// Like [`serialize_tree`], but stops at the first invalid atom and
+/// returns it.
+pub(crate) fn try_serialize_tree<'a>(
+    serializer: &mut Serializer<'_, '_>,
+    root_node: &'a TreeNode,
+    break_line_at: usize,
+) -> Result<(), &'a str> {
     enum State<'a> {
         Beginning(&'a TreeNode),
         Writing {
@@ -103,7 +115,10 @@ pub fn serialize_tree(
         match state {
             State::Beginning(node) => match node {
                 TreeNode::Atom(atom) => {
-                    serializer.put_atom(atom, break_line_at);
+                    if !check_atom(atom) {
+                        return Err(atom);
+                    }
+                    serializer.put_valid_atom(atom, break_line_at);
                     state = State::Finished;
                 }
                 TreeNode::List(list) => {
@@ -123,10 +138,13 @@ pub fn serialize_tree(
                 if let Some(node) = current_list.next() {
                     match node {
                         TreeNode::Atom(atom) => {
+                            if !check_atom(atom) {
+                                return Err(atom);
+                            }
                             if *list_beginning {
-                                serializer.put_atom(atom, usize::MAX);
+                                serializer.put_valid_atom(atom, usize::MAX);
                             } else {
-                                serializer.put_atom(atom, break_line_at);
+                                serializer.put_valid_atom(atom, break_line_at);
                             }
                             *list_beginning = false;
                         }
@@ -146,7 +164,7 @@ pub fn serialize_tree(
                     }
                 }
             }
-            State::Finished => return,
+            State::Finished => return Ok(()),
         }
     }
 }
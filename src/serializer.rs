@@ -144,7 +144,11 @@ impl<'a, 'b> Serializer<'a, 'b> {
 
     pub fn put_atom(&mut self, atom: &str, break_line_at: usize) {
         assert!(crate::check_atom(atom), "invalid atom {:?}", atom);
+        self.put_valid_atom(atom, break_line_at);
+    }
 
+    /// Like `put_atom`, for an atom that has already been checked.
+    pub(crate) fn put_valid_atom(&mut self, atom: &str, break_line_at: usize) {
         match self.state {
             State::Beginning => {
                 self.out.push_str(atom);
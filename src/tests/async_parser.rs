@@ -0,0 +1,311 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures::StreamExt as _;
+
+use crate::{sise_tree, AsyncParseError, AsyncParser, OwnedParsedItem, ParseError, ParserOptions};
+
+const ALL_OPTIONS: ParserOptions = ParserOptions {
+    block_comments: true,
+    datum_comments: true,
+};
+
+/// Reader that returns `Poll::Pending` before each chunk of
+/// `chunk_size` bytes.
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk_size: usize,
+    ready: bool,
+}
+
+impl futures::io::AsyncRead for ChunkedReader<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        if !self.ready {
+            self.ready = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        self.ready = false;
+        let n = self.chunk_size.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Reader that fails after returning its data.
+struct FailingReader<'a> {
+    data: &'a [u8],
+}
+
+impl futures::io::AsyncRead for FailingReader<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        if self.data.is_empty() {
+            return Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "connection reset",
+            )));
+        }
+        let n = buf.len().min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Poll::Ready(Ok(n))
+    }
+}
+
+fn collect_items(
+    src_data: &str,
+    options: ParserOptions,
+    chunk_size: usize,
+) -> (Vec<OwnedParsedItem>, Option<ParseError>) {
+    let reader = ChunkedReader {
+        data: src_data.as_bytes(),
+        chunk_size,
+        ready: false,
+    };
+    let results: Vec<_> =
+        futures::executor::block_on(AsyncParser::with_options(reader, options).collect::<Vec<_>>());
+
+    let mut items = Vec::new();
+    for result in results {
+        match result {
+            Ok(item) => items.push(item),
+            Err(AsyncParseError::Parse(e)) => return (items, Some(e)),
+            Err(AsyncParseError::Io(e)) => panic!("unexpected I/O error: {}", e),
+        }
+    }
+    (items, None)
+}
+
+#[track_caller]
+fn check(
+    src_data: &str,
+    options: ParserOptions,
+    expected_items: &[OwnedParsedItem],
+    expected_error: Option<ParseError>,
+) {
+    for &chunk_size in [1, 2, 3, 5, 8, 1000].iter() {
+        let (items, error) = collect_items(src_data, options, chunk_size);
+        assert_eq!(items, expected_items, "chunk_size = {}", chunk_size);
+        assert_eq!(error, expected_error, "chunk_size = {}", chunk_size);
+    }
+}
+
+fn atom(atom: &str, pos: usize) -> OwnedParsedItem {
+    OwnedParsedItem::Atom(String::from(atom), pos)
+}
+
+#[test]
+fn test_empty() {
+    check("", ParserOptions::default(), &[], None);
+    check(" ; comment\n", ParserOptions::default(), &[], None);
+}
+
+#[test]
+fn test_items() {
+    check(
+        "(abc \"d e\"f #| ( |# g)\r\nh ; i\n(\"\\\"\")",
+        ALL_OPTIONS,
+        &[
+            OwnedParsedItem::ListStart(0),
+            atom("abc", 1),
+            atom("\"d e\"f", 5),
+            atom("g", 20),
+            OwnedParsedItem::ListEnd(21),
+            atom("h", 24),
+            OwnedParsedItem::ListStart(30),
+            atom("\"\\\"\"", 31),
+            OwnedParsedItem::ListEnd(35),
+        ],
+        None,
+    );
+}
+
+#[test]
+fn test_datum_comments() {
+    check(
+        "#; a (b #;(c) #; #;d e f) #;",
+        ALL_OPTIONS,
+        &[
+            OwnedParsedItem::ListStart(5),
+            atom("b", 6),
+            atom("f", 23),
+            OwnedParsedItem::ListEnd(24),
+        ],
+        Some(ParseError::UnexpectedEof { pos: 28 }),
+    );
+}
+
+#[test]
+fn test_hash_at_chunk_end() {
+    check(
+        "(a#b #|c|##;d)",
+        ALL_OPTIONS,
+        &[
+            OwnedParsedItem::ListStart(0),
+            atom("a#b", 1),
+            OwnedParsedItem::ListEnd(13),
+        ],
+        None,
+    );
+}
+
+#[test]
+fn test_errors() {
+    check(
+        "(a) (b [)",
+        ParserOptions::default(),
+        &[
+            OwnedParsedItem::ListStart(0),
+            atom("a", 1),
+            OwnedParsedItem::ListEnd(2),
+            OwnedParsedItem::ListStart(4),
+            atom("b", 5),
+        ],
+        Some(ParseError::IllegalChr { pos: 7, chr: '[' }),
+    );
+    check(
+        "a )",
+        ParserOptions::default(),
+        &[atom("a", 0)],
+        Some(ParseError::UnexpectedRightParen { pos: 2 }),
+    );
+    check(
+        "(a",
+        ParserOptions::default(),
+        &[OwnedParsedItem::ListStart(0), atom("a", 1)],
+        Some(ParseError::UnexpectedEof { pos: 2 }),
+    );
+    check(
+        "(a \"b",
+        ParserOptions::default(),
+        &[OwnedParsedItem::ListStart(0), atom("a", 1)],
+        Some(ParseError::UnfinishedString { pos: 5 }),
+    );
+}
+
+#[test]
+fn test_split_character() {
+    // The character is never reported as invalid UTF-8, even when it
+    // is split across reads.
+    check(
+        "a ; \u{e9}\n",
+        ParserOptions::default(),
+        &[atom("a", 0)],
+        Some(ParseError::IllegalChrInComment {
+            pos: 4,
+            chr: '\u{e9}',
+        }),
+    );
+}
+
+#[test]
+fn test_long_input() {
+    let mut src_data = String::new();
+    let mut expected_items = Vec::new();
+    for i in 0..2000 {
+        expected_items.push(OwnedParsedItem::ListStart(src_data.len()));
+        src_data.push('(');
+        let atom_str = std::format!("\"item {}\"", i);
+        expected_items.push(atom(&atom_str, src_data.len()));
+        src_data.push_str(&atom_str);
+        expected_items.push(OwnedParsedItem::ListEnd(src_data.len()));
+        src_data.push_str(") ; comment\n");
+    }
+    let pos = src_data.len() + 1;
+    src_data.push_str("(\0)");
+    expected_items.push(OwnedParsedItem::ListStart(pos - 1));
+
+    let (items, error) = collect_items(&src_data, ParserOptions::default(), 1000);
+    assert_eq!(items, expected_items);
+    assert_eq!(error, Some(ParseError::IllegalChr { pos, chr: '\0' }));
+}
+
+#[test]
+fn test_unfinished_tokens() {
+    // Tokens and comments split at every position
+    check(
+        "(a\"b\\\"c\"d #| x #| y |# z ||# ; c\n \"\\\\\" #| |#)",
+        ALL_OPTIONS,
+        &[
+            OwnedParsedItem::ListStart(0),
+            atom("a\"b\\\"c\"d", 1),
+            atom("\"\\\\\"", 34),
+            OwnedParsedItem::ListEnd(44),
+        ],
+        None,
+    );
+}
+
+#[test]
+fn test_large_tokens() {
+    // Each byte of a token or comment that spans many reads is scanned
+    // once, not once per read, which would take minutes here.
+    let size = 1024 * 1024;
+    let long_string = std::format!("\"{}\"", "x \\\"".repeat(size / 4));
+    let mut src_data = String::from("(");
+    src_data.push_str(&long_string);
+    src_data.push_str(&" ".repeat(size));
+    src_data.push_str("#|");
+    src_data.push_str(&"|".repeat(size));
+    src_data.push_str("|#)");
+
+    let (items, error) = collect_items(&src_data, ALL_OPTIONS, 256);
+    assert_eq!(
+        items,
+        [
+            OwnedParsedItem::ListStart(0),
+            atom(&long_string, 1),
+            OwnedParsedItem::ListEnd(src_data.len() - 1),
+        ],
+    );
+    assert_eq!(error, None);
+}
+
+#[test]
+fn test_trees() {
+    let reader = ChunkedReader {
+        data: b"(a (b c)) d (\"e\" ())",
+        chunk_size: 3,
+        ready: false,
+    };
+    let nodes: Vec<_> = futures::executor::block_on(
+        AsyncParser::new(reader)
+            .into_trees()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(
+        nodes,
+        [
+            sise_tree!(["a", ["b", "c"]]),
+            sise_tree!("d"),
+            sise_tree!(["\"e\"", []]),
+        ],
+    );
+}
+
+#[test]
+fn test_io_error() {
+    let reader = FailingReader { data: b"(a) (b" };
+    let results: Vec<_> =
+        futures::executor::block_on(AsyncParser::new(reader).into_trees().collect::<Vec<_>>());
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap(), &sise_tree!(["a"]));
+    match results[1] {
+        Err(AsyncParseError::Io(ref e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::ConnectionReset);
+        }
+        _ => panic!("expected I/O error"),
+    }
+}
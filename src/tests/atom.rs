@@ -0,0 +1,76 @@
+use alloc::string::{String, ToString as _};
+
+use crate::{Atom, AtomError, AtomStr, TreeNode};
+
+#[test]
+fn test_valid() {
+    for &atom in [
+        "1234",
+        "AbCd-1234",
+        "\"\"",
+        "\" \\_ \\\" \\\\ \"",
+        "prefix\"abcd\"suffix",
+        "!#$%&*+-./:<=>?@_~",
+    ]
+    .iter()
+    {
+        assert_eq!(AtomStr::new(atom).unwrap().as_str(), atom);
+        assert_eq!(Atom::new(String::from(atom)).unwrap(), atom);
+        assert!(crate::check_atom(atom));
+    }
+}
+
+#[test]
+fn test_errors() {
+    let cases = [
+        ("", AtomError::Empty),
+        ("ab cd", AtomError::IllegalChr { pos: 2, chr: ' ' }),
+        (
+            "a\u{e9}",
+            AtomError::IllegalChr {
+                pos: 1,
+                chr: '\u{e9}',
+            },
+        ),
+        ("a(", AtomError::IllegalChr { pos: 1, chr: '(' }),
+        ("\\", AtomError::IllegalChr { pos: 0, chr: '\\' }),
+        (
+            "x\"a\tb\"",
+            AtomError::IllegalChrInString { pos: 3, chr: '\t' },
+        ),
+        (
+            "\"a\\\u{e9}\"",
+            AtomError::IllegalChrInString {
+                pos: 3,
+                chr: '\u{e9}',
+            },
+        ),
+        ("\"a\\", AtomError::DanglingEscape { pos: 2 }),
+        ("\"abcd", AtomError::UnfinishedString { pos: 0 }),
+        ("a\"\"b\"\\\"", AtomError::UnfinishedString { pos: 4 }),
+    ];
+    for (atom, error) in cases.iter() {
+        assert_eq!(AtomStr::new(atom).as_ref(), Err(error), "{:?}", atom);
+        assert_eq!(Atom::new(String::from(*atom)).as_ref(), Err(error));
+        assert!(!crate::check_atom(atom));
+    }
+}
+
+#[test]
+fn test_conversions() {
+    let atom_str = AtomStr::try_from("abc").unwrap();
+    let atom = Atom::from(atom_str);
+    assert_eq!(atom.as_atom_str(), atom_str);
+    assert_eq!(Atom::try_from("abc"), Ok(atom.clone()));
+    assert_eq!(Atom::try_from(String::from("abc")), Ok(atom.clone()));
+    assert_eq!(atom.to_string(), "abc");
+    assert_eq!(atom_str.to_string(), "abc");
+    assert_eq!(atom.len(), 3);
+    assert_eq!(TreeNode::from(atom_str), "abc");
+    assert_eq!(String::from(atom.clone()), "abc");
+    assert_eq!(atom.into_string(), "abc");
+    assert_eq!(
+        Atom::try_from("a b"),
+        Err(AtomError::IllegalChr { pos: 1, chr: ' ' })
+    );
+}
@@ -0,0 +1,73 @@
+use crate::{check_atom, decode_string, encode_string, suggest_atom};
+
+#[test]
+fn test_encode_string() {
+    assert_eq!(encode_string(""), "\"\"");
+    assert_eq!(encode_string("abc def"), "\"abc def\"");
+    assert_eq!(encode_string("\"\\"), "\"\\\"\\\\\"");
+    assert_eq!(encode_string("\t\n\r"), "\"\\t\\n\\r\"");
+    assert_eq!(encode_string("\0\u{7f}"), "\"\\u{0}\\u{7f}\"");
+    assert_eq!(encode_string("\u{e9}\u{1f600}"), "\"\\u{e9}\\u{1f600}\"");
+}
+
+#[test]
+fn test_round_trip() {
+    let strings = [
+        "",
+        "plain",
+        "with spaces",
+        "\"quoted\"",
+        "back\\slash",
+        "\\u{41}",
+        "line\nbreaks\r\n",
+        "\ttab",
+        "\0\u{1}\u{1f}\u{7f}",
+        "\u{e9}\u{3b1}\u{1f600}\u{10ffff}",
+    ];
+    for s in strings.iter() {
+        let atom = encode_string(s);
+        assert!(check_atom(&atom), "{:?}", atom);
+        assert_eq!(decode_string(&atom).as_deref(), Some(*s));
+    }
+}
+
+#[test]
+fn test_decode_invalid() {
+    let atoms = [
+        "",
+        "\"",
+        "abc",
+        "a\"b\"",
+        "\"a\"b",
+        "\"a\"\"b\"",
+        "\"\\\"",
+        "\"\\x\"",
+        "\"\\u\"",
+        "\"\\u{}\"",
+        "\"\\u{41\"",
+        "\"\\u{g}\"",
+        "\"\\u{1234567}\"",
+        "\"\\u{d800}\"",
+        "\"\\u{110000}\"",
+        "\"\t\"",
+        "\"\u{e9}\"",
+    ];
+    for atom in atoms.iter() {
+        assert_eq!(decode_string(atom), None, "{:?}", atom);
+    }
+}
+
+#[test]
+fn test_suggest_atom() {
+    assert_eq!(suggest_atom("abc"), "abc");
+    assert_eq!(suggest_atom("a\"b c\""), "a\"b c\"");
+    assert_eq!(suggest_atom("a\"b"), "\"a\\\"b\"");
+    assert_eq!(suggest_atom("x\\"), "\"x\\\\\"");
+    assert_eq!(suggest_atom("line\n"), "\"line\\n\"");
+    assert_eq!(suggest_atom("\u{e9}"), "\"\\u{e9}\"");
+    for text in ["", "a b", "(x)", "\"\\", "a;b"].iter() {
+        let atom = suggest_atom(text);
+        assert!(check_atom(&atom), "{:?}", atom);
+        assert_eq!(decode_string(&atom).as_deref(), Some(*text));
+    }
+}
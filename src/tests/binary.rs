@@ -0,0 +1,202 @@
+use alloc::vec::Vec;
+
+use crate::{
+    parse_tree, parse_tree_with, sise_tree, to_binary, BinaryError, BinaryOptions, BinaryReader,
+    OwnedParsedItem, ParsedItem, Parser, TreeBuilder, TreeNode,
+};
+
+const PLAIN: BinaryOptions = BinaryOptions { dedup_atoms: false };
+const DEDUP: BinaryOptions = BinaryOptions { dedup_atoms: true };
+
+fn trees() -> [TreeNode; 5] {
+    [
+        sise_tree!("atom"),
+        sise_tree!([]),
+        sise_tree!([[], [[]]]),
+        sise_tree!(["define", ["f", "x"], ["*", "x", "x", "\"a \\\" )\""]]),
+        sise_tree!(["a", "bb", "ccc", "bb", "a", ["a"]]),
+    ]
+}
+
+#[test]
+fn test_round_trip() {
+    for tree in trees().iter() {
+        for &options in [PLAIN, DEDUP].iter() {
+            let data = to_binary(tree, options);
+            let mut reader = BinaryReader::new(&data).unwrap();
+            assert_eq!(parse_tree(&mut reader).as_ref(), Ok(tree));
+            reader.finish().unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_same_items_as_parser() {
+    let text = "(define (f x) (* x x \"a \\\" )\") ())";
+    let mut parser = Parser::new(text);
+    let tree = parse_tree(&mut parser).unwrap();
+    parser.finish().unwrap();
+
+    // Items without positions
+    fn items<'a, I: Iterator<Item = ParsedItem<'a>>>(items: I) -> Vec<OwnedParsedItem> {
+        items
+            .map(|item| match item {
+                ParsedItem::Atom(atom, _) => ParsedItem::Atom(atom, 0),
+                ParsedItem::ListStart(_) => ParsedItem::ListStart(0),
+                ParsedItem::ListEnd(_) => ParsedItem::ListEnd(0),
+            })
+            .map(OwnedParsedItem::from)
+            .collect()
+    }
+
+    let text_items = items(Parser::new(text).map(Result::unwrap));
+    for &options in [PLAIN, DEDUP].iter() {
+        let data = to_binary(&tree, options);
+        let mut reader = BinaryReader::new(&data).unwrap();
+        let mut binary_items = Vec::new();
+        for _ in 0..text_items.len() {
+            binary_items.push(reader.next_item().unwrap());
+        }
+        reader.finish().unwrap();
+        assert_eq!(items(binary_items.into_iter()), text_items);
+    }
+}
+
+#[test]
+fn test_encoding() {
+    let tree = sise_tree!(["ab", ["ab", "c"]]);
+    assert_eq!(
+        to_binary(&tree, PLAIN),
+        b"SISB\x01\x00\x00\x06ab\x00\x06ab\x04c\x01\x01",
+    );
+    assert_eq!(
+        to_binary(&tree, DEDUP),
+        b"SISB\x01\x01\x02ab\x00\x03\x00\x03\x04c\x01\x01",
+    );
+
+    // Long atoms use multi-byte lengths
+    let atom = "x".repeat(100);
+    let mut expected = b"SISB\x01\x00\xCA\x01".to_vec();
+    expected.extend_from_slice(atom.as_bytes());
+    let tree = TreeNode::Atom(atom);
+    assert_eq!(to_binary(&tree, DEDUP), expected);
+}
+
+#[test]
+fn test_positions() {
+    let data = to_binary(&sise_tree!(["ab", ["ab"]]), DEDUP);
+    let mut reader = BinaryReader::new(&data).unwrap();
+    assert_eq!(reader.next_item().unwrap(), ParsedItem::ListStart(9));
+    assert_eq!(reader.next_item().unwrap(), ParsedItem::Atom("ab", 10));
+    assert_eq!(reader.next_item().unwrap(), ParsedItem::ListStart(11));
+    assert_eq!(reader.next_item().unwrap(), ParsedItem::Atom("ab", 12));
+    assert_eq!(reader.next_item().unwrap(), ParsedItem::ListEnd(13));
+    assert_eq!(reader.next_item().unwrap(), ParsedItem::ListEnd(14));
+    reader.finish().unwrap();
+}
+
+#[test]
+fn test_parse_tree_with() {
+    struct AtomCounter(usize);
+
+    impl TreeBuilder<'_> for AtomCounter {
+        type Output = usize;
+
+        fn atom(&mut self, _: &str, _: usize) {
+            self.0 += 1;
+        }
+
+        fn begin_list(&mut self, _: usize) {}
+
+        fn end_list(&mut self, _: usize) {}
+
+        fn finish(self) -> usize {
+            self.0
+        }
+    }
+
+    let data = to_binary(&sise_tree!(["a", ["b", "c"], "d"]), PLAIN);
+    let mut reader = BinaryReader::new(&data).unwrap();
+    assert_eq!(parse_tree_with(&mut reader, AtomCounter(0)), Ok(4));
+}
+
+#[test]
+fn test_header_errors() {
+    let cases: &[(&[u8], BinaryError)] = &[
+        (b"", BinaryError::InvalidHeader),
+        (b"SISB\x02\x00\x00", BinaryError::InvalidHeader),
+        (b"(a)", BinaryError::InvalidHeader),
+        (b"SISB\x01", BinaryError::UnexpectedEof { pos: 5 }),
+        (b"SISB\x01\x01\x06a", BinaryError::UnexpectedEof { pos: 8 }),
+        (b"SISB\x01\x01\x01(", BinaryError::InvalidAtom { pos: 6 }),
+        (b"SISB\x01\x01\x00", BinaryError::InvalidAtom { pos: 6 }),
+        (
+            b"SISB\x01\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x02",
+            BinaryError::InvalidVarint { pos: 5 },
+        ),
+    ];
+    for &(data, ref expected) in cases.iter() {
+        assert_eq!(
+            BinaryReader::new(data).err().as_ref(),
+            Some(expected),
+            "{:?}",
+            data
+        );
+    }
+}
+
+#[test]
+fn test_body_errors() {
+    let cases: &[(&[u8], BinaryError)] = &[
+        (b"SISB\x01\x00", BinaryError::UnexpectedEof { pos: 6 }),
+        (
+            b"SISB\x01\x00\x00\x04a",
+            BinaryError::UnexpectedEof { pos: 9 },
+        ),
+        (
+            b"SISB\x01\x00\x01",
+            BinaryError::UnexpectedListEnd { pos: 6 },
+        ),
+        (
+            b"SISB\x01\x00\x06a\xFF",
+            BinaryError::InvalidAtom { pos: 6 },
+        ),
+        (b"SISB\x01\x00\x08a b", BinaryError::InvalidAtom { pos: 6 }),
+        (
+            b"SISB\x01\x00\x03",
+            BinaryError::InvalidAtomIndex { pos: 6 },
+        ),
+        (
+            b"SISB\x01\x01\x01a\x00\x03\x05",
+            BinaryError::InvalidAtomIndex { pos: 10 },
+        ),
+        (
+            b"SISB\x01\x00\x00\x80",
+            BinaryError::UnexpectedEof { pos: 8 },
+        ),
+    ];
+    for &(data, ref expected) in cases.iter() {
+        let mut reader = BinaryReader::new(data).unwrap();
+        assert_eq!(
+            parse_tree(&mut reader).as_ref(),
+            Err(expected),
+            "{:?}",
+            data
+        );
+    }
+}
+
+#[test]
+fn test_trailing_data() {
+    let mut reader = BinaryReader::new(b"SISB\x01\x00\x04a\x04b").unwrap();
+    assert_eq!(reader.next_item().unwrap(), ParsedItem::Atom("a", 6));
+    assert_eq!(reader.finish(), Err(BinaryError::ExpectedEof { pos: 8 }));
+}
+
+#[test]
+#[should_panic(expected = "parsing finished")]
+fn test_read_after_root() {
+    let mut reader = BinaryReader::new(b"SISB\x01\x00\x04a").unwrap();
+    reader.next_item().unwrap();
+    let _ = reader.next_item();
+}
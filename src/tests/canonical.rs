@@ -0,0 +1,99 @@
+use crate::{
+    canonicalize, canonicalize_tree, canonicalize_with, hash_tree, parse_tree, sise_tree,
+    ParseError, Parser, ParserOptions, TreeNode,
+};
+
+const CASES: &[(&str, &str)] = &[
+    ("atom", "atom"),
+    ("  atom  \n", "atom"),
+    ("()", "()"),
+    ("( )", "()"),
+    ("(())", "(())"),
+    ("(() ())", "(() ())"),
+    ("( ( ) ( ) )", "(() ())"),
+    ("(a(b)c)", "(a (b) c)"),
+    ("(a\n\t(b  c)\r\n  d)", "(a (b c) d)"),
+    ("(a ; comment\n b)", "(a b)"),
+    ("(\"a  b\" \"c\\\" ; d\")", "(\"a  b\" \"c\\\" ; d\")"),
+    ("; header\n(x)\n; footer", "(x)"),
+];
+
+#[test]
+fn test_canonicalize() {
+    for &(input, expected) in CASES.iter() {
+        let canonical = canonicalize(input).unwrap();
+        assert_eq!(canonical, expected, "{:?}", input);
+        // Idempotent
+        assert_eq!(canonicalize(&canonical).unwrap(), canonical);
+    }
+}
+
+#[test]
+fn test_canonicalize_tree_agrees() {
+    for &(input, expected) in CASES.iter() {
+        let mut parser = Parser::new(input);
+        let tree = parse_tree(&mut parser).unwrap();
+        parser.finish().unwrap();
+        assert_eq!(canonicalize_tree(&tree), expected);
+    }
+}
+
+#[test]
+fn test_canonicalize_with_options() {
+    let options = ParserOptions {
+        block_comments: true,
+        datum_comments: true,
+    };
+    assert_eq!(
+        canonicalize_with("#;x (a #| (b) |# #;(c) d) #| e |#", options).unwrap(),
+        "(a d)",
+    );
+}
+
+#[test]
+fn test_canonicalize_errors() {
+    assert_eq!(
+        canonicalize("(a b"),
+        Err(ParseError::UnexpectedEof { pos: 4 })
+    );
+    assert_eq!(canonicalize("a b"), Err(ParseError::ExpectedEof { pos: 2 }));
+    assert_eq!(canonicalize(""), Err(ParseError::UnexpectedEof { pos: 0 }));
+}
+
+#[test]
+fn test_hash_tree() {
+    // FNV-1a of the canonical form. These values must not change
+    // between versions.
+    assert_eq!(hash_tree(&sise_tree!("a")), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(hash_tree(&sise_tree!([])), 0x07e1_1f07_b4a6_665a);
+    assert_eq!(hash_tree(&sise_tree!(["a", "b"])), 0x2718_87f6_0048_517f);
+
+    // Atoms are separated
+    assert_ne!(
+        hash_tree(&sise_tree!(["ab"])),
+        hash_tree(&sise_tree!(["a", "b"]))
+    );
+    assert_ne!(
+        hash_tree(&sise_tree!([["a"], "b"])),
+        hash_tree(&sise_tree!([["a", "b"]]))
+    );
+}
+
+#[test]
+fn test_hash_agrees_with_canonical_form() {
+    fn fnv1a(data: &[u8]) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for &byte in data.iter() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        hash
+    }
+
+    for &(input, _) in CASES.iter() {
+        let mut parser = Parser::new(input);
+        let tree: TreeNode = parse_tree(&mut parser).unwrap();
+        let canonical = canonicalize(input).unwrap();
+        assert_eq!(hash_tree(&tree), fnv1a(canonical.as_bytes()));
+    }
+}
@@ -0,0 +1,69 @@
+use alloc::string::ToString as _;
+use alloc::vec::Vec;
+
+use crate::{sise_tree, Atom, AtomError, CheckedTreeError, CheckedTreeNode, TreeNode};
+
+#[test]
+fn test_round_trip() {
+    let trees = [
+        sise_tree!("a"),
+        sise_tree!([]),
+        sise_tree!(["define", ["f", "x"], ["*", "x", "\"a ; b\""]]),
+    ];
+    for tree in trees.iter() {
+        let checked = CheckedTreeNode::try_from(tree.clone()).unwrap();
+        assert_eq!(checked.is_atom(), tree.is_atom());
+        assert_eq!(TreeNode::from(checked), *tree);
+    }
+}
+
+#[test]
+fn test_invalid() {
+    assert_eq!(
+        CheckedTreeNode::try_from(sise_tree!("")),
+        Err(CheckedTreeError {
+            path: Vec::new(),
+            error: AtomError::Empty,
+        }),
+    );
+    let error = CheckedTreeNode::try_from(sise_tree!(["a", ["b", "c"], ["d", "\"e"]])).unwrap_err();
+    assert_eq!(
+        error,
+        CheckedTreeError {
+            path: alloc::vec![2, 1],
+            error: AtomError::UnfinishedString { pos: 0 },
+        },
+    );
+}
+
+#[test]
+fn test_edit() {
+    let mut checked = CheckedTreeNode::try_from(sise_tree!(["a"])).unwrap();
+    checked
+        .as_mut_list()
+        .unwrap()
+        .push(CheckedTreeNode::from(Atom::try_from("b").unwrap()));
+    assert_eq!(checked.as_list().unwrap()[1].as_atom().unwrap(), "b");
+    assert_eq!(TreeNode::from(checked), sise_tree!(["a", "b"]));
+}
+
+#[test]
+fn test_serialize() {
+    let trees = [
+        sise_tree!("a"),
+        sise_tree!([]),
+        sise_tree!(["define", ["f", "x"], ["*", "x", "\"a ; b\""], [[]]]),
+    ];
+    for tree in trees.iter() {
+        let checked = CheckedTreeNode::try_from(tree.clone()).unwrap();
+        assert_eq!(checked.to_string(), tree.to_string());
+        assert_eq!(
+            alloc::format!("{:#}", checked),
+            alloc::format!("{:#}", tree)
+        );
+        assert_eq!(
+            alloc::format!("{:#1.1}", checked),
+            alloc::format!("{:#1.1}", tree),
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::io::Read;
+
+use crate::{
+    sise_tree, CodecError, MessageReader, MessageWriter, ParseError, ParserOptions, TreeNode,
+};
+
+/// Reader that returns at most `chunk_size` bytes at a time.
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk_size: usize,
+}
+
+impl Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.chunk_size.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+fn messages() -> [TreeNode; 5] {
+    [
+        sise_tree!(["call", "1", ["add", "2", "3"]]),
+        sise_tree!("ping"),
+        sise_tree!(["reply", "1", "\"a \\\" string\""]),
+        sise_tree!([]),
+        sise_tree!("pong"),
+    ]
+}
+
+#[test]
+fn test_round_trip() {
+    let mut writer = MessageWriter::new(Vec::new());
+    for message in messages().iter() {
+        writer.write_message(message).unwrap();
+    }
+    writer.flush().unwrap();
+    let data = writer.into_inner();
+
+    for &chunk_size in [1, 2, 3, 7, 1000].iter() {
+        let reader = ChunkedReader {
+            data: &data,
+            chunk_size,
+        };
+        let received: Vec<_> = MessageReader::new(reader).map(Result::unwrap).collect();
+        assert_eq!(received, messages());
+    }
+}
+
+#[test]
+fn test_comments_between_messages() {
+    let options = ParserOptions {
+        block_comments: true,
+        datum_comments: true,
+    };
+    let data = b"; header\n(a) #| ) |# #;(b) c\n#;";
+    for &chunk_size in [1, 1000].iter() {
+        let reader = ChunkedReader { data, chunk_size };
+        let mut reader = MessageReader::with_options(reader, options);
+        assert_eq!(reader.read_message().unwrap(), Some(sise_tree!(["a"])));
+        assert_eq!(reader.read_message().unwrap(), Some(sise_tree!("c")));
+        match reader.read_message() {
+            Err(CodecError::Parse(ParseError::UnexpectedEof { pos: 31 })) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(reader.read_message().unwrap(), None);
+    }
+}
+
+#[test]
+fn test_parse_error() {
+    let mut data = String::new();
+    for i in 0..1000 {
+        data.push_str(&std::format!("(message {})\n", i));
+    }
+    let pos = data.len() + 4;
+    data.push_str("(x \"\t\")\n");
+
+    let reader = ChunkedReader {
+        data: data.as_bytes(),
+        chunk_size: 100,
+    };
+    let mut reader = MessageReader::new(reader);
+    for _ in 0..1000 {
+        assert!(reader.read_message().unwrap().is_some());
+    }
+    match reader.read_message() {
+        Err(CodecError::Parse(e)) => {
+            assert_eq!(e, ParseError::IllegalChrInString { pos, chr: '\t' });
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(reader.read_message().unwrap(), None);
+}
+
+#[test]
+fn test_large_tokens() {
+    // Each byte of a token or comment that spans many reads is scanned
+    // once, not once per read, which would take minutes here.
+    let size = 1024 * 1024;
+    let long_atom = "a".repeat(size);
+    let long_string = std::format!("\"{}\"", "x \\\"".repeat(size / 4));
+    let mut data = String::new();
+    data.push_str(&long_atom);
+    data.push_str(&" ".repeat(size));
+    data.push('(');
+    data.push_str(&long_string);
+    data.push_str(")\n;");
+    data.push_str(&"c".repeat(size));
+    data.push_str("\n#|");
+    data.push_str(&"#|b|#".repeat(size / 5));
+    data.push_str("|#");
+
+    let reader = ChunkedReader {
+        data: data.as_bytes(),
+        chunk_size: 256,
+    };
+    let options = ParserOptions {
+        block_comments: true,
+        datum_comments: false,
+    };
+    let mut reader = MessageReader::with_options(reader, options);
+    assert_eq!(
+        reader.read_message().unwrap(),
+        Some(TreeNode::Atom(long_atom))
+    );
+    assert_eq!(
+        reader.read_message().unwrap(),
+        Some(TreeNode::List(std::vec![TreeNode::Atom(long_string)])),
+    );
+    assert_eq!(reader.read_message().unwrap(), None);
+}
+
+#[test]
+fn test_reader_max_size() {
+    let data = b"(12345678) (123456789)";
+    for &chunk_size in [1, 4, 1000].iter() {
+        let reader = ChunkedReader { data, chunk_size };
+        let mut reader = MessageReader::new(reader);
+        reader.set_max_message_size(11);
+        assert_eq!(
+            reader.read_message().unwrap(),
+            Some(sise_tree!(["12345678"]))
+        );
+        match reader.read_message() {
+            Err(CodecError::MessageTooLarge { pos: 10 }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(reader.read_message().unwrap(), None);
+    }
+}
+
+#[test]
+fn test_reader_max_size_unfinished() {
+    // An endless message is rejected without reading the whole stream.
+    let reader = std::io::repeat(b'(');
+    let mut reader = MessageReader::new(reader);
+    reader.set_max_message_size(100_000);
+    match reader.read_message() {
+        Err(CodecError::MessageTooLarge { pos: 0 }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn test_writer_max_size() {
+    let mut writer = MessageWriter::new(Vec::new());
+    writer.set_max_message_size(6);
+    writer.write_message(&sise_tree!(["abc"])).unwrap();
+    match writer.write_message(&sise_tree!(["abcd"])) {
+        Err(CodecError::MessageTooLarge { pos: 6 }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    writer.write_message(&sise_tree!("abcd")).unwrap();
+    assert_eq!(writer.into_inner(), b"(abc)\nabcd\n");
+}
+
+#[test]
+fn test_io_error() {
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "connection reset",
+            ))
+        }
+    }
+
+    let mut reader = MessageReader::new(std::io::Cursor::new(b"(a) (b").chain(FailingReader));
+    assert_eq!(reader.read_message().unwrap(), Some(sise_tree!(["a"])));
+    match reader.read_message() {
+        Err(CodecError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::ConnectionReset),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn test_tcp() {
+    let listener = match std::net::TcpListener::bind("127.0.0.1:0") {
+        Ok(listener) => listener,
+        // Networking might not be available
+        Err(_) => return,
+    };
+    let addr = listener.local_addr().unwrap();
+
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = MessageReader::new(stream.try_clone().unwrap());
+        let mut writer = MessageWriter::new(stream);
+        while let Some(message) = reader.read_message().unwrap() {
+            writer
+                .write_message(&sise_tree!(["echo", (message)]))
+                .unwrap();
+        }
+    });
+
+    let stream = std::net::TcpStream::connect(addr).unwrap();
+    let mut reader = MessageReader::new(stream.try_clone().unwrap());
+    let mut writer = MessageWriter::new(stream);
+    for message in messages().iter().cloned() {
+        writer.write_message(&message).unwrap();
+        assert_eq!(
+            reader.read_message().unwrap(),
+            Some(sise_tree!(["echo", (message)])),
+        );
+    }
+    writer
+        .into_inner()
+        .shutdown(std::net::Shutdown::Write)
+        .unwrap();
+    assert_eq!(reader.read_message().unwrap(), None);
+    server.join().unwrap();
+}
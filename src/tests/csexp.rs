@@ -0,0 +1,122 @@
+use crate::{
+    from_csexp, from_csexp_transport, sise_tree, to_csexp, to_csexp_transport, CsexpError, TreeNode,
+};
+
+fn trees() -> [TreeNode; 5] {
+    [
+        sise_tree!("atom"),
+        sise_tree!([]),
+        sise_tree!([[], [[]]]),
+        sise_tree!(["define", ["f", "x"], ["*", "x", "\"a \\\" )\""]]),
+        sise_tree!(["a", "bb", "ccc", "dddd", "eeeee"]),
+    ]
+}
+
+#[test]
+fn test_canonical() {
+    assert_eq!(to_csexp(&sise_tree!("atom")), b"4:atom");
+    assert_eq!(to_csexp(&sise_tree!([])), b"()");
+    assert_eq!(
+        to_csexp(&sise_tree!(["a", ["\"b c\""], []])),
+        b"(1:a(5:\"b c\")())",
+    );
+
+    let long_atom = "x".repeat(1234);
+    let mut expected = b"1234:".to_vec();
+    expected.extend_from_slice(long_atom.as_bytes());
+    assert_eq!(to_csexp(&TreeNode::Atom(long_atom.clone())), expected);
+    assert_eq!(from_csexp(&expected), Ok(TreeNode::Atom(long_atom)));
+}
+
+#[test]
+fn test_round_trip() {
+    for tree in trees().iter() {
+        assert_eq!(from_csexp(&to_csexp(tree)).as_ref(), Ok(tree));
+        let transport = to_csexp_transport(tree);
+        assert_eq!(
+            from_csexp_transport(transport.as_bytes()).as_ref(),
+            Ok(tree)
+        );
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_invalid_atom_panics() {
+    to_csexp(&sise_tree!(["a b"]));
+}
+
+#[test]
+fn test_read_errors() {
+    let cases: &[(&[u8], CsexpError)] = &[
+        (b"", CsexpError::UnexpectedEof { pos: 0 }),
+        (b"(", CsexpError::UnexpectedEof { pos: 1 }),
+        (b"(1:a", CsexpError::UnexpectedEof { pos: 4 }),
+        (b"3:ab", CsexpError::UnexpectedEof { pos: 4 }),
+        (b"12", CsexpError::UnexpectedEof { pos: 2 }),
+        (b")", CsexpError::UnexpectedByte { pos: 0 }),
+        (b"(1:a))", CsexpError::ExpectedEof { pos: 5 }),
+        (b"1:a1:b", CsexpError::ExpectedEof { pos: 3 }),
+        (b"(a)", CsexpError::UnexpectedByte { pos: 1 }),
+        (b"(1:a 1:b)", CsexpError::UnexpectedByte { pos: 4 }),
+        (b"[4:text]3:abc", CsexpError::UnexpectedByte { pos: 0 }),
+        (b"1x:a", CsexpError::UnexpectedByte { pos: 1 }),
+        (b"(01:a)", CsexpError::InvalidLength { pos: 1 }),
+        (b"00:", CsexpError::InvalidLength { pos: 0 }),
+        (
+            b"99999999999999999999999:a",
+            CsexpError::InvalidLength { pos: 0 },
+        ),
+        (b"0:", CsexpError::InvalidAtom { pos: 2 }),
+        (b"(1:a3:a b)", CsexpError::InvalidAtom { pos: 6 }),
+        (b"1:(", CsexpError::InvalidAtom { pos: 2 }),
+        (b"2:\xC3\x28", CsexpError::InvalidAtom { pos: 2 }),
+        (b"2:\xC3\xA9", CsexpError::InvalidAtom { pos: 2 }),
+        (b"3:\"a\t", CsexpError::InvalidAtom { pos: 2 }),
+    ];
+    for &(data, ref expected) in cases.iter() {
+        assert_eq!(from_csexp(data).as_ref(), Err(expected), "{:?}", data);
+    }
+}
+
+#[test]
+fn test_transport() {
+    // Every padding length
+    assert_eq!(to_csexp_transport(&sise_tree!("a")), "{MTph}");
+    assert_eq!(to_csexp_transport(&sise_tree!("ab")), "{MjphYg==}");
+    assert_eq!(to_csexp_transport(&sise_tree!("abc")), "{MzphYmM=}");
+    assert_eq!(to_csexp_transport(&sise_tree!("abcd")), "{NDphYmNk}");
+
+    assert_eq!(from_csexp_transport(b"{MjphYg==}"), Ok(sise_tree!("ab")));
+    assert_eq!(
+        from_csexp_transport(b"{Mz phY\r\n mM=}"),
+        Ok(sise_tree!("abc"))
+    );
+}
+
+#[test]
+fn test_transport_errors() {
+    let cases: &[(&[u8], CsexpError)] = &[
+        (b"", CsexpError::UnexpectedEof { pos: 0 }),
+        (b"MTph", CsexpError::UnexpectedByte { pos: 0 }),
+        (b"{MTph", CsexpError::UnexpectedEof { pos: 5 }),
+        (b"{MTph} ", CsexpError::ExpectedEof { pos: 6 }),
+        (b"{MT.h}", CsexpError::InvalidBase64 { pos: 3 }),
+        (b"{MTp}", CsexpError::InvalidBase64 { pos: 3 }),
+        (b"{MjphYg=}", CsexpError::InvalidBase64 { pos: 7 }),
+        (b"{MjphYg==Yg==}", CsexpError::InvalidBase64 { pos: 9 }),
+        (b"{M===}", CsexpError::InvalidBase64 { pos: 2 }),
+        // Unused bits are not zero
+        (b"{MjphYh==}", CsexpError::InvalidBase64 { pos: 8 }),
+        // Errors in the decoded data
+        (b"{MjphIA==}", CsexpError::InvalidAtom { pos: 2 }),
+    ];
+    for &(data, ref expected) in cases.iter() {
+        assert_eq!(
+            from_csexp_transport(data).as_ref(),
+            Err(expected),
+            "{:?}",
+            data
+        );
+    }
+}
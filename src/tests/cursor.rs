@@ -0,0 +1,115 @@
+use crate::{sise_tree, Cursor, TreeNode};
+
+#[test]
+fn test_moves() {
+    let mut tree = sise_tree!(["a", ["b", "c"], "d"]);
+    let mut cursor = Cursor::new(&mut tree);
+    assert!(cursor.is_root());
+    assert!(!cursor.up());
+    assert!(!cursor.left());
+    assert!(!cursor.right());
+    assert!(!cursor.down(3));
+
+    assert!(cursor.down(1));
+    assert_eq!(cursor.path(), [1]);
+    assert_eq!(*cursor.node(), sise_tree!(["b", "c"]));
+    assert!(cursor.down(1));
+    assert_eq!(*cursor.node(), "c");
+    assert!(!cursor.down(0));
+    assert!(!cursor.right());
+    assert!(cursor.left());
+    assert!(!cursor.left());
+    assert_eq!(cursor.path(), [1, 0]);
+    assert!(cursor.up());
+    assert!(cursor.right());
+    assert_eq!(*cursor.node(), "d");
+    assert!(!cursor.right());
+
+    cursor.to_root();
+    assert!(cursor.is_root());
+    assert_eq!(cursor.node(), cursor.root());
+}
+
+#[test]
+fn test_edits() {
+    let mut tree = sise_tree!(["a", ["b", "c"], "d"]);
+    let mut cursor = Cursor::new(&mut tree);
+    cursor.down(1);
+    cursor.down(0);
+    assert_eq!(cursor.replace(TreeNode::from("x")), "b");
+    cursor.insert_before(TreeNode::from("y"));
+    assert_eq!(cursor.path(), [1, 1]);
+    assert_eq!(*cursor.node(), "x");
+    cursor.insert_after(TreeNode::from("z"));
+    assert_eq!(cursor.path(), [1, 1]);
+    cursor.wrap();
+    assert_eq!(*cursor.node(), sise_tree!(["x"]));
+    cursor
+        .node_mut()
+        .as_mut_list()
+        .unwrap()
+        .push(TreeNode::from("w"));
+    assert_eq!(
+        *cursor.into_root(),
+        sise_tree!(["a", ["y", ["x", "w"], "z", "c"], "d"]),
+    );
+
+    let mut cursor = Cursor::new(&mut tree);
+    cursor.wrap();
+    assert!(cursor.is_root());
+    assert_eq!(tree, sise_tree!([["a", ["y", ["x", "w"], "z", "c"], "d"]]),);
+}
+
+#[test]
+fn test_delete() {
+    let mut tree = sise_tree!(["a", ["b", "c"], "d"]);
+    let mut cursor = Cursor::new(&mut tree);
+    cursor.down(1);
+    cursor.down(0);
+    // Moves to the next element
+    assert_eq!(cursor.delete(), "b");
+    assert_eq!(cursor.path(), [1, 0]);
+    assert_eq!(*cursor.node(), "c");
+    // Moves to the parent
+    assert_eq!(cursor.delete(), "c");
+    assert_eq!(cursor.path(), [1]);
+    assert_eq!(*cursor.node(), sise_tree!([]));
+    cursor.right();
+    // Moves to the previous element
+    assert_eq!(cursor.delete(), "d");
+    assert_eq!(cursor.path(), [1]);
+    assert_eq!(tree, sise_tree!(["a", []]));
+}
+
+#[test]
+fn test_splice() {
+    let mut tree = sise_tree!(["a", "b", "c"]);
+    let mut cursor = Cursor::new(&mut tree);
+    cursor.down(1);
+    assert_eq!(
+        cursor.splice([TreeNode::from("x"), TreeNode::from("y")]),
+        "b"
+    );
+    assert_eq!(cursor.path(), [1]);
+    assert_eq!(*cursor.node(), "x");
+    cursor.down(0);
+    cursor.to_root();
+    cursor.down(3);
+    assert_eq!(cursor.splice(None), "c");
+    assert_eq!(cursor.path(), [2]);
+    assert_eq!(tree, sise_tree!(["a", "x", "y"]));
+}
+
+#[test]
+#[should_panic(expected = "cursor is at the root")]
+fn test_delete_root() {
+    let mut tree = sise_tree!(["a"]);
+    Cursor::new(&mut tree).delete();
+}
+
+#[test]
+#[should_panic(expected = "cursor is at the root")]
+fn test_insert_root() {
+    let mut tree = sise_tree!(["a"]);
+    Cursor::new(&mut tree).insert_after(TreeNode::from("b"));
+}
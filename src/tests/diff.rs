@@ -0,0 +1,332 @@
+use alloc::string::{String, ToString as _};
+use alloc::vec::Vec;
+
+use crate::{diff, diff_to_tree, sise_tree, Edit, TreeNode};
+
+fn list_at<'a>(root: &'a mut TreeNode, path: &[usize]) -> &'a mut Vec<TreeNode> {
+    let mut node = root;
+    for &index in path.iter() {
+        node = &mut node.as_mut_list().unwrap()[index];
+    }
+    node.as_mut_list().unwrap()
+}
+
+fn node_at<'a>(root: &'a mut TreeNode, path: &[usize]) -> &'a mut TreeNode {
+    match path.split_last() {
+        None => root,
+        Some((&last, parent)) => &mut list_at(root, parent)[last],
+    }
+}
+
+/// Applies edits, checking that they are valid.
+fn apply(root: &mut TreeNode, edits: &[Edit]) {
+    for edit in edits.iter() {
+        match *edit {
+            Edit::Insert { ref path, ref node } => {
+                let (&last, parent) = path.split_last().unwrap();
+                list_at(root, parent).insert(last, node.clone());
+            }
+            Edit::Delete { ref path } => {
+                let (&last, parent) = path.split_last().unwrap();
+                list_at(root, parent).remove(last);
+            }
+            Edit::ReplaceAtom { ref path, ref atom } => {
+                let target = node_at(root, path);
+                assert!(target.is_atom());
+                *target = TreeNode::Atom(atom.clone());
+            }
+            Edit::Replace { ref path, ref node } => {
+                let target = node_at(root, path);
+                assert_ne!(target.is_atom(), node.is_atom());
+                *target = node.clone();
+            }
+            Edit::Move { ref from, ref to } => {
+                let (&from_last, from_parent) = from.split_last().unwrap();
+                let (&to_last, to_parent) = to.split_last().unwrap();
+                assert_eq!(from_parent, to_parent);
+                let list = list_at(root, from_parent);
+                let node = list.remove(from_last);
+                list.insert(to_last, node);
+            }
+        }
+    }
+}
+
+fn check_diff(old: &TreeNode, new: &TreeNode) -> Vec<Edit> {
+    let edits = diff(old, new);
+    let mut patched = old.clone();
+    apply(&mut patched, &edits);
+    assert_eq!(&patched, new, "edits: {:?}", edits);
+    edits
+}
+
+#[test]
+fn test_equal() {
+    let tree = sise_tree!(["a", ["b", "c"]]);
+    assert_eq!(check_diff(&tree, &tree), []);
+}
+
+#[test]
+fn test_root() {
+    assert_eq!(
+        check_diff(&sise_tree!("a"), &sise_tree!("b")),
+        [Edit::ReplaceAtom {
+            path: alloc::vec![],
+            atom: String::from("b"),
+        }],
+    );
+    assert_eq!(
+        check_diff(&sise_tree!("a"), &sise_tree!(["a"])),
+        [Edit::Replace {
+            path: alloc::vec![],
+            node: sise_tree!(["a"]),
+        }],
+    );
+}
+
+#[test]
+fn test_insert_delete() {
+    assert_eq!(
+        check_diff(&sise_tree!(["a", "b", "c"]), &sise_tree!(["a", "x", "b"])),
+        [
+            Edit::Delete {
+                path: alloc::vec![2],
+            },
+            Edit::Insert {
+                path: alloc::vec![1],
+                node: sise_tree!("x"),
+            },
+        ],
+    );
+    assert_eq!(
+        check_diff(&sise_tree!([]), &sise_tree!(["a", ["b"]])),
+        [
+            Edit::Insert {
+                path: alloc::vec![0],
+                node: sise_tree!("a"),
+            },
+            Edit::Insert {
+                path: alloc::vec![1],
+                node: sise_tree!(["b"]),
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_replace_in_place() {
+    // Changed elements between the same unchanged elements are diffed
+    // instead of deleted and inserted.
+    assert_eq!(
+        check_diff(
+            &sise_tree!(["a", "b", ["c", "d"], "e"]),
+            &sise_tree!(["a", "x", [["c"], "d"], "e"]),
+        ),
+        [
+            Edit::ReplaceAtom {
+                path: alloc::vec![1],
+                atom: String::from("x"),
+            },
+            Edit::Replace {
+                path: alloc::vec![2, 0],
+                node: sise_tree!(["c"]),
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_reorder() {
+    assert_eq!(
+        check_diff(
+            &sise_tree!(["a", "b", "c", "d"]),
+            &sise_tree!(["d", "a", "b", "c"]),
+        ),
+        [Edit::Move {
+            from: alloc::vec![3],
+            to: alloc::vec![0],
+        }],
+    );
+    assert_eq!(
+        check_diff(
+            &sise_tree!(["a", "b", "c", "d"]),
+            &sise_tree!(["b", "c", "d", "a"]),
+        ),
+        [Edit::Move {
+            from: alloc::vec![0],
+            to: alloc::vec![3],
+        }],
+    );
+}
+
+#[test]
+fn test_reorder_with_changes() {
+    let old = sise_tree!([
+        "config",
+        ["name", "x"],
+        ["port", "80"],
+        ["hosts", "a", "b"],
+        ["debug"],
+    ]);
+    let new = sise_tree!([
+        "config",
+        ["hosts", "b", "a"],
+        ["name", "x"],
+        ["port", "8080"],
+    ]);
+    assert_eq!(
+        check_diff(&old, &new),
+        [
+            Edit::Delete {
+                path: alloc::vec![4],
+            },
+            Edit::Move {
+                from: alloc::vec![3],
+                to: alloc::vec![1],
+            },
+            Edit::Move {
+                from: alloc::vec![1, 1],
+                to: alloc::vec![1, 2],
+            },
+            Edit::ReplaceAtom {
+                path: alloc::vec![3, 1],
+                atom: String::from("8080"),
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_diff_to_tree() {
+    let edits = [
+        Edit::Insert {
+            path: alloc::vec![0],
+            node: sise_tree!(["a", "b"]),
+        },
+        Edit::Delete {
+            path: alloc::vec![1, 2],
+        },
+        Edit::ReplaceAtom {
+            path: alloc::vec![],
+            atom: String::from("c"),
+        },
+        Edit::Replace {
+            path: alloc::vec![3],
+            node: sise_tree!("d"),
+        },
+        Edit::Move {
+            from: alloc::vec![0],
+            to: alloc::vec![4],
+        },
+    ];
+    assert_eq!(
+        diff_to_tree(&edits),
+        sise_tree!([
+            "diff",
+            ["insert", ["path", "0"], ["a", "b"]],
+            ["delete", ["path", "1", "2"]],
+            ["replace-atom", ["path"], "c"],
+            ["replace", ["path", "3"], "d"],
+            ["move", ["path", "0"], ["path", "4"]],
+        ]),
+    );
+    assert_eq!(diff_to_tree(&[]), sise_tree!(["diff"]));
+}
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % n
+    }
+}
+
+fn random_tree(rng: &mut Rng, depth: usize) -> TreeNode {
+    const ATOMS: &[&str] = &["a", "b", "c", "d", "e"];
+    if depth == 0 || rng.next(3) == 0 {
+        TreeNode::from(ATOMS[rng.next(ATOMS.len())])
+    } else {
+        let len = rng.next(6);
+        TreeNode::List((0..len).map(|_| random_tree(rng, depth - 1)).collect())
+    }
+}
+
+fn mutate(rng: &mut Rng, tree: &mut TreeNode, depth: usize) {
+    let list = match tree.as_mut_list() {
+        Some(list) => list,
+        None => {
+            *tree = random_tree(rng, depth);
+            return;
+        }
+    };
+    for _ in 0..rng.next(4) {
+        match rng.next(5) {
+            0 if !list.is_empty() => {
+                let i = rng.next(list.len());
+                list.remove(i);
+            }
+            1 => {
+                let i = rng.next(list.len() + 1);
+                list.insert(i, random_tree(rng, depth));
+            }
+            2 if !list.is_empty() => {
+                let node = list.remove(rng.next(list.len()));
+                let i = rng.next(list.len() + 1);
+                list.insert(i, node);
+            }
+            _ if !list.is_empty() => {
+                let i = rng.next(list.len());
+                mutate(rng, &mut list[i], depth.saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_random() {
+    let mut rng = Rng(0xD1FF);
+    for _ in 0..3000 {
+        let old = random_tree(&mut rng, 4);
+        let mut new = old.clone();
+        mutate(&mut rng, &mut new, 4);
+        check_diff(&old, &new);
+        check_diff(&new, &old);
+    }
+}
+
+fn numbered_list(len: usize) -> Vec<TreeNode> {
+    (0..len).map(|i| TreeNode::Atom(i.to_string())).collect()
+}
+
+#[test]
+fn test_long_lists() {
+    // Few differences
+    let old = numbered_list(50_000);
+    let mut new = old.clone();
+    for i in (0..new.len()).step_by(1000) {
+        new[i] = TreeNode::from("x");
+    }
+    new.insert(25_000, TreeNode::from("y"));
+    new.remove(40_000);
+    let edits = check_diff(&TreeNode::List(old.clone()), &TreeNode::List(new));
+    assert_eq!(edits.len(), 52);
+
+    // Too many differences to find the longest common subsequence
+    let mut new = old.clone();
+    for i in (0..new.len()).step_by(10) {
+        new[i] = TreeNode::from("x");
+    }
+    new[10_000..20_000].reverse();
+    check_diff(&TreeNode::List(old), &TreeNode::List(new));
+
+    let old = numbered_list(10_000);
+    let mut new = old.clone();
+    new.reverse();
+    let edits = check_diff(&TreeNode::List(old), &TreeNode::List(new));
+    assert_eq!(edits.len(), 9999);
+}
@@ -0,0 +1,152 @@
+use alloc::vec::Vec;
+
+use serde_json::json;
+
+use crate::{
+    json_to_tree, sise_tree, tree_to_json, tree_to_json_lossy, JsonError, JsonErrorKind, TreeNode,
+};
+
+#[track_caller]
+fn check_round_trip(value: serde_json::Value, expected_tree: TreeNode) {
+    let tree = json_to_tree(&value);
+    assert_eq!(tree, expected_tree);
+    assert_eq!(tree_to_json(&tree), Ok(value.clone()));
+    assert_eq!(tree_to_json_lossy(&tree), value);
+}
+
+#[track_caller]
+fn check_error(tree: TreeNode, path: &[usize], kind: JsonErrorKind) {
+    assert_eq!(
+        tree_to_json(&tree),
+        Err(JsonError {
+            path: Vec::from(path),
+            kind,
+        }),
+    );
+}
+
+#[test]
+fn test_scalars() {
+    check_round_trip(json!(null), sise_tree!("null"));
+    check_round_trip(json!(true), sise_tree!("true"));
+    check_round_trip(json!(false), sise_tree!("false"));
+    check_round_trip(json!(0), sise_tree!("0"));
+    check_round_trip(json!(-12), sise_tree!("-12"));
+    check_round_trip(
+        json!(18446744073709551615u64),
+        sise_tree!("18446744073709551615"),
+    );
+    check_round_trip(json!(0.5), sise_tree!("0.5"));
+    check_round_trip(json!(-1.5e300), sise_tree!("-1.5e+300"));
+    check_round_trip(json!(""), sise_tree!("\"\""));
+    check_round_trip(json!("a \"b\"\n"), sise_tree!("\"a \\\"b\\\"\\n\""));
+    check_round_trip(json!("null"), sise_tree!("\"null\""));
+    check_round_trip(json!("\u{e9}"), sise_tree!("\"\\u{e9}\""));
+}
+
+#[test]
+fn test_arrays() {
+    check_round_trip(json!([]), sise_tree!([]));
+    check_round_trip(json!([[]]), sise_tree!([[]]));
+    check_round_trip(
+        json!([1, "a", [null, {}]]),
+        sise_tree!(["1", "\"a\"", ["null", [":"]]]),
+    );
+    check_round_trip(json!([":a"]), sise_tree!(["\":a\""]));
+}
+
+#[test]
+fn test_objects() {
+    check_round_trip(json!({}), sise_tree!([":"]));
+    check_round_trip(json!({ "a": 1 }), sise_tree!([":a", "1"]));
+    check_round_trip(
+        json!({ "": [], "a b": {}, ":x": "y", "(": null }),
+        sise_tree!([
+            ":\"\"",
+            [],
+            ":\"(\"",
+            "null",
+            "::x",
+            "\"y\"",
+            ":\"a b\"",
+            [":"]
+        ]),
+    );
+    check_round_trip(
+        json!({ "outer": { "inner": [{ "k": true }] } }),
+        sise_tree!([":outer", [":inner", [[":k", "true"]]]]),
+    );
+}
+
+#[test]
+fn test_errors() {
+    check_error(sise_tree!("atom"), &[], JsonErrorKind::InvalidAtom);
+    check_error(sise_tree!("+1"), &[], JsonErrorKind::InvalidAtom);
+    check_error(sise_tree!("01"), &[], JsonErrorKind::InvalidAtom);
+    check_error(sise_tree!("1."), &[], JsonErrorKind::InvalidAtom);
+    check_error(sise_tree!("\"a\"b"), &[], JsonErrorKind::InvalidAtom);
+    check_error(
+        sise_tree!(["1", ["2", "x"]]),
+        &[1, 1],
+        JsonErrorKind::InvalidAtom,
+    );
+    check_error(
+        sise_tree!([":a", "1", "b", "2"]),
+        &[2],
+        JsonErrorKind::InvalidKey,
+    );
+    check_error(
+        sise_tree!([":a", "1", ["b"], "2"]),
+        &[2],
+        JsonErrorKind::InvalidKey,
+    );
+    check_error(
+        sise_tree!([":a", "1", ":", "2"]),
+        &[2],
+        JsonErrorKind::InvalidKey,
+    );
+    check_error(
+        sise_tree!([":a", "1", ":b"]),
+        &[2],
+        JsonErrorKind::MissingValue,
+    );
+    check_error(
+        sise_tree!([":a", "1", ":\"a\"", "2"]),
+        &[2],
+        JsonErrorKind::DuplicateKey,
+    );
+    check_error(
+        sise_tree!([":a", [":b", ["x"]]]),
+        &[1, 1, 0],
+        JsonErrorKind::InvalidAtom,
+    );
+}
+
+#[test]
+fn test_error_display() {
+    let error = JsonError {
+        path: alloc::vec![1, 0],
+        kind: JsonErrorKind::InvalidKey,
+    };
+    assert_eq!(std::format!("{}", error), "invalid key at (1 0)");
+}
+
+#[test]
+fn test_lossy() {
+    assert_eq!(
+        tree_to_json_lossy(&sise_tree!(["x", "+1", "\"a\"b", "\"ok\""])),
+        json!(["x", "+1", "\"a\"b", "ok"]),
+    );
+    assert_eq!(
+        tree_to_json_lossy(&sise_tree!([":a", "1", ":b"])),
+        json!([":a", 1, ":b"]),
+    );
+    assert_eq!(
+        tree_to_json_lossy(&sise_tree!([":a", "1", "b", "2"])),
+        json!([":a", 1, "b", 2]),
+    );
+    assert_eq!(
+        tree_to_json_lossy(&sise_tree!([":a", "1", ":a", ["x"]])),
+        json!({ "a": ["x"] }),
+    );
+}
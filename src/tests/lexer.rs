@@ -0,0 +1,279 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{ParseError, ParsedItem, Parser, ParserOptions, TokenKind, Tokenizer};
+
+/// Parses `src_data` using `Tokenizer`, which is implemented
+/// independently from the lexer used by `Parser`.
+fn reference_parse(
+    src_data: &str,
+    options: ParserOptions,
+) -> (Vec<ParsedItem<'_>>, Option<ParseError>) {
+    let mut items = Vec::new();
+    let mut depth = None;
+    // Atom (start and end) whose segments are being merged
+    let mut pending_atom: Option<(usize, usize)> = None;
+    for token in Tokenizer::with_options(src_data, options) {
+        let item = match token.kind {
+            TokenKind::LeftParen => ParsedItem::ListStart(token.span.start),
+            TokenKind::RightParen => ParsedItem::ListEnd(token.span.start),
+            TokenKind::BareAtom | TokenKind::QuotedAtom => {
+                match pending_atom {
+                    Some((start, end)) if end == token.span.start => {
+                        pending_atom = Some((start, token.span.end));
+                    }
+                    _ => {
+                        if let Err(e) =
+                            push_pending_atom(src_data, &mut items, &mut depth, pending_atom)
+                        {
+                            return (items, Some(e));
+                        }
+                        pending_atom = Some((token.span.start, token.span.end));
+                    }
+                }
+                continue;
+            }
+            TokenKind::Error(e) => {
+                let in_string = matches!(
+                    e,
+                    ParseError::IllegalChrInString { .. } | ParseError::UnfinishedString { .. }
+                );
+                match pending_atom {
+                    // The parser does not return incomplete atoms
+                    Some((_, end)) if in_string && end == token.span.start => {}
+                    _ => {
+                        if let Err(e) =
+                            push_pending_atom(src_data, &mut items, &mut depth, pending_atom)
+                        {
+                            return (items, Some(e));
+                        }
+                    }
+                }
+                return (items, Some(e));
+            }
+            TokenKind::DatumComment => unreachable!(),
+            _ => {
+                if let Err(e) =
+                    push_pending_atom(src_data, &mut items, &mut depth, pending_atom.take())
+                {
+                    return (items, Some(e));
+                }
+                continue;
+            }
+        };
+        if let Err(e) = push_pending_atom(src_data, &mut items, &mut depth, pending_atom.take()) {
+            return (items, Some(e));
+        }
+        if let Err(e) = push_item(&mut items, &mut depth, item) {
+            return (items, Some(e));
+        }
+    }
+    if let Err(e) = push_pending_atom(src_data, &mut items, &mut depth, pending_atom) {
+        return (items, Some(e));
+    }
+
+    if depth == Some(0) {
+        (items, None)
+    } else {
+        let pos = src_data.len();
+        (items, Some(ParseError::UnexpectedEof { pos }))
+    }
+}
+
+fn push_pending_atom<'a>(
+    src_data: &'a str,
+    items: &mut Vec<ParsedItem<'a>>,
+    depth: &mut Option<usize>,
+    pending_atom: Option<(usize, usize)>,
+) -> Result<(), ParseError> {
+    if let Some((start, end)) = pending_atom {
+        push_item(items, depth, ParsedItem::Atom(&src_data[start..end], start))
+    } else {
+        Ok(())
+    }
+}
+
+fn push_item<'a>(
+    items: &mut Vec<ParsedItem<'a>>,
+    depth: &mut Option<usize>,
+    item: ParsedItem<'a>,
+) -> Result<(), ParseError> {
+    *depth = match (*depth, item) {
+        (Some(0), _) => {
+            return Err(ParseError::ExpectedEof {
+                pos: item_pos(item),
+            })
+        }
+        (None, ParsedItem::Atom(..)) => Some(0),
+        (None, ParsedItem::ListStart(_)) => Some(1),
+        (None, ParsedItem::ListEnd(pos)) => return Err(ParseError::UnexpectedRightParen { pos }),
+        (Some(depth), ParsedItem::Atom(..)) => Some(depth),
+        (Some(depth), ParsedItem::ListStart(_)) => Some(depth + 1),
+        (Some(depth), ParsedItem::ListEnd(_)) => Some(depth - 1),
+    };
+    items.push(item);
+    Ok(())
+}
+
+fn item_pos(item: ParsedItem<'_>) -> usize {
+    match item {
+        ParsedItem::Atom(_, pos) | ParsedItem::ListStart(pos) | ParsedItem::ListEnd(pos) => pos,
+    }
+}
+
+fn parse(src_data: &str, options: ParserOptions) -> (Vec<ParsedItem<'_>>, Option<ParseError>) {
+    let mut items = Vec::new();
+    for item in Parser::with_options(src_data, options) {
+        match item {
+            Ok(item) => items.push(item),
+            Err(e) => return (items, Some(e)),
+        }
+    }
+    (items, None)
+}
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % n
+    }
+}
+
+const PIECES: &[&str] = &[
+    "(",
+    ")",
+    "(",
+    ")",
+    " ",
+    "        ",
+    "                         ",
+    "\t",
+    "\n",
+    "\r\n",
+    "atom",
+    "a-very-long-atom-with-many-characters",
+    "\"",
+    "\"string\"",
+    "\"a string that is longer than a few words\"",
+    "\"\\\"\\\\\"",
+    "\\",
+    "#",
+    "|",
+    "#|",
+    "|#",
+    "; comment\n",
+    "; a comment that is longer than a few words\n",
+    ";\t\ttabs\n",
+    "\u{e9}",
+    "\0",
+    "\x7F",
+];
+
+#[test]
+fn test_agrees_with_tokenizer() {
+    let mut rng = Rng(0x5153_4953);
+    for &block_comments in [false, true].iter() {
+        let options = ParserOptions {
+            block_comments,
+            datum_comments: false,
+        };
+        for _ in 0..5000 {
+            let mut src_data = String::new();
+            let num_pieces = rng.next(40);
+            for _ in 0..num_pieces {
+                // Make invalid pieces less likely
+                let max_piece = if rng.next(4) == 0 {
+                    PIECES.len()
+                } else {
+                    PIECES.len() - 3
+                };
+                src_data.push_str(PIECES[rng.next(max_piece)]);
+            }
+
+            assert_eq!(
+                parse(&src_data, options),
+                reference_parse(&src_data, options),
+                "input: {:?}",
+                src_data,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_long_runs() {
+    // Runs that span several words and end at every possible offset
+    // within a word.
+    for len in 0..40 {
+        let spaces = " ".repeat(len);
+        let string_chars = "x".repeat(len);
+        let comment_chars = "y".repeat(len);
+        let src_data = alloc::format!(
+            "({s}\"{c}\"{s};{m}\n#|{m}#|{m}|#{m}|#{s})",
+            s = spaces,
+            c = string_chars,
+            m = comment_chars,
+        );
+        let options = ParserOptions {
+            block_comments: true,
+            datum_comments: false,
+        };
+        assert_eq!(
+            parse(&src_data, options),
+            reference_parse(&src_data, options),
+        );
+
+        let src_data = alloc::format!("\"{}\u{e9}\"", string_chars);
+        assert_eq!(
+            parse(&src_data, options).1,
+            Some(ParseError::IllegalChrInString {
+                pos: len + 1,
+                chr: '\u{e9}',
+            }),
+        );
+
+        let src_data = alloc::format!("() ;{}\x7F", comment_chars);
+        assert_eq!(
+            parse(&src_data, options).1,
+            Some(ParseError::IllegalChrInComment {
+                pos: len + 4,
+                chr: '\x7F',
+            }),
+        );
+    }
+}
+
+#[test]
+fn test_every_byte_after_runs() {
+    // Each ASCII byte at every offset within a word, after runs of
+    // mixed whitespace and of string and comment characters.
+    let options = ParserOptions {
+        block_comments: true,
+        datum_comments: false,
+    };
+    for len in 0..17 {
+        let whitespace: String = " \n \t\r ".chars().cycle().take(len).collect();
+        let chars = "x".repeat(len);
+        for byte in 0..128u8 {
+            let chr = char::from(byte);
+            for src_data in [
+                alloc::format!("(a{}{}b)", whitespace, chr),
+                alloc::format!("(\"{}{}\")", chars, chr),
+                alloc::format!("(a;{}{}\n)", chars, chr),
+                alloc::format!("(a#|{}{}|#)", chars, chr),
+            ] {
+                assert_eq!(
+                    parse(&src_data, options),
+                    reference_parse(&src_data, options),
+                    "input: {:?}",
+                    src_data,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use alloc::string::String;
+
+use crate::{sise_tree, ListBuilder, Serializer, SerializerSink, SerializerStyle};
+
+#[test]
+fn test_build_tree() {
+    assert_eq!(ListBuilder::new().build(), sise_tree!([]));
+    let tree = ListBuilder::new()
+        .atom("a")
+        .quoted("b \"c\"\n")
+        .int(i64::MIN)
+        .uint(u64::MAX)
+        .bool(false)
+        .list(|l| l.list(|l| l))
+        .node(&sise_tree!(["x", ["y"]]))
+        .build();
+    assert_eq!(
+        tree,
+        sise_tree!([
+            "a",
+            "\"b \\\"c\\\"\\n\"",
+            "-9223372036854775808",
+            "18446744073709551615",
+            "false",
+            [[]],
+            ["x", ["y"]],
+        ]),
+    );
+}
+
+#[test]
+fn test_serializer_sink() {
+    let style = SerializerStyle {
+        line_break: "\n",
+        indentation: "  ",
+    };
+    let tree = sise_tree!(["define", ["f", "x"], ["*", "x", "\"a\""]]);
+
+    let mut expected = String::new();
+    let mut serializer = Serializer::new(style, &mut expected);
+    crate::serialize_tree(&mut serializer, &tree, 0);
+    serializer.finish(true);
+
+    let mut result = String::new();
+    let mut serializer = Serializer::new(style, &mut result);
+    ListBuilder::with_sink(SerializerSink::new(&mut serializer, 0))
+        .atom("define")
+        .list(|l| l.atom("f").atom("x"))
+        .list(|l| l.atom("*").atom("x").quoted("a"))
+        .build();
+    serializer.finish(true);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+#[should_panic(expected = "invalid atom \"a b\"")]
+fn test_invalid_atom() {
+    ListBuilder::new().atom("a b");
+}
+
+#[test]
+#[should_panic(expected = "invalid atom \"\"")]
+fn test_invalid_node() {
+    ListBuilder::new().node(&sise_tree!([""]));
+}
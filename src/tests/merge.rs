@@ -0,0 +1,239 @@
+use crate::{merge, sise_tree, ListStrategy, MergeConflict, MergeOptions, Provenance, TreeNode};
+
+const APPEND: MergeOptions = MergeOptions {
+    list_strategy: ListStrategy::Append,
+    record_provenance: false,
+};
+
+fn merge_tree(layers: &[TreeNode], options: MergeOptions) -> TreeNode {
+    merge(layers, options).tree
+}
+
+#[test]
+fn test_single_layer() {
+    let tree = sise_tree!(["config", ["a", "1"]]);
+    let result = merge(core::slice::from_ref(&tree), MergeOptions::default());
+    assert_eq!(result.tree, tree);
+    assert_eq!(result.conflicts, []);
+    assert_eq!(result.provenance, None);
+}
+
+#[test]
+#[should_panic(expected = "no layers to merge")]
+fn test_no_layers() {
+    merge(&[], MergeOptions::default());
+}
+
+#[test]
+fn test_nested_keyword_lists() {
+    let base = sise_tree!([
+        "config",
+        ["server", ["host", "a"], ["port", "80"]],
+        ["log", ["level", "info"]],
+    ]);
+    let env = sise_tree!(["config", ["server", ["port", "8080"], ["tls", "on"]]]);
+    let local = sise_tree!(["config", ["log", ["level", "debug"]], ["extra"]]);
+    let result = merge(&[base, env, local], MergeOptions::default());
+    assert_eq!(
+        result.tree,
+        sise_tree!([
+            "config",
+            ["server", ["host", "a"], ["port", "8080"], ["tls", "on"]],
+            ["log", ["level", "debug"]],
+            ["extra"],
+        ]),
+    );
+    assert_eq!(
+        result.conflicts,
+        [
+            MergeConflict {
+                path: alloc::vec![1, 2],
+                layer: 1,
+            },
+            MergeConflict {
+                path: alloc::vec![2, 1],
+                layer: 2,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_equal_values_are_not_conflicts() {
+    let base = sise_tree!(["config", ["a", "1"], ["b", "2"]]);
+    let overlay = sise_tree!(["config", ["b", "2"]]);
+    let result = merge(&[base.clone(), overlay], MergeOptions::default());
+    assert_eq!(result.tree, base);
+    assert_eq!(result.conflicts, []);
+}
+
+#[test]
+fn test_root_mismatch() {
+    let base = sise_tree!(["config", ["a", "1"]]);
+    let overlay = sise_tree!(["other", ["a", "2"]]);
+    let result = merge(&[base, overlay.clone()], MergeOptions::default());
+    assert_eq!(result.tree, overlay);
+    assert_eq!(
+        result.conflicts,
+        [MergeConflict {
+            path: alloc::vec![],
+            layer: 1,
+        }],
+    );
+
+    let result = merge(
+        &[sise_tree!("a"), sise_tree!(["a"])],
+        MergeOptions::default(),
+    );
+    assert_eq!(result.tree, sise_tree!(["a"]));
+    assert_eq!(result.conflicts.len(), 1);
+}
+
+#[test]
+fn test_list_strategy() {
+    let base = sise_tree!(["config", ["flags", "a", "b"], ["paths", ["x"]]]);
+    let overlay = sise_tree!(["config", ["flags", "c"], ["paths", "y"]]);
+
+    assert_eq!(
+        merge_tree(&[base.clone(), overlay.clone()], MergeOptions::default()),
+        sise_tree!(["config", ["flags", "c"], ["paths", "y"]]),
+    );
+
+    let result = merge(&[base, overlay], APPEND);
+    assert_eq!(
+        result.tree,
+        sise_tree!(["config", ["flags", "a", "b", "c"], ["paths", ["x"], "y"]]),
+    );
+    assert_eq!(result.conflicts, []);
+
+    // Lists without a head atom
+    assert_eq!(
+        merge_tree(&[sise_tree!(["a", "b"]), sise_tree!([["c"]])], APPEND),
+        sise_tree!(["a", "b", ["c"]]),
+    );
+    // Atoms are still replaced
+    let result = merge(&[sise_tree!(["a", "b"]), sise_tree!("c")], APPEND);
+    assert_eq!(result.tree, sise_tree!("c"));
+    assert_eq!(result.conflicts.len(), 1);
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let base = sise_tree!(["server", ["listen", "80"], ["listen", "443"]]);
+    let overlay = sise_tree!([
+        "server",
+        ["listen", "8080"],
+        ["listen", "443"],
+        ["listen", "9000"],
+    ]);
+    let result = merge(&[base, overlay], MergeOptions::default());
+    assert_eq!(
+        result.tree,
+        sise_tree!([
+            "server",
+            ["listen", "8080"],
+            ["listen", "443"],
+            ["listen", "9000"],
+        ]),
+    );
+    assert_eq!(
+        result.conflicts,
+        [MergeConflict {
+            path: alloc::vec![1],
+            layer: 1,
+        }],
+    );
+}
+
+#[test]
+fn test_provenance() {
+    let base = sise_tree!(["config", ["a", "1"], ["b", "x", "y"]]);
+    let env = sise_tree!(["config", ["a", "2"], ["c", "3"]]);
+    let local = sise_tree!(["config", ["b", "z"]]);
+    let options = MergeOptions {
+        list_strategy: ListStrategy::Append,
+        record_provenance: true,
+    };
+    let result = merge(&[base, env, local], options);
+    assert_eq!(
+        result.tree,
+        sise_tree!(["config", ["a", "1", "2"], ["b", "x", "y", "z"], ["c", "3"],]),
+    );
+
+    let provenance = result.provenance.unwrap();
+    let layer_at = |path: &[usize]| provenance.index_path(path).unwrap().layer;
+    assert_eq!(layer_at(&[]), 0);
+    assert_eq!(layer_at(&[0]), 0);
+    assert_eq!(layer_at(&[1]), 0);
+    assert_eq!(layer_at(&[1, 1]), 0);
+    assert_eq!(layer_at(&[1, 2]), 1);
+    assert_eq!(layer_at(&[2, 2]), 0);
+    assert_eq!(layer_at(&[2, 3]), 2);
+    assert_eq!(layer_at(&[3]), 1);
+    assert_eq!(layer_at(&[3, 1]), 1);
+    assert!(provenance.index_path(&[4]).is_none());
+    assert_eq!(
+        *provenance.index_path(&[3]).unwrap(),
+        Provenance {
+            layer: 1,
+            children: alloc::vec![
+                Provenance {
+                    layer: 1,
+                    children: alloc::vec![],
+                },
+                Provenance {
+                    layer: 1,
+                    children: alloc::vec![],
+                },
+            ],
+        },
+    );
+
+    // With replacements
+    let options = MergeOptions {
+        record_provenance: true,
+        ..MergeOptions::default()
+    };
+    let result = merge(
+        &[sise_tree!(["k", ["a", "1"]]), sise_tree!(["k", ["a", "2"]])],
+        options,
+    );
+    let provenance = result.provenance.unwrap();
+    assert_eq!(provenance.layer, 0);
+    assert_eq!(provenance.children[1].layer, 1);
+    assert_eq!(provenance.children[1].children[0].layer, 1);
+}
+
+#[test]
+fn test_many_entries() {
+    let entry = |i: usize, value: &str| {
+        TreeNode::List(alloc::vec![
+            TreeNode::Atom(alloc::format!("key{}", i)),
+            TreeNode::from(value),
+        ])
+    };
+    let n = 20_000;
+    let mut base = alloc::vec![TreeNode::from("config")];
+    base.extend((0..n).map(|i| entry(i, "a")));
+    // The overlay has the keys in reverse order, half of them new.
+    let mut overlay = alloc::vec![TreeNode::from("config")];
+    overlay.extend((n / 2..(n + n / 2)).rev().map(|i| entry(i, "b")));
+
+    let result = merge(
+        &[TreeNode::List(base), TreeNode::List(overlay)],
+        MergeOptions::default(),
+    );
+    let mut expected = alloc::vec![TreeNode::from("config")];
+    expected.extend((0..n / 2).map(|i| entry(i, "a")));
+    expected.extend((n / 2..n).map(|i| entry(i, "b")));
+    expected.extend((n..(n + n / 2)).rev().map(|i| entry(i, "b")));
+    assert_eq!(result.tree, TreeNode::List(expected));
+    assert_eq!(result.conflicts.len(), n / 2);
+    assert_eq!(
+        result.conflicts[0],
+        MergeConflict {
+            path: alloc::vec![n],
+            layer: 1,
+        },
+    );
+}
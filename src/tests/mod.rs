@@ -1,4 +1,28 @@
+#[cfg(feature = "async")]
+mod async_parser;
+mod atom;
+mod atom_encoding;
+mod binary;
+mod canonical;
+mod checked_tree;
+#[cfg(feature = "std")]
+mod codec;
+mod csexp;
+mod cursor;
+mod diff;
+#[cfg(all(feature = "json", feature = "std"))]
+mod json;
+mod lexer;
+mod list_builder;
+mod merge;
+#[cfg(feature = "std")]
+mod parallel;
 mod parse_tree;
 mod parser;
+mod patch;
+mod replay;
+mod schema;
 mod serializer;
+mod tokenizer;
+mod tree;
 mod util;
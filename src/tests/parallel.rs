@@ -0,0 +1,196 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{
+    parse_tree, parse_trees_parallel, sise_tree, ParseError, Parser, ParserOptions, TreeNode,
+};
+
+const ALL_OPTIONS: ParserOptions = ParserOptions {
+    block_comments: true,
+    datum_comments: true,
+};
+
+#[track_caller]
+fn check_pass(src_data: &str, options: ParserOptions, expected: &[TreeNode]) {
+    for num_threads in 1..=5 {
+        assert_eq!(
+            parse_trees_parallel(src_data, options, num_threads).as_deref(),
+            Ok(expected),
+        );
+    }
+}
+
+#[track_caller]
+fn check_fail(src_data: &str, options: ParserOptions, expected: ParseError) {
+    for num_threads in 1..=5 {
+        assert_eq!(
+            parse_trees_parallel(src_data, options, num_threads),
+            Err(expected.clone()),
+        );
+    }
+}
+
+#[test]
+fn test_empty() {
+    check_pass("", ParserOptions::default(), &[]);
+    check_pass(" ; comment\n", ParserOptions::default(), &[]);
+    check_pass("#| a |# #; (b)", ALL_OPTIONS, &[]);
+}
+
+#[test]
+fn test_records() {
+    check_pass(
+        "; header\n(a b)\nc\n(d (e \"(\"))(f \")\") ; )\n",
+        ParserOptions::default(),
+        &[
+            sise_tree!(["a", "b"]),
+            sise_tree!("c"),
+            sise_tree!(["d", ["e", "\"(\""]]),
+            sise_tree!(["f", "\")\""]),
+        ],
+    );
+}
+
+#[test]
+fn test_comments_between_records() {
+    check_pass(
+        "#; (x) (a) #| ( |# b #; #; c (d) e #;(f #; g)",
+        ALL_OPTIONS,
+        &[sise_tree!(["a"]), sise_tree!("b"), sise_tree!("e")],
+    );
+}
+
+#[test]
+fn test_many_records() {
+    let mut src_data = String::new();
+    for i in 0..1000 {
+        src_data.push_str(&std::format!("(record {} (\"value {}\" x))\n", i, i % 7));
+    }
+
+    let expected: Vec<_> = src_data
+        .lines()
+        .map(|line| parse_tree(&mut Parser::new(line)).unwrap())
+        .collect();
+
+    check_pass(&src_data, ParserOptions::default(), &expected);
+}
+
+#[test]
+fn test_error_offsets() {
+    let mut src_data = String::new();
+    for i in 0..100 {
+        src_data.push_str(&std::format!("(record {})\n", i));
+    }
+    let pos = src_data.len() + 3;
+    src_data.push_str("(a [b)\n(c)\n");
+
+    check_fail(
+        &src_data,
+        ParserOptions::default(),
+        ParseError::IllegalChr { pos, chr: '[' },
+    );
+}
+
+#[test]
+fn test_error_first_in_file() {
+    check_fail(
+        "(a) (b #;) (c) (d \"\t\")",
+        ALL_OPTIONS,
+        ParseError::UnexpectedRightParen { pos: 9 },
+    );
+}
+
+#[test]
+fn test_error_structure() {
+    check_fail(
+        "(a) b)",
+        ParserOptions::default(),
+        ParseError::UnexpectedRightParen { pos: 5 },
+    );
+    check_fail(
+        "(a) (b",
+        ParserOptions::default(),
+        ParseError::UnexpectedEof { pos: 6 },
+    );
+    check_fail("(a) #;", ALL_OPTIONS, ParseError::UnexpectedEof { pos: 6 });
+}
+
+#[test]
+fn test_delimiters_in_strings_and_comments() {
+    check_pass(
+        "(a \"x) (\\\"y\" ; ) (\n b) #| ) ( #| ) |# ( |# (c a#) (d)\n\"(\" (e \"\\\\\")",
+        ALL_OPTIONS,
+        &[
+            sise_tree!(["a", "\"x) (\\\"y\"", "b"]),
+            sise_tree!(["c", "a#"]),
+            sise_tree!(["d"]),
+            sise_tree!("\"(\""),
+            sise_tree!(["e", "\"\\\\\""]),
+        ],
+    );
+}
+
+#[test]
+fn test_error_inside_list() {
+    // When lists are not validated, the string hides the end of the
+    // second one, but the error before it is still found.
+    check_fail(
+        "(a) (b [\"c) (d \" e)",
+        ParserOptions::default(),
+        ParseError::IllegalChr { pos: 7, chr: '[' },
+    );
+    check_fail(
+        "(a) (b \"c\n\") (d)",
+        ParserOptions::default(),
+        ParseError::IllegalChrInString { pos: 9, chr: '\n' },
+    );
+    check_fail(
+        "(a) (b \"c) (d)",
+        ParserOptions::default(),
+        ParseError::UnfinishedString { pos: 14 },
+    );
+    check_fail(
+        "(a) (b #| c) (d)",
+        ALL_OPTIONS,
+        ParseError::UnfinishedBlockComment { pos: 16 },
+    );
+}
+
+#[test]
+fn test_errors_in_several_groups() {
+    // Every group fails, and the error of the first one is returned
+    // once all of them have finished.
+    let mut src_data = String::new();
+    for i in 0..100 {
+        src_data.push_str(&std::format!("(record {} [)\n", i));
+    }
+    check_fail(
+        &src_data,
+        ParserOptions::default(),
+        ParseError::IllegalChr { pos: 10, chr: '[' },
+    );
+}
+
+#[test]
+fn test_every_byte_in_lists() {
+    // Each ASCII byte at every offset within a word of a list that the
+    // records are split around. The result must be the same as parsing
+    // all the records as the elements of a single list.
+    for len in 0..17 {
+        let chars = "x".repeat(len);
+        for byte in 0..128u8 {
+            let src_data = std::format!("(a{}{}b) (c)", chars, char::from(byte));
+            let wrapped = std::format!("({})", src_data);
+            let mut parser = Parser::with_options(&wrapped, ALL_OPTIONS);
+            let tree = parse_tree(&mut parser).and_then(|tree| parser.finish().map(|()| tree));
+            match tree {
+                Ok(TreeNode::List(records)) => check_pass(&src_data, ALL_OPTIONS, &records),
+                _ => assert!(
+                    parse_trees_parallel(&src_data, ALL_OPTIONS, 2).is_err(),
+                    "input: {:?}",
+                    src_data,
+                ),
+            }
+        }
+    }
+}
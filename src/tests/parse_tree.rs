@@ -1,7 +1,11 @@
+use alloc::format;
+use alloc::string::String;
+
 use crate::parse_tree;
+use crate::parse_tree_with;
 use crate::sise_tree;
-use crate::Parser;
-use crate::TreeNode;
+use crate::{ParseError, ParsedItem, Parser};
+use crate::{TreeBuilder, TreeNode};
 
 struct ParseTreeTest<'a> {
     src_data: &'a str,
@@ -79,3 +83,83 @@ fn test_nested_list_3() {
     }
     .run();
 }
+
+// Builder that writes the events into a string
+struct TraceBuilder {
+    trace: String,
+}
+
+impl<'a> TreeBuilder<'a> for TraceBuilder {
+    type Output = String;
+
+    fn atom(&mut self, atom: &'a str, pos: usize) {
+        self.trace.push_str(&format!("atom {} {};", atom, pos));
+    }
+
+    fn begin_list(&mut self, pos: usize) {
+        self.trace.push_str(&format!("begin {};", pos));
+    }
+
+    fn end_list(&mut self, pos: usize) {
+        self.trace.push_str(&format!("end {};", pos));
+    }
+
+    fn finish(self) -> String {
+        self.trace
+    }
+}
+
+#[test]
+fn test_custom_builder() {
+    let mut parser = Parser::new("(a (b) c)");
+    let trace = parse_tree_with(
+        &mut parser,
+        TraceBuilder {
+            trace: String::new(),
+        },
+    )
+    .unwrap();
+    parser.finish().unwrap();
+    assert_eq!(
+        trace,
+        "begin 0;atom a 1;begin 3;atom b 4;end 5;atom c 7;end 8;"
+    );
+}
+
+#[test]
+fn test_custom_builder_sub_tree() {
+    let mut parser = Parser::new("(a (b) c)");
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListStart(0));
+    let builder = TraceBuilder {
+        trace: String::new(),
+    };
+    assert_eq!(parse_tree_with(&mut parser, builder).unwrap(), "atom a 1;");
+    let builder = TraceBuilder {
+        trace: String::new(),
+    };
+    assert_eq!(
+        parse_tree_with(&mut parser, builder).unwrap(),
+        "begin 3;atom b 4;end 5;"
+    );
+}
+
+#[test]
+fn test_custom_builder_fail() {
+    let mut parser = Parser::new("(a (b c)");
+    let builder = TraceBuilder {
+        trace: String::new(),
+    };
+    assert_eq!(
+        parse_tree_with(&mut parser, builder).unwrap_err(),
+        ParseError::UnexpectedEof { pos: 8 },
+    );
+}
+
+#[test]
+#[should_panic(expected = "no node to parse")]
+fn test_parse_at_list_end() {
+    let mut parser = Parser::new("(a)");
+    assert_eq!(parser.next_item(), Ok(ParsedItem::ListStart(0)));
+    assert_eq!(parser.next_item(), Ok(ParsedItem::Atom("a", 1)));
+    let _ = parse_tree(&mut parser);
+}
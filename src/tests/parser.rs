@@ -1,4 +1,11 @@
-use crate::{ParseError, ParsedItem, Parser};
+use alloc::vec::Vec;
+
+use crate::{ParseError, ParsedItem, Parser, ParserOptions};
+
+const ALL_OPTIONS: ParserOptions = ParserOptions {
+    block_comments: true,
+    datum_comments: true,
+};
 
 struct ParserPassTest<'a> {
     src_data: &'a str,
@@ -8,7 +15,12 @@ struct ParserPassTest<'a> {
 impl<'a> ParserPassTest<'a> {
     #[track_caller]
     fn run(&self) {
-        let mut parser = Parser::new(self.src_data);
+        self.run_with_options(ParserOptions::default());
+    }
+
+    #[track_caller]
+    fn run_with_options(&self, options: ParserOptions) {
+        let mut parser = Parser::with_options(self.src_data, options);
         for parsed_item in self.expected_items.iter() {
             assert_eq!(parser.next_item().unwrap(), *parsed_item);
         }
@@ -26,7 +38,12 @@ struct ParserFailTest<'a> {
 impl<'a> ParserFailTest<'a> {
     #[track_caller]
     fn run(&self) {
-        let mut parser = Parser::new(self.src_data);
+        self.run_with_options(ParserOptions::default());
+    }
+
+    #[track_caller]
+    fn run_with_options(&self, options: ParserOptions) {
+        let mut parser = Parser::with_options(self.src_data, options);
         for parsed_item in self.expected_items.iter() {
             assert_eq!(parser.next_item().unwrap(), *parsed_item);
         }
@@ -295,6 +312,111 @@ fn test_comment_3() {
     .run();
 }
 
+#[test]
+fn test_block_comment_1() {
+    ParserPassTest {
+        src_data: "#| comment |# (1 2)",
+        expected_items: &[
+            ParsedItem::ListStart(14),
+            ParsedItem::Atom("1", 15),
+            ParsedItem::Atom("2", 17),
+            ParsedItem::ListEnd(18),
+        ],
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_block_comment_2() {
+    ParserPassTest {
+        src_data: "(1 #|\n(a b)\r\n|# 2) #||#",
+        expected_items: &[
+            ParsedItem::ListStart(0),
+            ParsedItem::Atom("1", 1),
+            ParsedItem::Atom("2", 16),
+            ParsedItem::ListEnd(17),
+        ],
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_block_comment_nested() {
+    ParserPassTest {
+        src_data: "(1 #| a #| b |# c |# 2)",
+        expected_items: &[
+            ParsedItem::ListStart(0),
+            ParsedItem::Atom("1", 1),
+            ParsedItem::Atom("2", 21),
+            ParsedItem::ListEnd(22),
+        ],
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_datum_comment_atom() {
+    ParserPassTest {
+        src_data: "(1 #;2 3)",
+        expected_items: &[
+            ParsedItem::ListStart(0),
+            ParsedItem::Atom("1", 1),
+            ParsedItem::Atom("3", 7),
+            ParsedItem::ListEnd(8),
+        ],
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_datum_comment_list() {
+    ParserPassTest {
+        src_data: "(1 #; (2 (3) 4) 5)",
+        expected_items: &[
+            ParsedItem::ListStart(0),
+            ParsedItem::Atom("1", 1),
+            ParsedItem::Atom("5", 16),
+            ParsedItem::ListEnd(17),
+        ],
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_datum_comment_chained() {
+    ParserPassTest {
+        src_data: "(#; #; 1 2 3)",
+        expected_items: &[
+            ParsedItem::ListStart(0),
+            ParsedItem::Atom("3", 11),
+            ParsedItem::ListEnd(12),
+        ],
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_datum_comment_nested() {
+    ParserPassTest {
+        src_data: "(#;(1 #;2 #| x |# 3) 4)",
+        expected_items: &[
+            ParsedItem::ListStart(0),
+            ParsedItem::Atom("4", 21),
+            ParsedItem::ListEnd(22),
+        ],
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_datum_comment_root() {
+    ParserPassTest {
+        src_data: "#;(1 2) root #;trailing",
+        expected_items: &[ParsedItem::Atom("root", 8)],
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
 #[test]
 fn test_fail_empty() {
     ParserFailTest {
@@ -393,3 +515,269 @@ fn test_fail_illegal_chr_in_comment() {
     }
     .run();
 }
+
+#[test]
+fn test_fail_unfinished_block_comment() {
+    ParserFailTest {
+        src_data: "(1 #| a #| b |# c",
+        expected_items: &[ParsedItem::ListStart(0), ParsedItem::Atom("1", 1)],
+        error_at_finish: false,
+        expected_error: ParseError::UnfinishedBlockComment { pos: 17 },
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_fail_illegal_chr_in_block_comment() {
+    ParserFailTest {
+        src_data: "#| \0 |# ()",
+        expected_items: &[],
+        error_at_finish: false,
+        expected_error: ParseError::IllegalChrInComment { pos: 3, chr: '\0' },
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_fail_datum_comment_before_right_paren() {
+    ParserFailTest {
+        src_data: "(1 #;)",
+        expected_items: &[ParsedItem::ListStart(0), ParsedItem::Atom("1", 1)],
+        error_at_finish: false,
+        expected_error: ParseError::UnexpectedRightParen { pos: 5 },
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_fail_datum_comment_before_eof() {
+    ParserFailTest {
+        src_data: "() #;",
+        expected_items: &[ParsedItem::ListStart(0), ParsedItem::ListEnd(1)],
+        error_at_finish: true,
+        expected_error: ParseError::UnexpectedEof { pos: 5 },
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_fail_datum_comment_unclosed_list() {
+    ParserFailTest {
+        src_data: "(#;(1 2",
+        expected_items: &[ParsedItem::ListStart(0)],
+        error_at_finish: false,
+        expected_error: ParseError::UnexpectedEof { pos: 7 },
+    }
+    .run_with_options(ALL_OPTIONS);
+}
+
+#[test]
+fn test_fail_block_comment_without_option() {
+    ParserFailTest {
+        src_data: "(#| comment |#)",
+        expected_items: &[ParsedItem::ListStart(0), ParsedItem::Atom("#", 1)],
+        error_at_finish: false,
+        expected_error: ParseError::IllegalChr { pos: 2, chr: '|' },
+    }
+    .run();
+}
+
+#[test]
+fn test_datum_comment_without_option() {
+    ParserPassTest {
+        src_data: "(1 #;2\n3)",
+        expected_items: &[
+            ParsedItem::ListStart(0),
+            ParsedItem::Atom("1", 1),
+            ParsedItem::Atom("#", 3),
+            ParsedItem::Atom("3", 7),
+            ParsedItem::ListEnd(8),
+        ],
+    }
+    .run();
+}
+
+#[test]
+fn test_iterator() {
+    let items: Vec<_> = Parser::new("(1 (2) 3)").collect();
+    assert_eq!(
+        items,
+        [
+            Ok(ParsedItem::ListStart(0)),
+            Ok(ParsedItem::Atom("1", 1)),
+            Ok(ParsedItem::ListStart(3)),
+            Ok(ParsedItem::Atom("2", 4)),
+            Ok(ParsedItem::ListEnd(5)),
+            Ok(ParsedItem::Atom("3", 7)),
+            Ok(ParsedItem::ListEnd(8)),
+        ],
+    );
+
+    let items: Vec<_> = Parser::new("atom ; comment").collect();
+    assert_eq!(items, [Ok(ParsedItem::Atom("atom", 0))]);
+}
+
+#[test]
+fn test_iterator_fail() {
+    let items: Vec<_> = Parser::new("(1 2").collect();
+    assert_eq!(
+        items,
+        [
+            Ok(ParsedItem::ListStart(0)),
+            Ok(ParsedItem::Atom("1", 1)),
+            Ok(ParsedItem::Atom("2", 3)),
+            Err(ParseError::UnexpectedEof { pos: 4 }),
+        ],
+    );
+
+    let items: Vec<_> = Parser::new("a b").collect();
+    assert_eq!(
+        items,
+        [
+            Ok(ParsedItem::Atom("a", 0)),
+            Err(ParseError::ExpectedEof { pos: 2 }),
+        ],
+    );
+
+    let mut parser = Parser::new(")");
+    assert_eq!(
+        parser.next(),
+        Some(Err(ParseError::UnexpectedRightParen { pos: 0 })),
+    );
+    assert_eq!(parser.next(), None);
+}
+
+#[test]
+fn test_peek_item() {
+    let mut parser = Parser::new("(1 2)");
+    assert_eq!(parser.peek_item().unwrap(), ParsedItem::ListStart(0));
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListStart(0));
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::Atom("1", 1));
+    assert_eq!(parser.peek_item().unwrap(), ParsedItem::Atom("2", 3));
+    assert_eq!(parser.next(), Some(Ok(ParsedItem::Atom("2", 3))));
+    assert_eq!(parser.peek_item().unwrap(), ParsedItem::ListEnd(4));
+    assert_eq!(parser.next(), Some(Ok(ParsedItem::ListEnd(4))));
+    assert_eq!(parser.next(), None);
+
+    let mut parser = Parser::new("(1");
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListStart(0));
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::Atom("1", 1));
+    assert_eq!(
+        parser.peek_item().unwrap_err(),
+        ParseError::UnexpectedEof { pos: 2 },
+    );
+    assert_eq!(
+        parser.next_item().unwrap_err(),
+        ParseError::UnexpectedEof { pos: 2 },
+    );
+}
+
+#[test]
+#[should_panic(expected = "parsing not finished yet")]
+fn test_finish_with_peeked_item() {
+    let mut parser = Parser::new("atom");
+    parser.peek_item().unwrap();
+    let _ = parser.finish();
+}
+
+#[test]
+fn test_skip_node() {
+    let mut parser = Parser::new("(a (b (c d) e) f)");
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListStart(0));
+    parser.skip_node().unwrap();
+    parser.skip_node().unwrap();
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::Atom("f", 15));
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListEnd(16));
+    parser.finish().unwrap();
+
+    let mut parser = Parser::new("(a b c)");
+    parser.skip_node().unwrap();
+    parser.finish().unwrap();
+
+    let mut parser = Parser::new("(a (b");
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListStart(0));
+    parser.skip_node().unwrap();
+    assert_eq!(
+        parser.skip_node().unwrap_err(),
+        ParseError::UnexpectedEof { pos: 5 },
+    );
+}
+
+#[test]
+#[should_panic(expected = "no node to skip")]
+fn test_skip_node_at_list_end() {
+    let mut parser = Parser::new("()");
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListStart(0));
+    let _ = parser.skip_node();
+}
+
+#[test]
+fn test_fail_illegal_non_ascii_chr() {
+    ParserFailTest {
+        src_data: "(a \u{e9})",
+        expected_items: &[ParsedItem::ListStart(0), ParsedItem::Atom("a", 1)],
+        error_at_finish: false,
+        expected_error: ParseError::IllegalChr {
+            pos: 3,
+            chr: '\u{e9}',
+        },
+    }
+    .run();
+}
+
+#[test]
+fn test_from_bytes() {
+    let mut parser = Parser::from_bytes(b"(a \"b c\" ; comment\n d)");
+    let items: Vec<_> = parser.by_ref().map(Result::unwrap).collect();
+    assert_eq!(
+        items,
+        [
+            ParsedItem::ListStart(0),
+            ParsedItem::Atom("a", 1),
+            ParsedItem::Atom("\"b c\"", 3),
+            ParsedItem::Atom("d", 20),
+            ParsedItem::ListEnd(21),
+        ],
+    );
+}
+
+#[test]
+fn test_from_bytes_fail() {
+    let cases: &[(&[u8], ParseError)] = &[
+        (b"(a \xFF)", ParseError::InvalidUtf8 { pos: 3 }),
+        (b"(a\xC3)", ParseError::InvalidUtf8 { pos: 2 }),
+        (
+            b"(a \xC3\xA9)",
+            ParseError::IllegalChr {
+                pos: 3,
+                chr: '\u{e9}',
+            },
+        ),
+        (b"(\"a\xFF\")", ParseError::InvalidUtf8 { pos: 3 }),
+        (b"(\"a\\\xFF\")", ParseError::InvalidUtf8 { pos: 4 }),
+        (
+            b"(\"\xE2\x82\xAC\")",
+            ParseError::IllegalChrInString {
+                pos: 2,
+                chr: '\u{20ac}',
+            },
+        ),
+        (b"(; \x80\n)", ParseError::InvalidUtf8 { pos: 3 }),
+        (b"(a\xF0\x9F\x98", ParseError::InvalidUtf8 { pos: 2 }),
+    ];
+    for &(src_data, ref expected_error) in cases.iter() {
+        let parser = Parser::from_bytes(src_data);
+        let error = parser.filter_map(Result::err).next().unwrap();
+        assert_eq!(error, *expected_error);
+    }
+
+    let options = ParserOptions {
+        block_comments: true,
+        datum_comments: false,
+    };
+    let mut parser = Parser::from_bytes_with_options(b"#| \xFF |# a", options);
+    assert_eq!(
+        parser.next_item().unwrap_err(),
+        ParseError::InvalidUtf8 { pos: 3 },
+    );
+}
@@ -0,0 +1,301 @@
+use alloc::string::ToString as _;
+
+use crate::{
+    apply_patch, diff, diff_to_tree, from_str, sise_tree, Patch, PatchError, PatchErrorKind,
+    PatchFormatError, PatchOp, TreeNode,
+};
+
+fn parse_patch(data: &str) -> Result<Patch, PatchFormatError> {
+    Patch::from_tree(&from_str(data).unwrap())
+}
+
+fn apply(tree: &TreeNode, patch: &str) -> Result<TreeNode, PatchError> {
+    let mut tree = tree.clone();
+    apply_patch(&mut tree, &parse_patch(patch).unwrap())?;
+    Ok(tree)
+}
+
+#[test]
+fn test_from_tree() {
+    assert_eq!(parse_patch("(patch)"), Ok(Patch::default()));
+    assert_eq!(
+        parse_patch(
+            "(patch (replace (path) x) (insert (path 0 1) (a)) (remove (path 2)) \
+             (test (path 1) y) (move (path 0) (path 1 0)))"
+        ),
+        Ok(Patch {
+            ops: alloc::vec![
+                PatchOp::Replace {
+                    path: alloc::vec![],
+                    value: sise_tree!("x"),
+                },
+                PatchOp::Insert {
+                    path: alloc::vec![0, 1],
+                    value: sise_tree!(["a"]),
+                },
+                PatchOp::Remove {
+                    path: alloc::vec![2],
+                },
+                PatchOp::Test {
+                    path: alloc::vec![1],
+                    value: sise_tree!("y"),
+                },
+                PatchOp::Move {
+                    from: alloc::vec![0],
+                    to: alloc::vec![1, 0],
+                },
+            ],
+        }),
+    );
+}
+
+#[test]
+fn test_from_tree_errors() {
+    let cases: &[(&str, &[usize])] = &[
+        ("patch", &[]),
+        ("()", &[0]),
+        ("(other)", &[0]),
+        ("(patch remove)", &[1]),
+        ("(patch ())", &[1, 0]),
+        ("(patch ((remove) (path)))", &[1, 0]),
+        ("(patch (delete (path 0)))", &[1, 0]),
+        ("(patch (replace-atom (path 0) x))", &[1, 0]),
+        ("(diff (remove (path 0)))", &[1, 0]),
+        ("(diff (test (path 0) x))", &[1, 0]),
+        ("(diff (replace-atom (path 0) (x)))", &[1, 2]),
+        ("(diff (delete (path 0) x))", &[1]),
+        ("(patch (remove (path 0) x))", &[1]),
+        ("(patch (replace (path 0)))", &[1]),
+        ("(patch (remove (path 0)) (remove path))", &[2, 1]),
+        ("(patch (remove (path x)))", &[1, 1]),
+        ("(patch (remove (path -1)))", &[1, 1]),
+        ("(patch (remove (path +1)))", &[1, 1]),
+        ("(patch (remove (path (1))))", &[1, 1]),
+        ("(patch (remove (index 1)))", &[1, 1]),
+        ("(patch (move (path 1) (path a)))", &[1, 2]),
+    ];
+    for &(data, path) in cases.iter() {
+        assert_eq!(
+            parse_patch(data),
+            Err(PatchFormatError {
+                path: path.to_vec()
+            }),
+            "{:?}",
+            data
+        );
+    }
+}
+
+#[test]
+fn test_to_tree_round_trip() {
+    let data = "(patch (replace (path) x) (insert (path 0 1) (a)) (remove (path 2)) \
+                (test (path 1) y) (move (path 0) (path 1 0)))";
+    let patch = parse_patch(data).unwrap();
+    assert_eq!(patch.to_tree(), from_str(data).unwrap());
+}
+
+#[test]
+fn test_apply() {
+    let tree = sise_tree!(["a", ["b", "c"], "d"]);
+    assert_eq!(apply(&tree, "(patch)"), Ok(tree.clone()));
+    assert_eq!(
+        apply(&tree, "(patch (replace (path) x))"),
+        Ok(sise_tree!("x"))
+    );
+    assert_eq!(
+        apply(&tree, "(patch (replace (path 1 0) (x)))"),
+        Ok(sise_tree!(["a", [["x"], "c"], "d"])),
+    );
+    assert_eq!(
+        apply(&tree, "(patch (insert (path 0) x) (insert (path 4) y))"),
+        Ok(sise_tree!(["x", "a", ["b", "c"], "d", "y"])),
+    );
+    assert_eq!(
+        apply(&tree, "(patch (remove (path 1 0)) (remove (path 0)))"),
+        Ok(sise_tree!([["c"], "d"])),
+    );
+    assert_eq!(
+        apply(
+            &tree,
+            "(patch (test (path 1) (b c)) (test (path) (a (b c) d)))"
+        ),
+        Ok(tree.clone()),
+    );
+    assert_eq!(
+        apply(&tree, "(patch (move (path 0) (path 2)))"),
+        Ok(sise_tree!([["b", "c"], "d", "a"])),
+    );
+    // Move between lists
+    assert_eq!(
+        apply(&tree, "(patch (move (path 2) (path 1 1)))"),
+        Ok(sise_tree!(["a", ["b", "d", "c"]])),
+    );
+}
+
+#[test]
+fn test_apply_errors() {
+    let tree = sise_tree!(["a", ["b", "c"], "d"]);
+    let cases: &[(&str, usize, PatchErrorKind)] = &[
+        (
+            "(patch (replace (path 3) x))",
+            0,
+            PatchErrorKind::PathNotFound,
+        ),
+        (
+            "(patch (replace (path 0 0) x))",
+            0,
+            PatchErrorKind::NotAList,
+        ),
+        (
+            "(patch (insert (path 4) x))",
+            0,
+            PatchErrorKind::PathNotFound,
+        ),
+        ("(patch (insert (path 0 0) x))", 0, PatchErrorKind::NotAList),
+        (
+            "(patch (insert (path) x))",
+            0,
+            PatchErrorKind::RootNotAllowed,
+        ),
+        ("(patch (remove (path 3)))", 0, PatchErrorKind::PathNotFound),
+        ("(patch (remove (path)))", 0, PatchErrorKind::RootNotAllowed),
+        ("(patch (test (path 0) b))", 0, PatchErrorKind::TestFailed),
+        ("(patch (test (path 5) b))", 0, PatchErrorKind::PathNotFound),
+        (
+            "(patch (move (path) (path 0)))",
+            0,
+            PatchErrorKind::RootNotAllowed,
+        ),
+        // Into itself
+        (
+            "(patch (move (path 1) (path 1 0)))",
+            0,
+            PatchErrorKind::NotAList,
+        ),
+        (
+            "(patch (remove (path 0)) (remove (path 0)) (remove (path 0)) (remove (path 0)))",
+            3,
+            PatchErrorKind::PathNotFound,
+        ),
+    ];
+    for &(patch, op_index, ref kind) in cases.iter() {
+        let mut patched = tree.clone();
+        assert_eq!(
+            apply_patch(&mut patched, &parse_patch(patch).unwrap()),
+            Err(PatchError {
+                op_index,
+                kind: kind.clone(),
+            }),
+            "{:?}",
+            patch
+        );
+        // Atomic
+        assert_eq!(patched, tree);
+    }
+}
+
+#[test]
+fn test_error_display() {
+    let error = PatchError {
+        op_index: 2,
+        kind: PatchErrorKind::TestFailed,
+    };
+    assert_eq!(error.to_string(), "patch operation 2 failed: test failed");
+    let error = PatchFormatError {
+        path: alloc::vec![1, 0],
+    };
+    assert_eq!(error.to_string(), "invalid patch at (1 0)");
+}
+
+#[test]
+fn test_from_diff() {
+    let pairs: &[(TreeNode, TreeNode)] = &[
+        (sise_tree!("a"), sise_tree!(["a"])),
+        (
+            sise_tree!(["a", "b", ["c", "d"], "e"]),
+            sise_tree!(["e", ["c", "x", "d"], "a", ["f"]]),
+        ),
+        (
+            sise_tree!([["k", "1"], ["l", "2"], ["m", "3"]]),
+            sise_tree!([["m", "3"], ["k", "4"], "l"]),
+        ),
+    ];
+    for (old, new) in pairs.iter() {
+        let patch = Patch::from_diff(&diff(old, new));
+        let mut tree = old.clone();
+        apply_patch(&mut tree, &patch).unwrap();
+        assert_eq!(&tree, new);
+
+        // Also through the SISE form
+        let patch = Patch::from_tree(&from_str(&patch.to_tree().to_string()).unwrap()).unwrap();
+        let mut tree = old.clone();
+        apply_patch(&mut tree, &patch).unwrap();
+        assert_eq!(&tree, new);
+    }
+    assert_eq!(Patch::from_diff(&[]), Patch::default());
+}
+
+#[test]
+fn test_from_diff_tree() {
+    assert_eq!(parse_patch("(diff)"), Ok(Patch::default()));
+    assert_eq!(
+        parse_patch(
+            "(diff (insert (path 0) (a)) (delete (path 1 2)) (replace-atom (path) x) \
+             (replace (path 3) (b)) (move (path 0) (path 4)))"
+        ),
+        Ok(Patch {
+            ops: alloc::vec![
+                PatchOp::Insert {
+                    path: alloc::vec![0],
+                    value: sise_tree!(["a"]),
+                },
+                PatchOp::Remove {
+                    path: alloc::vec![1, 2],
+                },
+                PatchOp::Replace {
+                    path: alloc::vec![],
+                    value: sise_tree!("x"),
+                },
+                PatchOp::Replace {
+                    path: alloc::vec![3],
+                    value: sise_tree!(["b"]),
+                },
+                PatchOp::Move {
+                    from: alloc::vec![0],
+                    to: alloc::vec![4],
+                },
+            ],
+        }),
+    );
+
+    // diff -> tree -> patch -> apply
+    let pairs: &[(TreeNode, TreeNode)] = &[
+        (sise_tree!("a"), sise_tree!("b")),
+        (sise_tree!(["a"]), sise_tree!("a")),
+        (
+            sise_tree!([
+                "config",
+                ["name", "x"],
+                ["port", "80"],
+                ["hosts", "a", "b"],
+                ["debug"]
+            ]),
+            sise_tree!([
+                "config",
+                ["hosts", "b", "a"],
+                ["name", "y"],
+                ["port", "8080"],
+                "z"
+            ]),
+        ),
+    ];
+    for (old, new) in pairs.iter() {
+        let edits = diff(old, new);
+        let tree = from_str(&diff_to_tree(&edits).to_string()).unwrap();
+        let patch = Patch::from_tree(&tree).unwrap();
+        assert_eq!(patch, Patch::from_diff(&edits));
+        let mut patched = old.clone();
+        apply_patch(&mut patched, &patch).unwrap();
+        assert_eq!(&patched, new);
+    }
+}
@@ -0,0 +1,137 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{
+    parse_tree, record_node, replay_into_serializer, replay_into_tree, serialize_tree, sise_tree,
+    OwnedParsedItem, ParseError, ParsedItem, Parser, Serializer, SerializerStyle,
+};
+
+const STYLE: SerializerStyle<'static> = SerializerStyle {
+    line_break: "\n",
+    indentation: "\t",
+};
+
+struct ReplayTest<'a> {
+    src_data: &'a str,
+}
+
+impl<'a> ReplayTest<'a> {
+    #[track_caller]
+    fn run(&self) {
+        let mut parser = Parser::new(self.src_data);
+        let expected_tree = parse_tree(&mut parser).unwrap();
+        parser.finish().unwrap();
+
+        let mut parser = Parser::new(self.src_data);
+        let items = record_node(&mut parser).unwrap();
+        parser.finish().unwrap();
+
+        // Recorded items are the same as the parsed ones
+        let parsed_items: Vec<_> = Parser::new(self.src_data).map(Result::unwrap).collect();
+        let recorded_items: Vec<_> = items.iter().map(OwnedParsedItem::as_item).collect();
+        assert_eq!(recorded_items, parsed_items);
+
+        // Replay into a tree
+        let tree = replay_into_tree(items.iter().map(OwnedParsedItem::as_item));
+        assert_eq!(tree, expected_tree);
+
+        // Replay into a serializer
+        for &break_line_at in [usize::MAX, 0, 10].iter() {
+            let mut expected = String::new();
+            let mut serializer = Serializer::new(STYLE, &mut expected);
+            serialize_tree(&mut serializer, &expected_tree, break_line_at);
+            serializer.finish(false);
+
+            let mut result = String::new();
+            let mut serializer = Serializer::new(STYLE, &mut result);
+            replay_into_serializer(
+                items.iter().map(OwnedParsedItem::as_item),
+                &mut serializer,
+                break_line_at,
+            );
+            serializer.finish(false);
+
+            assert_eq!(result, expected);
+        }
+    }
+}
+
+#[test]
+fn test_single_atom() {
+    ReplayTest { src_data: "atom" }.run();
+}
+
+#[test]
+fn test_empty_list() {
+    ReplayTest { src_data: "()" }.run();
+}
+
+#[test]
+fn test_nested_lists() {
+    ReplayTest {
+        src_data: "(atom-1 (atom-2) (atom-3 (atom-4 ()) atom-5) atom-6)",
+    }
+    .run();
+}
+
+#[test]
+fn test_record_sub_node() {
+    let mut parser = Parser::new("(head (1 (2)) tail)");
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListStart(0));
+    let head = record_node(&mut parser).unwrap();
+    let sub_tree = record_node(&mut parser).unwrap();
+    assert_eq!(head, [OwnedParsedItem::Atom(String::from("head"), 1)]);
+    assert_eq!(
+        replay_into_tree(sub_tree.iter().map(OwnedParsedItem::as_item)),
+        sise_tree!(["1", ["2"]]),
+    );
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::Atom("tail", 14));
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListEnd(18));
+    parser.finish().unwrap();
+}
+
+#[test]
+fn test_record_fail() {
+    let mut parser = Parser::new("(1 (2)");
+    assert_eq!(
+        record_node(&mut parser).unwrap_err(),
+        ParseError::UnexpectedEof { pos: 6 },
+    );
+}
+
+#[test]
+#[should_panic(expected = "no node to record")]
+fn test_record_list_end() {
+    let mut parser = Parser::new("(1)");
+    assert_eq!(parser.next_item().unwrap(), ParsedItem::ListStart(0));
+    record_node(&mut parser).unwrap();
+    record_node(&mut parser).unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_owned_items_are_send() {
+    let mut parser = Parser::new("(1 2)");
+    let items = record_node(&mut parser).unwrap();
+    let tree =
+        std::thread::spawn(move || replay_into_tree(items.iter().map(OwnedParsedItem::as_item)))
+            .join()
+            .unwrap();
+    assert_eq!(tree, sise_tree!(["1", "2"]));
+}
+
+#[test]
+#[should_panic(expected = "incomplete node")]
+fn test_replay_into_tree_incomplete() {
+    replay_into_tree([ParsedItem::ListStart(0)].iter().copied());
+}
+
+#[test]
+#[should_panic(expected = "more than one node")]
+fn test_replay_into_tree_more_than_one() {
+    replay_into_tree(
+        [ParsedItem::Atom("a", 0), ParsedItem::Atom("b", 2)]
+            .iter()
+            .copied(),
+    );
+}
@@ -0,0 +1,497 @@
+use alloc::string::{String, ToString as _};
+use alloc::vec::Vec;
+
+use crate::{
+    from_str, sise_tree, ParseError, Parser, Schema, SchemaError, SchemaErrorKind, TreeNode,
+    Violation, ViolationKind,
+};
+
+fn compile(root: &str) -> Schema {
+    let tree = from_str(&alloc::format!("(schema {})", root)).unwrap();
+    Schema::from_tree(&tree).unwrap()
+}
+
+fn schema_error(data: &str) -> SchemaError {
+    Schema::from_tree(&from_str(data).unwrap()).unwrap_err()
+}
+
+fn messages(schema: &Schema, tree: &TreeNode) -> Vec<String> {
+    schema
+        .validate(tree)
+        .iter()
+        .map(|v| v.to_string())
+        .collect()
+}
+
+#[test]
+fn test_atom_types() {
+    let cases: &[(&str, &[&str], &[&str])] = &[
+        ("any", &["a", "(a b)"], &[]),
+        ("atom", &["a"], &["()"]),
+        ("list", &["()", "(a)"], &["a"]),
+        (
+            "int",
+            &["0", "-12", "+7", "123456789012345678901234"],
+            &["", "1.0", "x", "-"],
+        ),
+        (
+            "(int -5 5)",
+            &["-5", "0", "5"],
+            &["-6", "6", "99999999999999999999"],
+        ),
+        (
+            "float",
+            &["1", "-1.5", "1e3", "2.5E-3", "+0.0e+1"],
+            &["1.", ".5", "1e", "e3", "1.5.2", "x"],
+        ),
+        ("bool", &["true", "false"], &["True", "1", "(true)"]),
+        (
+            "string",
+            &["\"\"", "\"a \\\" b\""],
+            &["a", "\"a\"b", "\"\\x\""],
+        ),
+        ("(enum a b)", &["a", "b"], &["c", "(a)"]),
+        ("(keyword k)", &["k"], &["kk", "(k)"]),
+        ("(choice int (list atom))", &["1", "(a)"], &["a", "(1 2)"]),
+    ];
+    for &(pattern, valid, invalid) in cases.iter() {
+        let schema = compile(&alloc::format!("(root {})", pattern));
+        for &data in valid.iter() {
+            let tree = from_str(data).unwrap_or_else(|_| TreeNode::from(data));
+            assert!(
+                schema.is_valid(&tree),
+                "{} should match {:?}",
+                pattern,
+                data
+            );
+        }
+        for &data in invalid.iter() {
+            let tree = from_str(data).unwrap_or_else(|_| TreeNode::from(data));
+            assert!(
+                !schema.is_valid(&tree),
+                "{} should not match {:?}",
+                pattern,
+                data
+            );
+        }
+    }
+}
+
+#[test]
+fn test_regex() {
+    let cases: &[(&str, &[&str], &[&str])] = &[
+        ("abc", &["abc"], &["ab", "abcd", "xabc"]),
+        ("a.c", &["abc", "a-c"], &["ac", "abbc"]),
+        ("[a-z_]+[0-9]*", &["a", "ab_12", "x9"], &["9", "A", "a9a"]),
+        ("[^0-9]x?", &["a", "ax", "-"], &["1", "axx"]),
+        ("a*a", &["a", "aaaa"], &["b"]),
+        ("\\.[.a-]", &[".a", "..", ".-"], &["a.", ".b"]),
+        (
+            "a?b+c*",
+            &["b", "abb", "bcc", "abbbc"],
+            &["", "a", "ac", "bab"],
+        ),
+        ("x*y?z*", &["", "xx", "y", "xyz", "zz"], &["yy", "zx"]),
+    ];
+    for &(regex, valid, invalid) in cases.iter() {
+        let source = crate::encode_string(regex);
+        let schema = compile(&alloc::format!("(root (pattern {}))", source));
+        for &atom in valid.iter() {
+            assert!(schema.is_valid(&TreeNode::from(atom)), "{} {}", regex, atom);
+        }
+        for &atom in invalid.iter() {
+            assert!(
+                !schema.is_valid(&TreeNode::from(atom)),
+                "{} {}",
+                regex,
+                atom
+            );
+        }
+    }
+
+    // Matching takes linear time, even when there are many ways of
+    // matching the beginning of the text.
+    let schema = compile("(root (pattern \"a*a*a*a*a*a*a*a*b\"))");
+    let atom = "a".repeat(10_000);
+    assert!(!schema.is_valid(&TreeNode::from(atom.as_str())));
+    assert!(schema.is_valid(&TreeNode::Atom(atom + "b")));
+
+    for regex in ["*a", "a(b)", "a|b", "[a", "[]", "[z-a]", "a\\"].iter() {
+        let data = alloc::format!("(schema (root (pattern {})))", crate::encode_string(regex));
+        assert_eq!(
+            schema_error(&data),
+            SchemaError {
+                path: alloc::vec![1, 1, 1],
+                kind: SchemaErrorKind::InvalidRegex,
+            },
+            "{:?}",
+            regex,
+        );
+    }
+}
+
+#[test]
+fn test_list() {
+    let schema = compile("(root (list (keyword point) int (optional int) (repeat atom 0 2)))");
+    assert!(schema.is_valid(&sise_tree!(["point", "1"])));
+    assert!(schema.is_valid(&sise_tree!(["point", "1", "2"])));
+    assert!(schema.is_valid(&sise_tree!(["point", "1", "x", "y"])));
+    assert!(schema.is_valid(&sise_tree!(["point", "1", "2", "x", "y"])));
+    assert_eq!(
+        schema.validate(&sise_tree!(["point"])),
+        [Violation {
+            path: Vec::new(),
+            pos: None,
+            kind: ViolationKind::MissingElement {
+                expected: String::from("int"),
+            },
+        }],
+    );
+    assert_eq!(
+        messages(
+            &schema,
+            &sise_tree!(["dot", "x", "1", "a", "b", ["c"], "d"])
+        ),
+        [
+            "at (0): expected keyword point",
+            "at (1): expected int",
+            "at (5): unexpected element",
+            "at (6): unexpected element",
+        ],
+    );
+    assert_eq!(
+        messages(&schema, &sise_tree!("point")),
+        ["at (): expected list"]
+    );
+
+    // Elements that do not match a repetition are reported with the
+    // reason, and the repetition goes on.
+    let schema = compile("(root (list (keyword flags) (repeat (enum debug verbose))))");
+    assert_eq!(
+        messages(&schema, &sise_tree!(["flags", "debug", "color", "verbose"])),
+        ["at (2): expected one of debug, verbose"],
+    );
+    let schema = compile("(root (list (repeat (list int int))))");
+    assert_eq!(
+        messages(&schema, &from_str("((1 2) (3 x) (5 6))").unwrap()),
+        ["at (1 1): expected int"],
+    );
+    let schema = compile("(root (list (repeat int) (list)))");
+    assert_eq!(
+        messages(&schema, &from_str("(1 x 2 () ())").unwrap()),
+        ["at (1): expected int", "at (4): unexpected element"],
+    );
+
+    let schema = compile("(root (list (repeat int 2)))");
+    assert!(schema.is_valid(&sise_tree!(["1", "2", "3"])));
+    assert_eq!(
+        messages(&schema, &sise_tree!(["1", "x"])),
+        ["at (1): expected int"],
+    );
+    assert_eq!(
+        messages(&schema, &sise_tree!([])),
+        ["at (): missing element, expected int"],
+    );
+}
+
+#[test]
+fn test_record() {
+    let schema = compile(
+        "(root (record server
+           (required name string)
+           (optional port (int 1 65535))
+           (repeated alias atom)
+           (optional flags (repeat atom))))",
+    );
+    assert!(schema.is_valid(&from_str("(server (name \"x\"))").unwrap()));
+    assert!(schema.is_valid(
+        &from_str("(server (alias b) (flags) (port 80) (alias a) (name \"x\"))").unwrap()
+    ));
+    let tree =
+        from_str("(server (port 80 81) (port 0) x () (alias) (flags a (b)) (user root))").unwrap();
+    assert_eq!(
+        messages(&schema, &tree),
+        [
+            "at (1 2): unexpected element",
+            "at (2): duplicate entry port",
+            "at (3): unexpected element",
+            "at (4): unexpected element",
+            "at (5): missing element, expected atom",
+            "at (6 2): expected atom",
+            "at (7): unknown entry user",
+            "at (): missing entry name",
+        ],
+    );
+    assert_eq!(
+        messages(&schema, &from_str("(client (name \"x\"))").unwrap()),
+        ["at (0): expected keyword server"],
+    );
+    assert_eq!(
+        messages(&schema, &sise_tree!([])),
+        ["at (): missing element, expected keyword server"],
+    );
+    assert_eq!(
+        messages(&schema, &sise_tree!("server")),
+        ["at (): expected (server ...)"],
+    );
+
+    let schema = compile("(root (record server (optional flags (repeat (enum debug verbose)))))");
+    assert_eq!(
+        messages(
+            &schema,
+            &from_str("(server (flags debug color verbose))").unwrap()
+        ),
+        ["at (1 2): expected one of debug, verbose"],
+    );
+}
+
+#[test]
+fn test_validate_parsed() {
+    let schema = compile(
+        "(root (record server
+           (required name string)
+           (optional port (int 1 65535))))",
+    );
+    let data = "(server\n  (port 0)\n  (user root)\n  (port 80 81))";
+    let mut parser = Parser::new(data);
+    let violations = schema.validate_parsed(&mut parser).unwrap();
+    parser.finish().unwrap();
+    assert_eq!(
+        violations.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+        [
+            "at (1 1), byte 16: expected int from 1 to 65535",
+            "at (2), byte 21: unknown entry user",
+            "at (3), byte 35: duplicate entry port",
+            "at (), byte 0: missing entry name",
+        ],
+    );
+    assert_eq!(
+        schema.validate(&from_str(data).unwrap()),
+        violations
+            .iter()
+            .map(|v| Violation {
+                pos: None,
+                ..v.clone()
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    // Errors from the parser are returned as they are.
+    assert_eq!(
+        schema.validate_parsed(&mut Parser::new("(server")),
+        Err(ParseError::UnexpectedEof { pos: 7 }),
+    );
+}
+
+#[test]
+fn test_definitions() {
+    // Recursive definitions through lists
+    let schema = compile(
+        "(define expr (choice int (list (enum + *) (repeat expr 1))))
+         (root expr)",
+    );
+    assert!(schema.is_valid(&from_str("(+ 1 (* 2 3) 4)").unwrap()));
+    // A choice is reported as a whole when none of its patterns match
+    assert_eq!(
+        messages(&schema, &from_str("(+ 1 (- 2))").unwrap()),
+        ["at (): expected int or list"],
+    );
+
+    let schema = compile(
+        "(root (list port port))
+         (define port (int 1 65535))",
+    );
+    assert_eq!(
+        messages(&schema, &sise_tree!(["80", "x"])),
+        ["at (1): expected int from 1 to 65535"],
+    );
+}
+
+#[test]
+fn test_schema_errors() {
+    let cases = [
+        ("schema", Vec::new(), SchemaErrorKind::InvalidSyntax),
+        ("(config)", Vec::new(), SchemaErrorKind::InvalidSyntax),
+        ("(schema)", Vec::new(), SchemaErrorKind::MissingRoot),
+        (
+            "(schema (root int) (root int))",
+            alloc::vec![2],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+        (
+            "(schema (other))",
+            alloc::vec![1],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+        (
+            "(schema (root foo))",
+            alloc::vec![1, 1],
+            SchemaErrorKind::UnknownType {
+                name: String::from("foo"),
+            },
+        ),
+        (
+            "(schema (root (list int (repeat (list foo)))))",
+            alloc::vec![1, 1, 2, 1, 1],
+            SchemaErrorKind::UnknownType {
+                name: String::from("foo"),
+            },
+        ),
+        (
+            "(schema (define a int) (define a atom) (root a))",
+            alloc::vec![2, 1],
+            SchemaErrorKind::DuplicateDefinition {
+                name: String::from("a"),
+            },
+        ),
+        (
+            "(schema (define int atom) (root int))",
+            alloc::vec![1, 1],
+            SchemaErrorKind::DuplicateDefinition {
+                name: String::from("int"),
+            },
+        ),
+        (
+            "(schema (define a (choice int b)) (define b a) (root a))",
+            alloc::vec![1, 1],
+            SchemaErrorKind::RecursiveDefinition {
+                name: String::from("a"),
+            },
+        ),
+        (
+            "(schema (root (int 5 1)))",
+            alloc::vec![1, 1],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+        (
+            "(schema (root (enum)))",
+            alloc::vec![1, 1],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+        (
+            "(schema (root (enum a (b))))",
+            alloc::vec![1, 1, 2],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+        (
+            "(schema (root (pattern abc)))",
+            alloc::vec![1, 1, 1],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+        (
+            "(schema (root (list (repeat int 3 2))))",
+            alloc::vec![1, 1, 1],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+        (
+            "(schema (root (record a (required b) (optional b))))",
+            alloc::vec![1, 1, 3],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+        (
+            "(schema (root (record a (maybe b))))",
+            alloc::vec![1, 1, 2],
+            SchemaErrorKind::InvalidSyntax,
+        ),
+    ];
+    for (data, path, kind) in cases.iter() {
+        assert_eq!(
+            schema_error(data),
+            SchemaError {
+                path: path.clone(),
+                kind: kind.clone(),
+            },
+            "{}",
+            data,
+        );
+    }
+    assert_eq!(
+        schema_error("(schema (root foo))").to_string(),
+        "invalid schema at (1 1): unknown type foo",
+    );
+}
+
+#[test]
+fn test_deep_tree() {
+    fn nested(depth: usize) -> TreeNode {
+        let mut node = TreeNode::from("a");
+        for _ in 0..depth {
+            node = TreeNode::List(alloc::vec![node]);
+        }
+        node
+    }
+
+    // Dropping the tree recursively would overflow the stack.
+    fn drop_nested(mut node: TreeNode) {
+        while let Some(inner) = node.as_mut_list().and_then(Vec::pop) {
+            node = inner;
+        }
+    }
+
+    let schema = compile("(define n (choice atom (list (repeat n)))) (root n)");
+
+    let tree = nested(256);
+    assert!(schema.is_valid(&tree));
+    drop_nested(tree);
+
+    for &depth in [257, 200_000].iter() {
+        let tree = nested(depth);
+        assert_eq!(
+            schema.validate(&tree),
+            [Violation {
+                path: alloc::vec![0; 256],
+                pos: None,
+                kind: ViolationKind::TooDeep,
+            }],
+        );
+        drop_nested(tree);
+    }
+
+    // Violations found before the limit are kept.
+    let schema = compile("(define n (choice int (list (repeat n)))) (root (list int n))");
+    let tree = TreeNode::List(alloc::vec![TreeNode::from("x"), nested(300)]);
+    assert_eq!(
+        messages(&schema, &tree),
+        [
+            "at (0): expected int",
+            &alloc::format!("at (1{}): nested too deeply", " 0".repeat(255)),
+        ],
+    );
+    drop_nested(tree);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_deep_invalid_tree() {
+    // Each list is tried against several patterns, which must not
+    // validate its elements again each time.
+    let deepest = alloc::vec![0; 250];
+    let schemas = [
+        ("(define t (list (repeat t))) (root t)", &deepest[..]),
+        (
+            "(define t (choice (list t) (list t int))) (root t)",
+            &[][..],
+        ),
+        (
+            "(define t (list (repeat t) (optional t))) (root t)",
+            &deepest[..],
+        ),
+    ];
+    let mut tree = TreeNode::from("x");
+    for _ in 0..250 {
+        tree = TreeNode::List(alloc::vec![tree]);
+    }
+    for &(data, path) in schemas.iter() {
+        let schema =
+            Schema::from_tree(&from_str(&alloc::format!("(schema {})", data)).unwrap()).unwrap();
+        let start = std::time::Instant::now();
+        let violations = schema.validate(&tree);
+        assert!(
+            start.elapsed() < std::time::Duration::from_secs(1),
+            "{}",
+            data
+        );
+        assert_eq!(violations.len(), 1, "{}", data);
+        assert_eq!(violations[0].path, path, "{}", data);
+    }
+}
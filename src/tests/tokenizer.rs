@@ -0,0 +1,260 @@
+use alloc::vec::Vec;
+
+use crate::{ParseError, ParsedItem, Parser, ParserOptions, TokenKind, Tokenizer};
+
+const ALL_OPTIONS: ParserOptions = ParserOptions {
+    block_comments: true,
+    datum_comments: true,
+};
+
+struct TokenizerTest<'a> {
+    src_data: &'a str,
+    options: ParserOptions,
+    expected_tokens: &'a [(TokenKind, &'a str)],
+}
+
+impl<'a> TokenizerTest<'a> {
+    #[track_caller]
+    fn run(&self) {
+        let tokens: Vec<_> = Tokenizer::with_options(self.src_data, self.options).collect();
+
+        // Tokens are contiguous and cover the whole input
+        let mut pos = 0;
+        for token in tokens.iter() {
+            assert_eq!(token.span.start, pos);
+            assert_eq!(token.text, &self.src_data[token.span.clone()]);
+            pos = token.span.end;
+        }
+        assert_eq!(pos, self.src_data.len());
+
+        let tokens: Vec<_> = tokens
+            .into_iter()
+            .map(|token| (token.kind, token.text))
+            .collect();
+        assert_eq!(tokens, self.expected_tokens);
+    }
+}
+
+#[test]
+fn test_empty() {
+    TokenizerTest {
+        src_data: "",
+        options: ParserOptions::default(),
+        expected_tokens: &[],
+    }
+    .run();
+}
+
+#[test]
+fn test_list() {
+    TokenizerTest {
+        src_data: "(a  b\t(c))",
+        options: ParserOptions::default(),
+        expected_tokens: &[
+            (TokenKind::LeftParen, "("),
+            (TokenKind::BareAtom, "a"),
+            (TokenKind::Whitespace, "  "),
+            (TokenKind::BareAtom, "b"),
+            (TokenKind::Whitespace, "\t"),
+            (TokenKind::LeftParen, "("),
+            (TokenKind::BareAtom, "c"),
+            (TokenKind::RightParen, ")"),
+            (TokenKind::RightParen, ")"),
+        ],
+    }
+    .run();
+}
+
+#[test]
+fn test_atom_segments() {
+    TokenizerTest {
+        src_data: "a\"b \\\" c\"\"\"d",
+        options: ParserOptions::default(),
+        expected_tokens: &[
+            (TokenKind::BareAtom, "a"),
+            (TokenKind::QuotedAtom, "\"b \\\" c\""),
+            (TokenKind::QuotedAtom, "\"\""),
+            (TokenKind::BareAtom, "d"),
+        ],
+    }
+    .run();
+}
+
+#[test]
+fn test_line_breaks_and_comments() {
+    TokenizerTest {
+        src_data: "; c1\r\n\r\n;c2\r",
+        options: ParserOptions::default(),
+        expected_tokens: &[
+            (TokenKind::LineComment, "; c1"),
+            (TokenKind::LineBreak, "\r\n"),
+            (TokenKind::LineBreak, "\r\n"),
+            (TokenKind::LineComment, ";c2"),
+            (TokenKind::LineBreak, "\r"),
+        ],
+    }
+    .run();
+}
+
+#[test]
+fn test_block_and_datum_comments() {
+    TokenizerTest {
+        src_data: "#| a #| b |#\n|##;x a#;",
+        options: ALL_OPTIONS,
+        expected_tokens: &[
+            (TokenKind::BlockComment, "#| a #| b |#\n|#"),
+            (TokenKind::DatumComment, "#;"),
+            (TokenKind::BareAtom, "x"),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::BareAtom, "a#"),
+            (TokenKind::LineComment, ";"),
+        ],
+    }
+    .run();
+}
+
+#[test]
+fn test_hash_without_options() {
+    TokenizerTest {
+        src_data: "#;x",
+        options: ParserOptions::default(),
+        expected_tokens: &[(TokenKind::BareAtom, "#"), (TokenKind::LineComment, ";x")],
+    }
+    .run();
+}
+
+#[test]
+fn test_error_illegal_chr() {
+    TokenizerTest {
+        src_data: "(a[b)",
+        options: ParserOptions::default(),
+        expected_tokens: &[
+            (TokenKind::LeftParen, "("),
+            (TokenKind::BareAtom, "a"),
+            (
+                TokenKind::Error(ParseError::IllegalChr { pos: 2, chr: '[' }),
+                "[",
+            ),
+            (TokenKind::BareAtom, "b"),
+            (TokenKind::RightParen, ")"),
+        ],
+    }
+    .run();
+}
+
+#[test]
+fn test_error_illegal_chr_in_string() {
+    TokenizerTest {
+        src_data: "\"a\u{e9}\\\tb\" c",
+        options: ParserOptions::default(),
+        expected_tokens: &[
+            (TokenKind::QuotedAtom, "\"a"),
+            (
+                TokenKind::Error(ParseError::IllegalChrInString {
+                    pos: 2,
+                    chr: '\u{e9}',
+                }),
+                "\u{e9}",
+            ),
+            (TokenKind::QuotedAtom, "\\"),
+            (
+                TokenKind::Error(ParseError::IllegalChrInString { pos: 5, chr: '\t' }),
+                "\t",
+            ),
+            (TokenKind::QuotedAtom, "b\""),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::BareAtom, "c"),
+        ],
+    }
+    .run();
+}
+
+#[test]
+fn test_error_illegal_chr_in_comments() {
+    TokenizerTest {
+        src_data: "; a\0\n#|\0|#",
+        options: ALL_OPTIONS,
+        expected_tokens: &[
+            (TokenKind::LineComment, "; a"),
+            (
+                TokenKind::Error(ParseError::IllegalChrInComment { pos: 3, chr: '\0' }),
+                "\0",
+            ),
+            (TokenKind::LineBreak, "\n"),
+            (TokenKind::BlockComment, "#|"),
+            (
+                TokenKind::Error(ParseError::IllegalChrInComment { pos: 7, chr: '\0' }),
+                "\0",
+            ),
+            (TokenKind::BlockComment, "|#"),
+        ],
+    }
+    .run();
+}
+
+#[test]
+fn test_error_unfinished() {
+    TokenizerTest {
+        src_data: "(\"abc",
+        options: ParserOptions::default(),
+        expected_tokens: &[
+            (TokenKind::LeftParen, "("),
+            (
+                TokenKind::Error(ParseError::UnfinishedString { pos: 5 }),
+                "\"abc",
+            ),
+        ],
+    }
+    .run();
+
+    TokenizerTest {
+        src_data: "#| a #| b |#",
+        options: ALL_OPTIONS,
+        expected_tokens: &[(
+            TokenKind::Error(ParseError::UnfinishedBlockComment { pos: 12 }),
+            "#| a #| b |#",
+        )],
+    }
+    .run();
+}
+
+#[test]
+fn test_agrees_with_parser() {
+    let src_data = "(a \"b\"c #| x |# (d ; y\n e\"f\") () g)";
+
+    let mut parser = Parser::with_options(src_data, ALL_OPTIONS);
+    let mut expected_items = Vec::new();
+    loop {
+        let item = parser.next_item().unwrap();
+        expected_items.push(item);
+        if item == ParsedItem::ListEnd(src_data.len() - 1) {
+            break;
+        }
+    }
+    parser.finish().unwrap();
+
+    let mut items = Vec::new();
+    let mut prev_token_end = None;
+    for token in Tokenizer::with_options(src_data, ALL_OPTIONS) {
+        match token.kind {
+            TokenKind::LeftParen => items.push(ParsedItem::ListStart(token.span.start)),
+            TokenKind::RightParen => items.push(ParsedItem::ListEnd(token.span.start)),
+            TokenKind::BareAtom | TokenKind::QuotedAtom => {
+                if prev_token_end == Some(token.span.start) {
+                    // Merge with the previous segment
+                    if let Some(&mut ParsedItem::Atom(ref mut atom, pos)) = items.last_mut() {
+                        *atom = &src_data[pos..token.span.end];
+                    }
+                } else {
+                    items.push(ParsedItem::Atom(token.text, token.span.start));
+                }
+                prev_token_end = Some(token.span.end);
+                continue;
+            }
+            _ => {}
+        }
+        prev_token_end = None;
+    }
+
+    assert_eq!(items, expected_items);
+}
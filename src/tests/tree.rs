@@ -0,0 +1,275 @@
+use alloc::format;
+use alloc::string::{String, ToString as _};
+use alloc::vec::Vec;
+
+use crate::{from_str, sise_tree, to_string_pretty, ParseError, Quoted, TreeNode};
+
+fn trees() -> [TreeNode; 5] {
+    [
+        sise_tree!("atom"),
+        sise_tree!([]),
+        sise_tree!([[], [[]]]),
+        sise_tree!(["define", ["f", "x"], ["*", "x", "\"a ; b\""]]),
+        sise_tree!([["a", "b"], "c", ["d", ["e", "f"], "g"]]),
+    ]
+}
+
+#[test]
+fn test_display_compact() {
+    assert_eq!(sise_tree!("atom").to_string(), "atom");
+    assert_eq!(sise_tree!([]).to_string(), "()");
+    assert_eq!(sise_tree!([[], [[]]]).to_string(), "(() (()))");
+    assert_eq!(
+        sise_tree!([["a", "b"], "c", ["d"]]).to_string(),
+        "((a b) c (d))"
+    );
+}
+
+#[test]
+fn test_display_pretty() {
+    let tree = sise_tree!(["a", ["b", "c"], "d"]);
+    assert_eq!(format!("{:#}", tree), "(a (b c) d)");
+    assert_eq!(format!("{:#1}", tree), "(a\n  (b\n    c\n  )\n  d\n)");
+    assert_eq!(format!("{:#1.0}", tree), "(a\n(b\nc\n)\nd\n)");
+    assert_eq!(
+        format!("{:#1.4}", tree),
+        "(a\n    (b\n        c\n    )\n    d\n)"
+    );
+    assert_eq!(format!("{:#100}", tree), "(a (b c) d)");
+    assert_eq!(format!("{:#5.1}", tree), "(a (b\n  c\n ) d\n)");
+    assert_eq!(to_string_pretty(&tree), format!("{:#}", tree));
+    assert_eq!(format!("{:#}", sise_tree!("atom")), "atom");
+
+    let long_tree = sise_tree!(["list", ..(0..30).map(|i| format!("item-{}", i))]);
+    let text = format!("{:#}", long_tree);
+    assert_eq!(text.lines().count(), 4, "{}", text);
+    assert_eq!(from_str(&text), Ok(long_tree));
+}
+
+#[test]
+fn test_display_padding() {
+    let tree = sise_tree!(["a", ["b"]]);
+    assert_eq!(format!("{:10}|", tree), "(a (b))   |");
+    assert_eq!(format!("{:*^11}", tree), "**(a (b))**");
+    assert_eq!(format!("{:.4}", tree), "(a (");
+    assert_eq!(format!("{:3}", tree), "(a (b))");
+}
+
+#[test]
+fn test_display_invalid_atoms() {
+    use core::fmt::Write as _;
+
+    for tree in [
+        sise_tree!(["a b", ["c"]]),
+        sise_tree!(["a", [""]]),
+        sise_tree!("\"d"),
+    ]
+    .iter()
+    {
+        let mut text = String::new();
+        assert_eq!(write!(text, "{}", tree), Err(core::fmt::Error));
+        assert_eq!(write!(text, "{:10}", tree), Err(core::fmt::Error));
+        assert_eq!(write!(text, "{:#}", tree), Err(core::fmt::Error));
+        assert_eq!(write!(text, "{:#1}", tree), Err(core::fmt::Error));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_to_string_invalid_atom() {
+    let _ = sise_tree!(["a b"]).to_string();
+}
+
+#[test]
+#[should_panic(expected = "invalid atom")]
+fn test_to_string_pretty_invalid_atom() {
+    let _ = to_string_pretty(&sise_tree!(["a b"]));
+}
+
+#[test]
+fn test_round_trip() {
+    for tree in trees().iter() {
+        assert_eq!(from_str(&tree.to_string()).as_ref(), Ok(tree));
+        assert_eq!(tree.to_string().parse::<TreeNode>().as_ref(), Ok(tree));
+        assert_eq!(from_str(&to_string_pretty(tree)).as_ref(), Ok(tree));
+        assert_eq!(from_str(&format!("{:#3.1}", tree)).as_ref(), Ok(tree));
+    }
+}
+
+#[test]
+fn test_from_str_errors() {
+    assert_eq!(from_str(""), Err(ParseError::UnexpectedEof { pos: 0 }));
+    assert_eq!(from_str("(a"), Err(ParseError::UnexpectedEof { pos: 2 }));
+    assert_eq!(from_str("a b"), Err(ParseError::ExpectedEof { pos: 2 }));
+    assert_eq!(
+        "(a) )".parse::<TreeNode>(),
+        Err(ParseError::ExpectedEof { pos: 4 })
+    );
+}
+
+fn config() -> TreeNode {
+    // (config (name "x") (port 80) flag (flag a) () ((x)) (flag b))
+    sise_tree!([
+        "config",
+        ["name", "\"x\""],
+        ["port", "80"],
+        "flag",
+        ["flag", "a"],
+        [],
+        [["x"]],
+        ["flag", "b"],
+    ])
+}
+
+#[test]
+fn test_entries() {
+    let tree = config();
+    assert_eq!(tree.head(), Some("config"));
+    assert_eq!(tree.tail().unwrap().len(), 7);
+    let entries: Vec<_> = tree.entries().collect();
+    assert_eq!(
+        entries,
+        [
+            ("name", &sise_tree!(["name", "\"x\""])),
+            ("port", &sise_tree!(["port", "80"])),
+            ("flag", &sise_tree!(["flag", "a"])),
+            ("flag", &sise_tree!(["flag", "b"])),
+        ],
+    );
+    assert_eq!(sise_tree!("config").entries().count(), 0);
+    assert_eq!(sise_tree!([]).entries().count(), 0);
+    // The first element is never an entry
+    assert_eq!(sise_tree!([["a", "b"]]).entries().count(), 0);
+}
+
+#[test]
+fn test_get_entry() {
+    let tree = config();
+    assert_eq!(tree.get_entry("port"), Some(&sise_tree!(["port", "80"])));
+    assert_eq!(tree.get_entry("flag"), Some(&sise_tree!(["flag", "a"])));
+    assert_eq!(tree.get_entry("config"), None);
+    assert_eq!(tree.get_entry("x"), None);
+    assert_eq!(
+        tree.get_all_entries("flag"),
+        [&sise_tree!(["flag", "a"]), &sise_tree!(["flag", "b"])],
+    );
+    assert!(sise_tree!("flag").get_all_entries("flag").is_empty());
+}
+
+#[test]
+fn test_entry_mut() {
+    let mut tree = config();
+    *tree.get_entry_mut("flag").unwrap() = sise_tree!(["flag", "c"]);
+    assert_eq!(
+        tree.get_all_entries("flag"),
+        [&sise_tree!(["flag", "c"]), &sise_tree!(["flag", "b"])],
+    );
+    assert!(tree.get_entry_mut("host").is_none());
+
+    assert_eq!(
+        tree.set_entry("flag", alloc::vec![TreeNode::from("d")]),
+        Some(sise_tree!(["flag", "c"])),
+    );
+    assert_eq!(
+        tree.get_all_entries("flag"),
+        [&sise_tree!(["flag", "d"]), &sise_tree!(["flag", "b"])],
+    );
+    assert_eq!(tree.set_entry("host", alloc::vec![]), None);
+    assert_eq!(tree.as_list().unwrap().last(), Some(&sise_tree!(["host"])));
+
+    assert_eq!(tree.remove_entry("flag"), Some(sise_tree!(["flag", "d"])));
+    assert_eq!(tree.remove_entry("flag"), Some(sise_tree!(["flag", "b"])));
+    assert_eq!(tree.remove_entry("flag"), None);
+    assert_eq!(
+        tree,
+        sise_tree!([
+            "config",
+            ["name", "\"x\""],
+            ["port", "80"],
+            "flag",
+            [],
+            [["x"]],
+            ["host"],
+        ]),
+    );
+    assert_eq!(sise_tree!("flag").remove_entry("flag"), None);
+}
+
+#[test]
+#[should_panic(expected = "node is not a non-empty list")]
+fn test_set_entry_atom() {
+    sise_tree!("config").set_entry("port", alloc::vec![]);
+}
+
+#[test]
+#[should_panic(expected = "invalid atom")]
+fn test_set_entry_invalid_key() {
+    sise_tree!(["config"]).set_entry("a b", alloc::vec![]);
+}
+
+#[test]
+fn test_from_values() {
+    assert_eq!(TreeNode::from(-12i8), "-12");
+    assert_eq!(
+        TreeNode::from(u128::MAX),
+        "340282366920938463463374607431768211455"
+    );
+    assert_eq!(TreeNode::from(0usize), "0");
+    assert_eq!(TreeNode::from(true), "true");
+    assert_eq!(TreeNode::from(false), "false");
+    assert_eq!(TreeNode::from(Quoted("")), "\"\"");
+    assert_eq!(TreeNode::from(Quoted("a\\b\n")), "\"a\\\\b\\n\"");
+}
+
+#[test]
+fn test_macro_interpolation() {
+    let port = 80u16;
+    let name = String::from("x");
+    let child = sise_tree!(["c"]);
+    assert_eq!(
+        sise_tree!([
+            (name.as_str()),
+            (port),
+            (Quoted(&name)),
+            (child.clone()),
+            (false)
+        ]),
+        TreeNode::List(alloc::vec![
+            TreeNode::from("x"),
+            TreeNode::from("80"),
+            TreeNode::from("\"x\""),
+            child,
+            TreeNode::from("false"),
+        ]),
+    );
+    assert_eq!(sise_tree!(port), "80");
+}
+
+#[test]
+fn test_macro_splice() {
+    let empty: [&str; 0] = [];
+    let items = alloc::vec![1, 2, 3];
+    assert_eq!(sise_tree!([..empty]), sise_tree!([]));
+    assert_eq!(sise_tree!([..empty,]), sise_tree!([]));
+    assert_eq!(
+        sise_tree!(["a", ..items.iter().copied(), "b", ..empty, [..["x", "y"]]]),
+        sise_tree!(["a", "1", "2", "3", "b", ["x", "y"]]),
+    );
+    assert_eq!(
+        sise_tree!([..items.iter().map(|&i| sise_tree!([(i)])), ..items.clone(),]),
+        sise_tree!([["1"], ["2"], ["3"], "1", "2", "3"]),
+    );
+
+    // Many elements do not reach the recursion limit
+    macro_rules! long_list {
+        ($($atom:literal)*) => {
+            sise_tree!([..empty, $($atom, $atom, $atom, $atom, $atom, $atom, $atom, $atom,)* ..items])
+        };
+    }
+    let tree = long_list!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19);
+    let list = tree.as_list().unwrap();
+    assert_eq!(list.len(), 163);
+    assert_eq!(list[0], "0");
+    assert_eq!(list[159], "19");
+    assert_eq!(list[162], "3");
+}
@@ -28,3 +28,59 @@ fn test_check_atom() {
     assert!(!crate::check_atom("\"abcd"));
     assert!(!crate::check_atom("\"\\\""));
 }
+
+#[test]
+fn test_validate_atom() {
+    use crate::{validate_atom, AtomError};
+
+    assert_eq!(validate_atom("abcd"), Ok(()));
+    assert_eq!(validate_atom("a\"\\\"\"b"), Ok(()));
+    assert_eq!(validate_atom(""), Err(AtomError::Empty));
+    assert_eq!(
+        validate_atom("ab;"),
+        Err(AtomError::IllegalChr { pos: 2, chr: ';' }),
+    );
+    assert_eq!(
+        validate_atom("\u{e9}"),
+        Err(AtomError::IllegalChr {
+            pos: 0,
+            chr: '\u{e9}'
+        }),
+    );
+    assert_eq!(
+        validate_atom("\"\u{e9}\u{e8}"),
+        Err(AtomError::IllegalChrInString {
+            pos: 1,
+            chr: '\u{e9}'
+        }),
+    );
+    assert_eq!(
+        validate_atom("\"ab\\\n\""),
+        Err(AtomError::IllegalChrInString { pos: 4, chr: '\n' }),
+    );
+    assert_eq!(
+        validate_atom("x\"\\"),
+        Err(AtomError::DanglingEscape { pos: 2 }),
+    );
+    assert_eq!(
+        validate_atom("x\"\\\""),
+        Err(AtomError::UnfinishedString { pos: 1 }),
+    );
+}
+
+#[test]
+fn test_atom_error_display() {
+    use alloc::string::ToString as _;
+
+    use crate::AtomError;
+
+    assert_eq!(AtomError::Empty.to_string(), "empty atom");
+    assert_eq!(
+        AtomError::IllegalChrInString { pos: 3, chr: '\t' }.to_string(),
+        "illegal character '\\t' in string at byte 3",
+    );
+    assert_eq!(
+        AtomError::DanglingEscape { pos: 2 }.to_string(),
+        "dangling escape at byte 2",
+    );
+}
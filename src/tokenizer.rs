@@ -0,0 +1,348 @@
+use core::ops::Range;
+
+use crate::{is_atom_chr, is_atom_string_chr, ParseError, ParserOptions};
+
+/// The kind of a [`Token`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// `(`
+    LeftParen,
+    /// `)`
+    RightParen,
+    /// A segment of an atom made of atom characters outside a string.
+    ///
+    /// An atom can be made of several consecutive segments (e.g.,
+    /// `prefix"string"suffix`), there is no other token between them.
+    BareAtom,
+    /// A segment of an atom enclosed with `"`, including the quotes.
+    ///
+    /// When a segment is interrupted by an error token, the segment
+    /// that follows the error does not begin with `"`.
+    QuotedAtom,
+    /// A sequence of spaces and tabs.
+    Whitespace,
+    /// A line break: `\n`, `\r` or `\r\n`.
+    LineBreak,
+    /// A comment from `;` to the end of the line, excluding the line
+    /// break.
+    LineComment,
+    /// A (possibly nested) block comment from `#|` to `|#`. Only
+    /// produced when [`ParserOptions::block_comments`] is enabled.
+    BlockComment,
+    /// `#;`, which comments out the following node. Only produced when
+    /// [`ParserOptions::datum_comments`] is enabled.
+    DatumComment,
+    /// Invalid input. Tokenization continues after it.
+    Error(ParseError),
+}
+
+/// A token produced by [`Tokenizer`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// Byte range of the token in the input
+    pub span: Range<usize>,
+    /// Text of the token, equal to `&input[span]`
+    pub text: &'a str,
+}
+
+/// Splits a SISE file into tokens, including whitespace, line breaks
+/// and comments.
+///
+/// Unlike [`Parser`](crate::Parser), it does not stop at the first
+/// error. Invalid input is reported with [`TokenKind::Error`] tokens
+/// and the tokenizer keeps going. Concatenating the text of all the
+/// tokens gives back the whole input, which makes it suitable for
+/// syntax highlighters and formatters.
+///
+/// It does not check the structure (e.g., balanced parentheses).
+///
+/// # Example
+///
+/// ```
+/// use sise::TokenKind;
+///
+/// let data = "(atom \"str\"x) ; comment\n";
+/// let tokens: Vec<_> = sise::Tokenizer::new(data)
+///     .map(|token| (token.kind, token.text))
+///     .collect();
+/// assert_eq!(
+///     tokens,
+///     [
+///         (TokenKind::LeftParen, "("),
+///         (TokenKind::BareAtom, "atom"),
+///         (TokenKind::Whitespace, " "),
+///         (TokenKind::QuotedAtom, "\"str\""),
+///         (TokenKind::BareAtom, "x"),
+///         (TokenKind::RightParen, ")"),
+///         (TokenKind::Whitespace, " "),
+///         (TokenKind::LineComment, "; comment"),
+///         (TokenKind::LineBreak, "\n"),
+///     ],
+/// );
+/// ```
+pub struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+    options: ParserOptions,
+    mode: Mode,
+    // Whether the previous token was an atom segment, so `#` cannot
+    // begin a comment.
+    in_atom: bool,
+}
+
+#[derive(Copy, Clone)]
+enum Mode {
+    Normal,
+    LineComment,
+    BlockComment { depth: usize },
+    String,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
+        Self {
+            input,
+            pos: 0,
+            options,
+            mode: Mode::Normal,
+            in_atom: false,
+        }
+    }
+
+    #[inline]
+    fn rem_input(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn make_token(&mut self, kind: TokenKind, start: usize) -> Token<'a> {
+        self.in_atom = matches!(kind, TokenKind::BareAtom | TokenKind::QuotedAtom);
+        Token {
+            kind,
+            span: start..self.pos,
+            text: &self.input[start..self.pos],
+        }
+    }
+
+    /// Returns a token with the text consumed since `start`, or, if
+    /// nothing has been consumed, consumes `chr` and returns an error
+    /// token with `error`.
+    fn token_or_error(
+        &mut self,
+        kind: TokenKind,
+        start: usize,
+        chr: char,
+        error: ParseError,
+    ) -> Token<'a> {
+        if self.pos != start {
+            self.make_token(kind, start)
+        } else {
+            self.pos += chr.len_utf8();
+            self.make_token(TokenKind::Error(error), start)
+        }
+    }
+
+    fn lex_normal(&mut self) -> Token<'a> {
+        let start = self.pos;
+        let rem_input = self.rem_input();
+        let chr = rem_input.chars().next().unwrap();
+        match chr {
+            ' ' | '\t' => {
+                let len = rem_input
+                    .find(|chr| chr != ' ' && chr != '\t')
+                    .unwrap_or(rem_input.len());
+                self.pos += len;
+                self.make_token(TokenKind::Whitespace, start)
+            }
+            '\n' => {
+                self.pos += 1;
+                self.make_token(TokenKind::LineBreak, start)
+            }
+            '\r' => {
+                self.pos += if rem_input.starts_with("\r\n") { 2 } else { 1 };
+                self.make_token(TokenKind::LineBreak, start)
+            }
+            ';' => {
+                self.pos += 1;
+                self.mode = Mode::LineComment;
+                self.lex_line_comment(start)
+            }
+            '#' if !self.in_atom && self.options.block_comments && rem_input.starts_with("#|") => {
+                self.pos += 2;
+                self.mode = Mode::BlockComment { depth: 1 };
+                self.lex_block_comment(start, 1)
+            }
+            '#' if !self.in_atom && self.options.datum_comments && rem_input.starts_with("#;") => {
+                self.pos += 2;
+                self.make_token(TokenKind::DatumComment, start)
+            }
+            '(' => {
+                self.pos += 1;
+                self.make_token(TokenKind::LeftParen, start)
+            }
+            ')' => {
+                self.pos += 1;
+                self.make_token(TokenKind::RightParen, start)
+            }
+            '"' => {
+                self.pos += 1;
+                self.mode = Mode::String;
+                self.lex_string(start)
+            }
+            chr if is_atom_chr(chr) => {
+                let len = rem_input
+                    .find(|chr| !is_atom_chr(chr))
+                    .unwrap_or(rem_input.len());
+                self.pos += len;
+                self.make_token(TokenKind::BareAtom, start)
+            }
+            chr => {
+                self.pos += chr.len_utf8();
+                self.make_token(
+                    TokenKind::Error(ParseError::IllegalChr { pos: start, chr }),
+                    start,
+                )
+            }
+        }
+    }
+
+    fn lex_line_comment(&mut self, start: usize) -> Token<'a> {
+        loop {
+            let chr_pos = self.pos;
+            match self.rem_input().chars().next() {
+                None | Some('\n' | '\r') => {
+                    self.mode = Mode::Normal;
+                    if self.pos == start {
+                        // Resumed after an error just before the end
+                        // of the line.
+                        return self.lex_normal();
+                    }
+                    return self.make_token(TokenKind::LineComment, start);
+                }
+                Some(chr @ ('\t' | ' '..='~')) => self.pos += chr.len_utf8(),
+                Some(chr) => {
+                    return self.token_or_error(
+                        TokenKind::LineComment,
+                        start,
+                        chr,
+                        ParseError::IllegalChrInComment { pos: chr_pos, chr },
+                    );
+                }
+            }
+        }
+    }
+
+    fn lex_block_comment(&mut self, start: usize, mut depth: usize) -> Token<'a> {
+        loop {
+            let chr_pos = self.pos;
+            let rem_input = self.rem_input();
+            if rem_input.starts_with("|#") {
+                self.pos += 2;
+                depth -= 1;
+                if depth == 0 {
+                    self.mode = Mode::Normal;
+                    return self.make_token(TokenKind::BlockComment, start);
+                }
+            } else if rem_input.starts_with("#|") {
+                self.pos += 2;
+                depth += 1;
+            } else {
+                match rem_input.chars().next() {
+                    None => {
+                        self.mode = Mode::Normal;
+                        let error = ParseError::UnfinishedBlockComment { pos: chr_pos };
+                        return self.make_token(TokenKind::Error(error), start);
+                    }
+                    Some(chr @ ('\t' | '\n' | '\r' | ' '..='~')) => self.pos += chr.len_utf8(),
+                    Some(chr) => {
+                        self.mode = Mode::BlockComment { depth };
+                        return self.token_or_error(
+                            TokenKind::BlockComment,
+                            start,
+                            chr,
+                            ParseError::IllegalChrInComment { pos: chr_pos, chr },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn lex_string(&mut self, start: usize) -> Token<'a> {
+        loop {
+            let chr_pos = self.pos;
+            let mut chars = self.rem_input().chars();
+            match chars.next() {
+                None => {
+                    self.mode = Mode::Normal;
+                    let error = ParseError::UnfinishedString { pos: chr_pos };
+                    return self.make_token(TokenKind::Error(error), start);
+                }
+                Some('"') => {
+                    self.pos += 1;
+                    self.mode = Mode::Normal;
+                    return self.make_token(TokenKind::QuotedAtom, start);
+                }
+                Some('\\') => match chars.next() {
+                    None => {
+                        self.pos += 1;
+                    }
+                    Some(chr) if chr == '"' || chr == '\\' || is_atom_string_chr(chr) => {
+                        self.pos += 2;
+                    }
+                    Some(_) => {
+                        // Keep the backslash in the segment, the error
+                        // token will be produced for the next character.
+                        self.pos += 1;
+                        return self.make_token(TokenKind::QuotedAtom, start);
+                    }
+                },
+                Some(chr) if is_atom_string_chr(chr) => self.pos += 1,
+                Some(chr) => {
+                    return self.token_or_error(
+                        TokenKind::QuotedAtom,
+                        start,
+                        chr,
+                        ParseError::IllegalChrInString { pos: chr_pos, chr },
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.pos == self.input.len() {
+            return match self.mode {
+                Mode::Normal | Mode::LineComment => None,
+                Mode::BlockComment { .. } => {
+                    self.mode = Mode::Normal;
+                    let error = ParseError::UnfinishedBlockComment { pos: self.pos };
+                    Some(self.make_token(TokenKind::Error(error), self.pos))
+                }
+                Mode::String => {
+                    self.mode = Mode::Normal;
+                    let error = ParseError::UnfinishedString { pos: self.pos };
+                    Some(self.make_token(TokenKind::Error(error), self.pos))
+                }
+            };
+        }
+
+        let start = self.pos;
+        Some(match self.mode {
+            Mode::Normal => self.lex_normal(),
+            Mode::LineComment => self.lex_line_comment(start),
+            Mode::BlockComment { depth } => self.lex_block_comment(start, depth),
+            Mode::String => self.lex_string(start),
+        })
+    }
+}
+
+impl core::iter::FusedIterator for Tokenizer<'_> {}
@@ -1,6 +1,15 @@
-use alloc::string::String;
+use alloc::string::{String, ToString as _};
 use alloc::vec::Vec;
 
+use crate::canonical::CanonicalWriter;
+use crate::serialize_tree::try_serialize_tree;
+use crate::{
+    check_atom, encode_string, parse_tree, ParseError, Parser, Quoted, Serializer, SerializerStyle,
+};
+
+/// Line length used by `{:#}` when no width is given.
+pub(crate) const DEFAULT_LINE_WIDTH: usize = 80;
+
 /// A SISE tree node.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TreeNode {
@@ -11,7 +20,7 @@ pub enum TreeNode {
     /// Where `:atomchar:` is one of:
     ///
     /// > `!`, `#`, `$`, `%`, `&`, `*`, `+`, `-`, `.`, `/`, `:`, `<`, `=`,
-    /// `>`, `?`, `@`, `_`, `~`
+    /// > `>`, `?`, `@`, `_`, `~`
     ///
     /// And `:stringchar:` is any character between ASCII space and `~`,
     /// except `\` and `"`.
@@ -124,8 +133,232 @@ impl TreeNode {
         }
         Some(current_node)
     }
+
+    /// Returns the first element of the node if it is a list whose
+    /// first element is an atom.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// assert_eq!(sise_tree!(["port", "80"]).head(), Some("port"));
+    /// assert_eq!(sise_tree!([["a"], "b"]).head(), None);
+    /// assert_eq!(sise_tree!([]).head(), None);
+    /// assert_eq!(sise_tree!("port").head(), None);
+    /// ```
+    pub fn head(&self) -> Option<&str> {
+        match *self.as_list()?.first()? {
+            Self::Atom(ref atom) => Some(atom),
+            Self::List(_) => None,
+        }
+    }
+
+    /// Returns the elements of the node after the first one if it is a
+    /// non-empty list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// let tree = sise_tree!(["flags", "a", "b"]);
+    /// assert_eq!(tree.tail().unwrap(), [sise_tree!("a"), sise_tree!("b")]);
+    /// assert!(sise_tree!(["flags"]).tail().unwrap().is_empty());
+    /// assert!(sise_tree!([]).tail().is_none());
+    /// assert!(sise_tree!("flags").tail().is_none());
+    /// ```
+    pub fn tail(&self) -> Option<&[Self]> {
+        match *self {
+            Self::List(ref list) if !list.is_empty() => Some(&list[1..]),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the entries of a keyword list, with
+    /// their keys.
+    ///
+    /// The entries are the elements of [`tail`](Self::tail) that are
+    /// lists beginning with an atom, which is their key. Other elements
+    /// are skipped. The iterator is empty if the node is an atom.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// // (config (name "x") (port 80) flag (flags a b))
+    /// let tree = sise_tree!([
+    ///     "config",
+    ///     ["name", "\"x\""],
+    ///     ["port", "80"],
+    ///     "flag",
+    ///     ["flags", "a", "b"],
+    /// ]);
+    /// let keys: Vec<&str> = tree.entries().map(|(key, _)| key).collect();
+    /// assert_eq!(keys, ["name", "port", "flags"]);
+    /// ```
+    pub fn entries(&self) -> Entries<'_> {
+        Entries {
+            iter: self.tail().unwrap_or(&[]).iter(),
+        }
+    }
+
+    /// Returns the first entry (see [`entries`](Self::entries)) with
+    /// key `key`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// let tree = sise_tree!(["config", ["port", "80"], ["port", "81"]]);
+    /// let entry = tree.get_entry("port").unwrap();
+    /// assert_eq!(*entry, sise_tree!(["port", "80"]));
+    /// assert_eq!(entry.tail().unwrap(), ["80"]);
+    /// assert!(tree.get_entry("host").is_none());
+    /// ```
+    pub fn get_entry(&self, key: &str) -> Option<&Self> {
+        self.entries()
+            .find(|&(entry_key, _)| entry_key == key)
+            .map(|(_, entry)| entry)
+    }
+
+    /// Returns all the entries (see [`entries`](Self::entries)) with key
+    /// `key`, in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// let tree = sise_tree!(["config", ["flag", "a"], ["port", "80"], ["flag", "b"]]);
+    /// assert_eq!(
+    ///     tree.get_all_entries("flag"),
+    ///     [&sise_tree!(["flag", "a"]), &sise_tree!(["flag", "b"])],
+    /// );
+    /// assert!(tree.get_all_entries("host").is_empty());
+    /// ```
+    pub fn get_all_entries(&self, key: &str) -> Vec<&Self> {
+        self.entries()
+            .filter(|&(entry_key, _)| entry_key == key)
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+
+    /// Returns a mutable reference to the first entry (see
+    /// [`entries`](Self::entries)) with key `key`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// let mut tree = sise_tree!(["config", ["flags", "a"]]);
+    /// let entry = tree.get_entry_mut("flags").unwrap();
+    /// entry.as_mut_list().unwrap().push(sise::TreeNode::from("b"));
+    /// assert_eq!(tree, sise_tree!(["config", ["flags", "a", "b"]]));
+    /// ```
+    pub fn get_entry_mut(&mut self, key: &str) -> Option<&mut Self> {
+        let index = self.entry_index(key)?;
+        Some(&mut self.as_mut_list().unwrap()[index])
+    }
+
+    /// Sets the values of the first entry (see
+    /// [`entries`](Self::entries)) with key `key`, replacing it in
+    /// place and returning it. If there is no such entry, a new one is
+    /// appended to the list and `None` is returned. Other entries with
+    /// the same key are not modified.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is not a non-empty list or if `key` is not a
+    /// valid atom.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::{sise_tree, TreeNode};
+    ///
+    /// let mut tree = sise_tree!(["config", ["port", "80"]]);
+    /// let old = tree.set_entry("port", vec![TreeNode::from("8080")]);
+    /// assert_eq!(old, Some(sise_tree!(["port", "80"])));
+    /// let old = tree.set_entry("flags", vec![TreeNode::from("a"), TreeNode::from("b")]);
+    /// assert_eq!(old, None);
+    /// assert_eq!(
+    ///     tree,
+    ///     sise_tree!(["config", ["port", "8080"], ["flags", "a", "b"]]),
+    /// );
+    /// ```
+    pub fn set_entry(&mut self, key: &str, values: Vec<Self>) -> Option<Self> {
+        assert!(check_atom(key), "invalid atom {:?}", key);
+        let index = self.entry_index(key);
+        let list = match *self {
+            Self::List(ref mut list) if !list.is_empty() => list,
+            _ => panic!("node is not a non-empty list"),
+        };
+        let mut entry = Vec::with_capacity(values.len() + 1);
+        entry.push(Self::Atom(String::from(key)));
+        entry.extend(values);
+        let entry = Self::List(entry);
+        match index {
+            Some(index) => Some(core::mem::replace(&mut list[index], entry)),
+            None => {
+                list.push(entry);
+                None
+            }
+        }
+    }
+
+    /// Removes the first entry (see [`entries`](Self::entries)) with
+    /// key `key` and returns it. Other entries with the same key are
+    /// kept, so the removal can be repeated to remove all of them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sise::sise_tree;
+    ///
+    /// let mut tree = sise_tree!(["config", ["flag", "a"], ["port", "80"], ["flag", "b"]]);
+    /// assert_eq!(tree.remove_entry("flag"), Some(sise_tree!(["flag", "a"])));
+    /// assert_eq!(tree, sise_tree!(["config", ["port", "80"], ["flag", "b"]]));
+    /// assert_eq!(tree.remove_entry("host"), None);
+    /// ```
+    pub fn remove_entry(&mut self, key: &str) -> Option<Self> {
+        let index = self.entry_index(key)?;
+        Some(self.as_mut_list().unwrap().remove(index))
+    }
+
+    /// Returns the index in the list of the first entry with key `key`.
+    fn entry_index(&self, key: &str) -> Option<usize> {
+        self.tail()?
+            .iter()
+            .position(|entry| entry.head() == Some(key))
+            .map(|i| i + 1)
+    }
+}
+
+/// Iterator over the entries of a keyword list, returned by
+/// [`TreeNode::entries`].
+///
+/// Each item is a pair of the key and the whole entry, including the key.
+#[derive(Clone, Debug)]
+pub struct Entries<'a> {
+    iter: core::slice::Iter<'a, TreeNode>,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = (&'a str, &'a TreeNode);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .by_ref()
+            .find_map(|entry| entry.head().map(|key| (key, entry)))
+    }
 }
 
+impl core::iter::FusedIterator for Entries<'_> {}
+
 impl PartialEq<str> for TreeNode {
     fn eq(&self, other: &str) -> bool {
         match *self {
@@ -173,3 +406,183 @@ impl From<Vec<TreeNode>> for TreeNode {
         Self::List(list)
     }
 }
+
+impl<S: AsRef<str>> From<Quoted<S>> for TreeNode {
+    #[inline]
+    fn from(quoted: Quoted<S>) -> Self {
+        Self::Atom(encode_string(quoted.0.as_ref()))
+    }
+}
+
+/// Converts a boolean into the atom `true` or `false`.
+impl From<bool> for TreeNode {
+    #[inline]
+    fn from(value: bool) -> Self {
+        Self::Atom(String::from(if value { "true" } else { "false" }))
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            /// Converts an integer into a decimal atom.
+            impl From<$t> for TreeNode {
+                #[inline]
+                fn from(value: $t) -> Self {
+                    Self::Atom(value.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Formats the tree as SISE text.
+///
+/// `{}` writes it in a single line, in the canonical form described at
+/// [`canonicalize`](crate::canonicalize). `{:#}` writes it in multiple
+/// lines, where the width is the line length after which nodes are
+/// placed in a new line (by default 80, and with 1 each node except the
+/// first atom of a list goes in its own line) and the precision is the
+/// number of spaces of each indentation level (by default 2). There is
+/// no line break at the end.
+///
+/// `{}` also accepts a width, fill, alignment and precision, which are
+/// applied as they are to a string (e.g., `{:>20}`).
+///
+/// Formatting fails with [`core::fmt::Error`] when it reaches an atom
+/// that is not valid (see [`check_atom`]), since the output could not
+/// be parsed back into the same tree, so `to_string` panics in that
+/// case.
+///
+/// # Example
+///
+/// ```
+/// use std::fmt::Write as _;
+///
+/// use sise::sise_tree;
+///
+/// let tree = sise_tree!(["example", ["1", "2"], ["a", "b"]]);
+/// assert_eq!(format!("{}", tree), "(example (1 2) (a b))");
+/// assert_eq!(tree.to_string(), "(example (1 2) (a b))");
+/// assert_eq!(format!("{:#}", tree), "(example (1 2) (a b))");
+/// assert_eq!(
+///     format!("{:#1}", tree),
+///     "(example\n  (1\n    2\n  )\n  (a\n    b\n  )\n)",
+/// );
+/// assert_eq!(
+///     format!("{:#12.1}", tree),
+///     "(example (1 2)\n (a b)\n)",
+/// );
+///
+/// assert_eq!(format!("[{:>8}]", sise_tree!(["a", "b"])), "[   (a b)]");
+///
+/// let mut text = String::new();
+/// let result = write!(text, "{}", sise_tree!(["a b"]));
+/// assert_eq!(result, Err(std::fmt::Error));
+/// ```
+impl core::fmt::Display for TreeNode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if f.alternate() {
+            let indentation = " ".repeat(f.precision().unwrap_or(2));
+            let style = SerializerStyle {
+                line_break: "\n",
+                indentation: &indentation,
+            };
+            let mut result = String::new();
+            let mut serializer = Serializer::new(style, &mut result);
+            let width = f.width().unwrap_or(DEFAULT_LINE_WIDTH);
+            try_serialize_tree(&mut serializer, self, width).map_err(|_| core::fmt::Error)?;
+            serializer.finish(false);
+            f.write_str(&result)
+        } else if f.width().is_some() || f.precision().is_some() {
+            let mut result = String::new();
+            let mut writer = CanonicalWriter::new(|s: &str| result.push_str(s));
+            writer.try_node(self).map_err(|_| core::fmt::Error)?;
+            f.pad(&result)
+        } else {
+            let mut result = Ok(());
+            let mut writer = CanonicalWriter::new(|s: &str| {
+                if result.is_ok() {
+                    result = f.write_str(s);
+                }
+            });
+            writer.try_node(self).map_err(|_| core::fmt::Error)?;
+            result
+        }
+    }
+}
+
+/// Parses SISE text with the default [`ParserOptions`](crate::ParserOptions).
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree: sise::TreeNode = "(a (b c))".parse().unwrap();
+/// assert_eq!(tree, sise_tree!(["a", ["b", "c"]]));
+///
+/// let result = "(a b".parse::<sise::TreeNode>();
+/// assert_eq!(result, Err(sise::ParseError::UnexpectedEof { pos: 4 }));
+/// ```
+impl core::str::FromStr for TreeNode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(s);
+        let root_node = parse_tree(&mut parser)?;
+        parser.finish()?;
+        Ok(root_node)
+    }
+}
+
+/// Parses SISE text into a tree, with the default
+/// [`ParserOptions`](crate::ParserOptions).
+///
+/// It is a shorthand for [`Parser::new`], [`parse_tree`] and
+/// [`Parser::finish`].
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree = sise::from_str("(a ; comment\n (b c))").unwrap();
+/// assert_eq!(tree, sise_tree!(["a", ["b", "c"]]));
+/// ```
+#[inline]
+pub fn from_str(data: &str) -> Result<TreeNode, ParseError> {
+    data.parse()
+}
+
+/// Serializes a tree in multiple lines, as `format!("{:#}", node)`
+/// does (see the [`Display`](core::fmt::Display) implementation of
+/// [`TreeNode`]), placing nodes in a new line after 80 characters.
+///
+/// # Panics
+///
+/// Panics if the tree contains an invalid atom.
+///
+/// # Example
+///
+/// ```
+/// use sise::sise_tree;
+///
+/// let tree = sise_tree!(["a", ["b", "c"]]);
+/// assert_eq!(sise::to_string_pretty(&tree), "(a (b c))");
+///
+/// let tree = sise_tree!(["list", ..(0..30).map(|i| format!("item-{}", i))]);
+/// let text = sise::to_string_pretty(&tree);
+/// assert_eq!(text.lines().count(), 4);
+/// assert_eq!(sise::from_str(&text), Ok(tree));
+/// ```
+pub fn to_string_pretty(node: &TreeNode) -> String {
+    use core::fmt::Write as _;
+    let mut result = String::new();
+    if write!(result, "{:#}", node).is_err() {
+        panic!("invalid atom in tree");
+    }
+    result
+}
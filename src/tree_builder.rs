@@ -0,0 +1,141 @@
+use alloc::vec::Vec;
+
+use crate::TreeNode;
+
+/// Receives the nodes of a tree as a sequence of events and builds
+/// some output from them.
+///
+/// It is driven by [`parse_tree_with`](crate::parse_tree_with) and
+/// [`replay_into_builder`](crate::replay_into_builder), which makes it
+/// possible to build custom tree types directly from the parser.
+///
+/// `pos` is the byte offset of the atom, `(` or `)` in the input.
+///
+/// # Example
+///
+/// A tree that keeps the position of each node:
+///
+/// ```
+/// #[derive(Debug, PartialEq)]
+/// enum SpannedNode<'a> {
+///     Atom(&'a str, usize),
+///     List(Vec<SpannedNode<'a>>, usize),
+/// }
+///
+/// #[derive(Default)]
+/// struct SpannedBuilder<'a> {
+///     stack: Vec<(Vec<SpannedNode<'a>>, usize)>,
+///     root: Option<SpannedNode<'a>>,
+/// }
+///
+/// impl<'a> SpannedBuilder<'a> {
+///     fn push(&mut self, node: SpannedNode<'a>) {
+///         match self.stack.last_mut() {
+///             Some((list, _)) => list.push(node),
+///             None => self.root = Some(node),
+///         }
+///     }
+/// }
+///
+/// impl<'a> sise::TreeBuilder<'a> for SpannedBuilder<'a> {
+///     type Output = SpannedNode<'a>;
+///
+///     fn atom(&mut self, atom: &'a str, pos: usize) {
+///         self.push(SpannedNode::Atom(atom, pos));
+///     }
+///
+///     fn begin_list(&mut self, pos: usize) {
+///         self.stack.push((Vec::new(), pos));
+///     }
+///
+///     fn end_list(&mut self, _pos: usize) {
+///         let (list, pos) = self.stack.pop().unwrap();
+///         self.push(SpannedNode::List(list, pos));
+///     }
+///
+///     fn finish(self) -> SpannedNode<'a> {
+///         self.root.unwrap()
+///     }
+/// }
+///
+/// let data = "(a (b))";
+/// let mut parser = sise::Parser::new(data);
+/// let root_node = sise::parse_tree_with(&mut parser, SpannedBuilder::default()).unwrap();
+/// parser.finish().unwrap();
+/// assert_eq!(
+///     root_node,
+///     SpannedNode::List(
+///         vec![
+///             SpannedNode::Atom("a", 1),
+///             SpannedNode::List(vec![SpannedNode::Atom("b", 4)], 3),
+///         ],
+///         0,
+///     ),
+/// );
+/// ```
+pub trait TreeBuilder<'a> {
+    /// The result of building the tree.
+    type Output;
+
+    /// Adds an atom to the current list, or sets it as the root node.
+    fn atom(&mut self, atom: &'a str, pos: usize);
+
+    /// Begins a list, which becomes the current one.
+    fn begin_list(&mut self, pos: usize);
+
+    /// Ends the current list.
+    fn end_list(&mut self, pos: usize);
+
+    /// Called once the root node has been completed.
+    fn finish(self) -> Self::Output;
+}
+
+/// A [`TreeBuilder`] that builds a [`TreeNode`].
+///
+/// # Panics
+///
+/// Its methods panic if they are not called with a sequence that forms
+/// exactly one node.
+#[derive(Clone, Debug, Default)]
+pub struct TreeNodeBuilder {
+    stack: Vec<Vec<TreeNode>>,
+    root_node: Option<TreeNode>,
+}
+
+impl TreeNodeBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push_node(&mut self, node: TreeNode) {
+        assert!(self.root_node.is_none(), "more than one node");
+        if let Some(current) = self.stack.last_mut() {
+            current.push(node);
+        } else {
+            self.root_node = Some(node);
+        }
+    }
+}
+
+impl<'a> TreeBuilder<'a> for TreeNodeBuilder {
+    type Output = TreeNode;
+
+    fn atom(&mut self, atom: &'a str, _pos: usize) {
+        self.push_node(TreeNode::Atom(atom.into()));
+    }
+
+    fn begin_list(&mut self, _pos: usize) {
+        assert!(self.root_node.is_none(), "more than one node");
+        self.stack.push(Vec::new());
+    }
+
+    fn end_list(&mut self, _pos: usize) {
+        let list = self.stack.pop().expect("unexpected list end");
+        self.push_node(TreeNode::List(list));
+    }
+
+    fn finish(self) -> TreeNode {
+        self.root_node.expect("incomplete node")
+    }
+}
@@ -37,38 +37,115 @@ pub const fn is_atom_string_chr(chr: char) -> bool {
 
 /// Checks whether `atom` is a valid atom (i.e. matches the regular
 /// expression documented at `TreeNode::Atom`).
+///
+/// Use [`validate_atom`] to find out why an atom is not valid.
+#[inline]
 pub fn check_atom(atom: &str) -> bool {
+    validate_atom(atom).is_ok()
+}
+
+/// Represents the reason why a string is not a valid atom.
+///
+/// `pos` is a byte offset in the string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AtomError {
+    /// The string is empty
+    Empty,
+
+    /// There is an invalid character outside a string
+    IllegalChr { pos: usize, chr: char },
+
+    /// There is an invalid character inside a string (enclosed with `"`)
+    IllegalChrInString { pos: usize, chr: char },
+
+    /// There is a `\` at the end of the atom, inside a string
+    DanglingEscape { pos: usize },
+
+    /// The atom ends before finding the closing `"` of the string that
+    /// begins at `pos`
+    UnfinishedString { pos: usize },
+}
+
+impl core::fmt::Display for AtomError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            AtomError::Empty => f.write_str("empty atom"),
+            AtomError::IllegalChr { pos, chr } => {
+                write!(f, "illegal character {:?} at byte {}", chr, pos)
+            }
+            AtomError::IllegalChrInString { pos, chr } => {
+                write!(f, "illegal character {:?} in string at byte {}", chr, pos)
+            }
+            AtomError::DanglingEscape { pos } => write!(f, "dangling escape at byte {}", pos),
+            AtomError::UnfinishedString { pos } => {
+                write!(f, "unfinished string at byte {}", pos)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AtomError {}
+
+/// Checks whether `atom` is a valid atom (i.e. matches the regular
+/// expression documented at `TreeNode::Atom`), returning the first
+/// problem found otherwise.
+///
+/// # Example
+///
+/// ```
+/// use sise::AtomError;
+///
+/// assert_eq!(sise::validate_atom("abc\"d e\""), Ok(()));
+/// assert_eq!(sise::validate_atom(""), Err(AtomError::Empty));
+/// assert_eq!(
+///     sise::validate_atom("d e"),
+///     Err(AtomError::IllegalChr { pos: 1, chr: ' ' }),
+/// );
+/// assert_eq!(
+///     sise::validate_atom("\"a\u{e9}\""),
+///     Err(AtomError::IllegalChrInString { pos: 2, chr: '\u{e9}' }),
+/// );
+/// assert_eq!(
+///     sise::validate_atom("\"a\\"),
+///     Err(AtomError::DanglingEscape { pos: 2 }),
+/// );
+/// assert_eq!(
+///     sise::validate_atom("a\"b"),
+///     Err(AtomError::UnfinishedString { pos: 1 }),
+/// );
+/// ```
+pub fn validate_atom(atom: &str) -> Result<(), AtomError> {
     if atom.is_empty() {
-        // Empty atom
-        return false;
+        return Err(AtomError::Empty);
     }
 
-    let mut iter = atom.chars();
-    let mut in_string = false;
-    loop {
-        if !in_string {
-            match iter.next() {
-                None => return true,
-                Some('"') => in_string = true,
-                Some(chr) if is_atom_chr(chr) => {}
-                // Invalid character
-                Some(_) => return false,
+    let mut iter = atom.char_indices();
+    // Position of the `"` that begins the current string
+    let mut string_start = None;
+    while let Some((pos, chr)) = iter.next() {
+        if string_start.is_none() {
+            match chr {
+                '"' => string_start = Some(pos),
+                chr if is_atom_chr(chr) => {}
+                chr => return Err(AtomError::IllegalChr { pos, chr }),
             }
         } else {
-            match iter.next() {
-                // Unfinished string
-                None => return false,
-                Some('"') => in_string = false,
-                Some('\\') => match iter.next() {
-                    Some('"' | '\\') => {}
-                    Some(chr) if is_atom_string_chr(chr) => {}
-                    // Invalid character or unfinished string
-                    Some(_) | None => return false,
+            match chr {
+                '"' => string_start = None,
+                '\\' => match iter.next() {
+                    Some((_, '"' | '\\')) => {}
+                    Some((_, chr)) if is_atom_string_chr(chr) => {}
+                    Some((pos, chr)) => return Err(AtomError::IllegalChrInString { pos, chr }),
+                    None => return Err(AtomError::DanglingEscape { pos }),
                 },
-                Some(chr) if is_atom_string_chr(chr) => {}
-                // Invalid character
-                Some(_) => return false,
+                chr if is_atom_string_chr(chr) => {}
+                chr => return Err(AtomError::IllegalChrInString { pos, chr }),
             }
         }
     }
+    match string_start {
+        Some(pos) => Err(AtomError::UnfinishedString { pos }),
+        None => Ok(()),
+    }
 }